@@ -0,0 +1,65 @@
+//! Bakes directory locations from the build environment.
+//!
+//! Only runs when the `bake` feature is enabled; see `src/baked.rs`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Baked directories, named by their constant, kind, variable and default.
+const DIRS: [(&str, &str, &str, Option<&str>); 6] = [
+    ("BIN", "executable", "XDG_BIN_HOME", Some(".local/bin")),
+    ("CACHE", "cache", "XDG_CACHE_HOME", Some(".cache")),
+    ("CONFIG", "config", "XDG_CONFIG_HOME", Some(".config")),
+    ("DATA", "data", "XDG_DATA_HOME", Some(".local/share")),
+    ("RUNTIME", "runtime", "XDG_RUNTIME_DIR", None),
+    ("STATE", "state", "XDG_STATE_HOME", Some(".local/state")),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_BAKE").is_none() {
+        return;
+    }
+
+    let home = var("HOME");
+    let mut out = String::new();
+    out.push_str(&item("HOME", "user's home directory", home.as_deref()));
+    for (name, kind, var, default) in DIRS {
+        let path = self::var(var).or_else(|| {
+            let home = home.as_deref()?;
+            let default = default?;
+            Some(
+                default
+                    .split('/')
+                    .fold(PathBuf::from(home), |path, part| path.join(part))
+                    .to_str()?
+                    .to_string(),
+            )
+        });
+        let desc = format!("user's {kind} directory");
+        out.push_str(&item(name, &desc, path.as_deref()));
+    }
+
+    let dest = Path::new(&env::var_os("OUT_DIR").unwrap()).join("baked.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// Reads a variable from the build environment, if it is a valid absolute
+/// path.
+fn var(name: &str) -> Option<String> {
+    println!("cargo:rerun-if-env-changed={name}");
+    let value = env::var(name).ok().filter(|value| !value.is_empty())?;
+    if Path::new(&value).is_absolute() {
+        Some(value)
+    } else {
+        println!("cargo:warning=ignoring relative ${name}: {value}");
+        None
+    }
+}
+
+/// Renders a baked constant.
+fn item(name: &str, desc: &str, path: Option<&str>) -> String {
+    let value = path.map_or_else(|| String::from("None"), |path| format!("Some({path:?})"));
+    format!("/// Baked location of the {desc}.\npub const {name}: Option<&str> = {value};\n")
+}
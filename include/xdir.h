@@ -0,0 +1,139 @@
+/* Generated with cbindgen; do not edit by hand. */
+
+#ifndef XDIR_H
+#define XDIR_H
+
+#include <stddef.h>
+
+/**
+ * User's executable directory.
+ */
+#define XDIR_BIN 0
+
+/**
+ * User's cache directory.
+ */
+#define XDIR_CACHE 1
+
+/**
+ * User's config directory.
+ */
+#define XDIR_CONFIG 2
+
+/**
+ * User's data directory.
+ */
+#define XDIR_DATA 3
+
+/**
+ * User's runtime directory.
+ */
+#define XDIR_RUNTIME 4
+
+/**
+ * User's state directory.
+ */
+#define XDIR_STATE 5
+
+#ifdef __cplusplus
+extern "C" {
+#endif  // __cplusplus
+
+/**
+ * Writes the path to the user's executable directory into a buffer.
+ *
+ * See [`xdir_dir`] for details.
+ *
+ * # Safety
+ *
+ * `buf` must be null, or valid for writes of `len` bytes.
+ */
+size_t xdir_bin(char *buf, size_t len);
+
+/**
+ * Writes the path to the user's cache directory into a buffer.
+ *
+ * See [`xdir_dir`] for details.
+ *
+ * # Safety
+ *
+ * `buf` must be null, or valid for writes of `len` bytes.
+ */
+size_t xdir_cache(char *buf, size_t len);
+
+/**
+ * Writes the path to the user's config directory into a buffer.
+ *
+ * See [`xdir_dir`] for details.
+ *
+ * # Safety
+ *
+ * `buf` must be null, or valid for writes of `len` bytes.
+ */
+size_t xdir_config(char *buf, size_t len);
+
+/**
+ * Writes the path to the user's data directory into a buffer.
+ *
+ * See [`xdir_dir`] for details.
+ *
+ * # Safety
+ *
+ * `buf` must be null, or valid for writes of `len` bytes.
+ */
+size_t xdir_data(char *buf, size_t len);
+
+/**
+ * Writes the path to the user's runtime directory into a buffer.
+ *
+ * See [`xdir_dir`] for details.
+ *
+ * # Safety
+ *
+ * `buf` must be null, or valid for writes of `len` bytes.
+ */
+size_t xdir_runtime(char *buf, size_t len);
+
+/**
+ * Writes the path to the user's state directory into a buffer.
+ *
+ * See [`xdir_dir`] for details.
+ *
+ * # Safety
+ *
+ * `buf` must be null, or valid for writes of `len` bytes.
+ */
+size_t xdir_state(char *buf, size_t len);
+
+/**
+ * Writes the path to a directory into a buffer, returning its length.
+ *
+ * The directory is given by one of the `XDIR_*` constants. Returns zero if
+ * the directory is unknown or could not be found.
+ *
+ * # Safety
+ *
+ * `buf` must be null, or valid for writes of `len` bytes.
+ */
+size_t xdir_dir(int dir, char *buf, size_t len);
+
+/**
+ * Writes the path to an application's directory into a buffer, returning its
+ * length.
+ *
+ * The application is given by its name, as per [`App::new`], and the
+ * directory by one of the `XDIR_*` constants. Returns zero if the name is not
+ * valid UTF-8, or if the directory is unknown or could not be found.
+ *
+ * # Safety
+ *
+ * `app` must be a valid NUL-terminated string, and `buf` must be null, or
+ * valid for writes of `len` bytes.
+ */
+size_t xdir_app_dir(const char *app, int dir, char *buf, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XDIR_H */
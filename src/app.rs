@@ -0,0 +1,1326 @@
+use std::fmt::Write as _;
+use std::path::{Component, Path, PathBuf};
+#[cfg(feature = "io-helpers")]
+use std::time::SystemTime;
+use std::{fs, io};
+
+#[cfg(feature = "io-helpers")]
+use crate::atomic;
+use crate::fallback::{AppHook, Fallback};
+use crate::journal::Journal;
+#[cfg(feature = "owner")]
+use crate::owner;
+#[cfg(feature = "io-helpers")]
+use crate::time::DateTime;
+#[cfg(feature = "search-path")]
+use crate::Overlay;
+use crate::{
+    perms, readonly, scope, search, sys, Dir, DirSet, Dirs, Permissions, Resolved, Retry, Scope,
+    Source,
+};
+
+/// Directories of an application.
+///
+/// Each directory is scoped by the application's name, such that (for example)
+/// the config directory of `myapp` is `$XDG_CONFIG_HOME/myapp`.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::App;
+///
+/// let app = App::new("myapp");
+/// let config = app.config().map(|path| path.join("config.toml"));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct App {
+    name: String,
+    portable: Option<String>,
+    profile: Option<String>,
+    version: Option<u64>,
+    scope: Option<Scope>,
+    permissions: Permissions,
+    #[cfg(feature = "owner")]
+    verify_owner: bool,
+    extended: bool,
+    read_only: bool,
+    journal: Option<Journal>,
+    fallback: Option<AppHook>,
+    env_vars: bool,
+    vars: [Option<String>; 6],
+    retry: Retry,
+}
+
+impl App {
+    /// Constructs the directories of an application.
+    ///
+    /// The name is used as-is, without being [sanitized](sanitize_app_name);
+    /// use [`sanitized`](Self::sanitized) to normalize it first.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            portable: None,
+            profile: None,
+            version: None,
+            scope: None,
+            permissions: Permissions::new(),
+            #[cfg(feature = "owner")]
+            verify_owner: false,
+            extended: false,
+            read_only: false,
+            journal: None,
+            fallback: None,
+            env_vars: false,
+            vars: Default::default(),
+            retry: Retry::new(1),
+        }
+    }
+
+    /// Constructs the directories of an application, first
+    /// [sanitizing](sanitize_app_name) its name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::App;
+    ///
+    /// assert_eq!(App::sanitized("My App! v2").name(), "my-app-v2");
+    /// ```
+    #[must_use]
+    pub fn sanitized(name: &str) -> Self {
+        Self::new(sanitize_app_name(name))
+    }
+
+    /// Enables portable mode, detected by a marker file next to the running
+    /// executable (e.g. `portable.txt`).
+    ///
+    /// When the marker exists, the application's directories are resolved
+    /// within the executable's directory (e.g. `<exe-dir>/config`) rather than
+    /// the user's directories, and system directories are not searched. This
+    /// allows the application to be run self-contained from removable media or
+    /// network shares.
+    #[must_use]
+    pub fn portable(mut self, marker: impl Into<String>) -> Self {
+        self.portable = Some(marker.into());
+        self
+    }
+
+    /// Returns the directory of the running executable if portable mode is
+    /// enabled and its marker exists.
+    #[must_use]
+    pub fn portable_root(&self) -> Option<PathBuf> {
+        let marker = self.portable.as_ref()?;
+        let exe = std::env::current_exe().ok()?;
+        let root = exe.parent()?;
+        root.join(marker).is_file().then(|| root.to_path_buf())
+    }
+
+    /// Checks whether the application is running in portable mode.
+    #[must_use]
+    pub fn is_portable(&self) -> bool {
+        self.portable_root().is_some()
+    }
+
+    /// Scopes the application's directories by a profile.
+    ///
+    /// Profiled directories are placed at `<app>/<profile>`, allowing several
+    /// independent configurations of the application to coexist (e.g. `work`
+    /// and `personal`). System directories are shared between profiles.
+    #[must_use]
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Scopes the application's directories by a profile named in an
+    /// environment variable (e.g. `MYAPP_PROFILE`).
+    ///
+    /// The profile is left unchanged if the variable is unset or empty.
+    #[must_use]
+    pub fn profile_from_env(self, var: &str) -> Self {
+        match std::env::var(var) {
+            Ok(profile) if !profile.is_empty() => self.profile(profile),
+            _ => self,
+        }
+    }
+
+    /// Returns the application's profile, if any.
+    #[must_use]
+    pub fn profile_name(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Scopes the application's data and state directories by its major
+    /// version.
+    ///
+    /// Versioned directories are placed at `<app>/<major>` (or, if profiled, at
+    /// `<app>/<profile>/<major>`), such that each major version has its own
+    /// on-disk format. Data from earlier versions can be carried forward using
+    /// migrations (see `migrate::Migrations`).
+    #[must_use]
+    pub fn versioned(mut self, major: u64) -> Self {
+        self.version = Some(major);
+        self
+    }
+
+    /// Returns the application's major version, if it is versioned.
+    #[must_use]
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    /// Sets the scope of the application's directories.
+    ///
+    /// Machine-scoped directories are shared by the whole machine (e.g.
+    /// `/etc/<app>`, or `%ProgramData%\<app>\config` on Windows), as suits
+    /// system services. When created, they are only accessible to the system,
+    /// administrators and their owner on Windows. Profiles, versions and
+    /// [per-application variables](Self::env_vars) still apply, but the
+    /// override file does not.
+    ///
+    /// Unless set, the scope is [detected](Scope::detect), such that Windows
+    /// services use machine-wide directories rather than the profile of their
+    /// service account.
+    #[must_use]
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Checks whether the application's directories are machine-wide.
+    #[must_use]
+    pub fn is_machine(&self) -> bool {
+        self.scope.unwrap_or_else(Scope::detect) == Scope::Machine
+    }
+
+    /// Sets the permissions of the directories and files created for the
+    /// application.
+    ///
+    /// See [`Permissions`] for details.
+    #[must_use]
+    pub fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// Sets whether directories are [verified](owner::verify) to be owned by
+    /// the effective user when they are created.
+    ///
+    /// This is disabled by default.
+    #[cfg(feature = "owner")]
+    #[must_use]
+    pub fn verify_owner(mut self, verify: bool) -> Self {
+        self.verify_owner = verify;
+        self
+    }
+
+    /// Sets whether directories are returned in their extended-length form on
+    /// Windows (e.g. `\\?\C:\Users\me`).
+    ///
+    /// This allows files nested deeply within the application's directories to
+    /// exceed `MAX_PATH`. It is disabled by default, and has no effect on other
+    /// platforms.
+    #[must_use]
+    pub fn extended_paths(mut self, extended: bool) -> Self {
+        self.extended = extended;
+        self
+    }
+
+    /// Sets whether the application is in read-only mode.
+    ///
+    /// While enabled, the application's directories and files are never
+    /// created, written or removed, as if [read-only mode](crate::set_read_only)
+    /// were enabled globally. This is disabled by default.
+    #[must_use]
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Registers a hook, invoked whenever resolving the application's
+    /// directories rejects a variable, falling back to a default location.
+    ///
+    /// This takes precedence over the [global hook](crate::fallback::set_hook)
+    /// for the application's resolutions, such that libraries can surface
+    /// fallbacks through their own diagnostics. See [`fallback`](crate::fallback)
+    /// for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::App;
+    ///
+    /// let app = App::new("myapp").on_fallback(|fallback| eprintln!("myapp: {fallback}"));
+    /// let config = app.config();
+    /// ```
+    #[must_use]
+    pub fn on_fallback(mut self, hook: impl Fn(&Fallback) + Send + Sync + 'static) -> Self {
+        self.fallback = Some(AppHook::new(hook));
+        self
+    }
+
+    /// Retries creating the application's directories and writing its files
+    /// while they fail with [transient](crate::is_transient) errors.
+    ///
+    /// See [`Retry`] for details.
+    #[must_use]
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Records the removal and moving of the application's directories and
+    /// files into a journal, such that it can later be [undone](crate::undo).
+    ///
+    /// See [`journal`](crate::journal) for details.
+    #[cfg(feature = "journal")]
+    #[must_use]
+    pub fn journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Returns the application's permissions policy.
+    #[cfg(feature = "preflight")]
+    pub(crate) fn permissions_ref(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    /// Returns the application's journal, if any.
+    #[cfg(any(feature = "cache-gc", feature = "migrate"))]
+    pub(crate) fn journal_ref(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    /// Sets whether the application's directories may be relocated by
+    /// variables named after the application (e.g. `MYAPP_CONFIG_DIR`).
+    ///
+    /// Variables are named by the application's name in uppercase, with any
+    /// other characters than letters and digits replaced by `_`, followed by
+    /// the directory's kind (i.e. `CACHE`, `CONFIG`, `DATA`, `RUNTIME` or
+    /// `STATE`) and `_DIR`. When set to an absolute path, it is used in place
+    /// of the application's directory, taking precedence over the generic XDG
+    /// variables, the override file and portable mode. Profiles and versions
+    /// are still applied within it. This is disabled by default.
+    ///
+    /// Variables can also be registered explicitly with
+    /// [`env_var`](Self::env_var).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::{App, Dir};
+    ///
+    /// let app = App::new("my-app").env_vars(true);
+    /// assert_eq!(app.dir_var(Dir::State).as_deref(), Some("MY_APP_STATE_DIR"));
+    /// ```
+    #[must_use]
+    pub fn env_vars(mut self, enabled: bool) -> Self {
+        self.env_vars = enabled;
+        self
+    }
+
+    /// Registers the variable relocating one of the application's directories
+    /// (e.g. `MYAPP_HOME` for its data directory).
+    ///
+    /// This takes precedence over the name derived by
+    /// [`env_vars`](Self::env_vars), and applies even if it is disabled. As
+    /// executables are not scoped by application, [`Dir::Bin`] cannot be
+    /// relocated this way.
+    #[must_use]
+    pub fn env_var(mut self, dir: Dir, var: impl Into<String>) -> Self {
+        self.vars[dir as usize] = Some(var.into());
+        self
+    }
+
+    /// Returns the name of the variable relocating one of the application's
+    /// directories, if any.
+    #[must_use]
+    pub fn dir_var(&self, dir: Dir) -> Option<String> {
+        if dir == Dir::Bin {
+            return None;
+        }
+        if let Some(var) = &self.vars[dir as usize] {
+            return Some(var.clone());
+        }
+        self.env_vars.then(|| {
+            format!(
+                "{}_{}_DIR",
+                var_prefix(&self.name),
+                dir.to_string().to_uppercase()
+            )
+        })
+    }
+
+    /// Returns the location of one of the application's directories, if set by
+    /// its variable.
+    fn var_dir(&self, dir: Dir) -> Option<PathBuf> {
+        let value = std::env::var_os(self.dir_var(dir)?).filter(|value| !value.is_empty())?;
+        Some(crate::resolve::native(PathBuf::from(value))).filter(|path| path.is_absolute())
+    }
+
+    /// Returns the application's name.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path to one of the application's directories.
+    ///
+    /// As executables are not scoped by application, [`Dir::Bin`] yields the
+    /// user's executable directory (or, in [portable mode](Self::portable), the
+    /// executable's directory).
+    #[must_use]
+    pub fn dir(&self, dir: Dir) -> Option<PathBuf> {
+        match dir {
+            Dir::Bin => self.bin_with(|| self.base(dir)).map(Resolved::into_path),
+            Dir::Cache => self.cache(),
+            Dir::Config => self.config(),
+            Dir::Data => self.data(),
+            Dir::Runtime => self.runtime(),
+            Dir::State => self.state(),
+        }
+    }
+
+    /// Returns the path to one of the application's directories, along with
+    /// its source.
+    ///
+    /// Directories within the user's directories report the source of the
+    /// latter (e.g. [`Source::EnvVar`]), whereas those relocated for the
+    /// application report how (e.g. [`Source::AppVar`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use xdir::{App, Dir, Source};
+    ///
+    /// std::env::set_var("MYAPP_CACHE_DIR", "/scratch/myapp");
+    /// let app = App::new("myapp").env_vars(true);
+    /// let cache = app.dir_resolved(Dir::Cache).unwrap();
+    /// assert_eq!(cache.source(), &Source::AppVar("MYAPP_CACHE_DIR".into()));
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn dir_resolved(&self, dir: Dir) -> Option<Resolved> {
+        let version = matches!(dir, Dir::Data | Dir::State)
+            .then_some(self.version)
+            .flatten();
+        match dir {
+            Dir::Bin => self.bin_with(|| self.base(dir)),
+            _ => self.scoped_with(dir, version, || self.base(dir)),
+        }
+    }
+
+    /// Returns the path to one of the application's directories, within the
+    /// user's directories as [resolved in a single pass](crate::resolve_all).
+    ///
+    /// Operations on several of the application's directories use this, such
+    /// that the user's directories are only resolved once.
+    pub(crate) fn dir_in(&self, dirs: &Dirs, dir: Dir) -> Option<PathBuf> {
+        let base = || dirs.resolved(dir).cloned();
+        let path = match dir {
+            Dir::Bin => self.bin_with(base),
+            Dir::Data | Dir::State => self.scoped_with(dir, self.version, base),
+            Dir::Cache | Dir::Config | Dir::Runtime => self.scoped_with(dir, None, base),
+        };
+        path.map(Resolved::into_path)
+    }
+
+    /// Returns the path to one of the application's directories, creating it
+    /// if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be found or created, or if ownership
+    /// is verified and the directory is owned by another user.
+    pub fn create(&self, dir: Dir) -> io::Result<PathBuf> {
+        let path = self.dir(dir).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("could not find {dir} directory"),
+            )
+        })?;
+        self.create_dir_within(dir, Some(&path), &path)?;
+        Ok(path)
+    }
+
+    /// Creates each of the given directories of the application, returning
+    /// their paths.
+    ///
+    /// # Errors
+    ///
+    /// Errors if any directory could not be found or created, or if ownership
+    /// is verified and it is owned by another user.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xdir::{App, DirSet};
+    ///
+    /// let app = App::new("myapp");
+    /// app.ensure(DirSet::CONFIG | DirSet::STATE).unwrap();
+    /// ```
+    pub fn ensure(&self, dirs: DirSet) -> io::Result<Vec<PathBuf>> {
+        let base = self.resolve_all();
+        dirs.into_iter()
+            .map(|dir| {
+                let path = self.dir_in(&base, dir).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("could not find {dir} directory"),
+                    )
+                })?;
+                self.create_dir_within(dir, Some(&path), &path)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Removes each of the given directories of the application, along with
+    /// their contents.
+    ///
+    /// Directories which do not exist are skipped. Only the current version's
+    /// directories are removed, if the application is
+    /// [versioned](Self::versioned).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the executable directory is given, as it is not scoped by
+    /// application, or if any directory could not be removed.
+    pub fn purge(&self, dirs: DirSet) -> io::Result<()> {
+        if dirs.contains(Dir::Bin) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot purge the shared bin directory",
+            ));
+        }
+        self.check_writable()?;
+        let base = self.resolve_all();
+        for path in dirs.into_iter().filter_map(|dir| self.dir_in(&base, dir)) {
+            match fs::remove_dir_all(&path) {
+                Ok(()) => {
+                    if let Some(journal) = &self.journal {
+                        journal.removed(&path)?;
+                    }
+                }
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                Err(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates a [`tmpfiles.d(5)`] configuration creating the application's
+    /// directories within the given scope.
+    ///
+    /// Machine-scoped directories are owned by a system user and group named
+    /// after the application, and accessible only to them. User-scoped
+    /// directories (for use with `systemd-tmpfiles --user`) are private to the
+    /// user. Directories which could not be found are omitted.
+    ///
+    /// [`tmpfiles.d(5)`]: https://www.freedesktop.org/software/systemd/man/latest/tmpfiles.d.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use xdir::{App, Scope};
+    ///
+    /// let snippet = App::new("myapp").tmpfiles_snippet(Scope::Machine);
+    /// assert!(snippet.contains("d /var/lib/myapp 0750 myapp myapp -\n"));
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn tmpfiles_snippet(&self, scope: Scope) -> String {
+        let app = Self {
+            portable: None,
+            scope: Some(scope),
+            ..self.clone()
+        };
+        let (mode, owner) = match scope {
+            Scope::User => ("0700", "-"),
+            Scope::Machine => ("0750", app.name.as_str()),
+        };
+        let base = app.resolve_all();
+        let mut paths = Vec::new();
+        for dir in [Dir::Config, Dir::Data, Dir::State, Dir::Cache, Dir::Runtime] {
+            // Directories may coincide, such as machine-scoped data and state.
+            match app.dir_in(&base, dir) {
+                Some(path) if !paths.contains(&path) => paths.push(path),
+                _ => (),
+            }
+        }
+        let mut out = format!("# Directories of {}\n", app.name);
+        for path in paths {
+            let mut path = path.display().to_string();
+            if path.contains(char::is_whitespace) || path.contains('"') {
+                path = format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""));
+            }
+            let _ = writeln!(out, "d {path} {mode} {owner} {owner} -");
+        }
+        out
+    }
+
+    /// Returns the path to a file within one of the application's directories.
+    ///
+    /// The path is relative to the directory (e.g. `config.toml`), and may not
+    /// traverse outside of it.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be found, or if the path is not
+    /// relative to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::{App, Dir};
+    ///
+    /// let app = App::new("myapp");
+    /// # if xdir::config().is_some() {
+    /// assert_eq!(
+    ///     app.file(Dir::Config, "config.toml").unwrap(),
+    ///     app.config().unwrap().join("config.toml"),
+    /// );
+    /// # }
+    /// assert!(app.file(Dir::Config, "../other/config.toml").is_err());
+    /// ```
+    pub fn file(&self, dir: Dir, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let base = self.dir(dir).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("could not find {dir} directory"),
+            )
+        })?;
+        join(&base, path.as_ref())
+    }
+
+    /// Returns the path to a file within one of the application's directories,
+    /// creating its parent directories if they do not exist.
+    ///
+    /// See [`file`](Self::file) for details.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is invalid, if its parent directories could not be
+    /// created, or if ownership is verified and they are owned by another user.
+    pub fn create_parents(&self, dir: Dir, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = self.file(dir, path)?;
+        if let Some(parent) = path.parent() {
+            self.create_dir(dir, parent)?;
+        }
+        Ok(path)
+    }
+
+    /// Returns the path to a file within the application's cache directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is invalid. See [`file`](Self::file) for details.
+    pub fn cache_file(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        self.file(Dir::Cache, path)
+    }
+
+    /// Returns the path to a file within the application's config directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is invalid. See [`file`](Self::file) for details.
+    pub fn config_file(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        self.file(Dir::Config, path)
+    }
+
+    /// Returns the path to a file within the application's data directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is invalid. See [`file`](Self::file) for details.
+    pub fn data_file(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        self.file(Dir::Data, path)
+    }
+
+    /// Returns the path to a file within the application's state directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is invalid. See [`file`](Self::file) for details.
+    pub fn state_file(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        self.file(Dir::State, path)
+    }
+
+    /// Returns the path to the application's cache directory.
+    #[must_use]
+    pub fn cache(&self) -> Option<PathBuf> {
+        self.scoped(Dir::Cache, None)
+    }
+
+    /// Returns the path to the application's config directory.
+    #[must_use]
+    pub fn config(&self) -> Option<PathBuf> {
+        self.scoped(Dir::Config, None)
+    }
+
+    /// Returns the preference-ordered config search path of the application.
+    ///
+    /// This includes the application's config directory, followed by those
+    /// within the system config directories. The latter are omitted in
+    /// [portable mode](Self::portable).
+    #[must_use]
+    pub fn config_dirs(&self) -> Vec<PathBuf> {
+        let system = if self.is_portable() {
+            Vec::new()
+        } else {
+            crate::config_dirs()
+        };
+        self.config()
+            .into_iter()
+            .chain(system.into_iter().map(|path| path.join(&self.name)))
+            .collect()
+    }
+
+    /// Returns a read-only view merging the application's config search path.
+    ///
+    /// Files within the user's config directory take precedence over those of
+    /// the system config directories. See [`Overlay`] for details.
+    #[cfg(feature = "search-path")]
+    #[must_use]
+    pub fn config_overlay(&self) -> Overlay {
+        Overlay::new(self.config_dirs())
+    }
+
+    /// Installs a default config file, unless one already exists.
+    ///
+    /// The file is written into the application's config directory, but only if
+    /// no file of the same name exists anywhere within its [config search
+    /// path](Self::config_dirs). It is created atomically, such that an
+    /// existing file is never clobbered, even if several processes race to
+    /// seed it. Returns whether this call created the file.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `name` is not a relative path within the config directory, if
+    /// the config directory could not be found or created, or if the file could
+    /// not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xdir::App;
+    ///
+    /// let app = App::new("myapp");
+    /// app.seed_config("# Default configuration\n", "config.toml").unwrap();
+    /// ```
+    #[cfg(feature = "io-helpers")]
+    pub fn seed_config(
+        &self,
+        contents: impl AsRef<[u8]>,
+        name: impl AsRef<Path>,
+    ) -> io::Result<bool> {
+        let name = name.as_ref();
+        for dir in self.config_dirs() {
+            if join(&dir, name)?.exists() {
+                return Ok(false);
+            }
+        }
+        self.check_writable()?;
+        let path = join(&self.create(Dir::Config)?, name)?;
+        if let Some(parent) = path.parent() {
+            self.create_dir(Dir::Config, parent)?;
+        }
+        let mode = self.permissions.file_mode(Dir::Config);
+        self.retry
+            .run(|| atomic::create(&path, contents.as_ref(), mode))
+    }
+
+    /// Returns the path to the application's data directory.
+    ///
+    /// This is scoped by the application's major version, if it is
+    /// [versioned](Self::versioned).
+    #[must_use]
+    pub fn data(&self) -> Option<PathBuf> {
+        self.scoped(Dir::Data, self.version)
+    }
+
+    /// Returns the preference-ordered data search path of the application.
+    ///
+    /// This includes the application's data directory, followed by those
+    /// within the system data directories. The latter are omitted in
+    /// [portable mode](Self::portable).
+    #[must_use]
+    pub fn data_dirs(&self) -> Vec<PathBuf> {
+        let system = if self.is_portable() {
+            Vec::new()
+        } else {
+            crate::data_dirs()
+        };
+        self.data()
+            .into_iter()
+            .chain(system.into_iter().map(|path| path.join(&self.name)))
+            .collect()
+    }
+
+    /// Returns the path to the application's machine-wide data directory,
+    /// shared by all users.
+    ///
+    /// This is the application's data directory under the [machine
+    /// scope](Scope::Machine) (e.g. `/var/lib/<app>`, or
+    /// `%ProgramData%\<app>\data` on Windows), regardless of the application's
+    /// own scope. It suits large downloads (e.g. models or assets) which should
+    /// not be duplicated for each user. It is scoped by the application's major
+    /// version, if it is [versioned](Self::versioned), but not by its profile.
+    /// In [portable mode](Self::portable), there is no shared data directory.
+    ///
+    /// On Unix, the directory is usually writable only by administrators, such
+    /// that it is populated by an installer or a service.
+    #[must_use]
+    pub fn shared_data(&self) -> Option<PathBuf> {
+        if self.is_portable() {
+            return None;
+        }
+        let mut path = scope::machine(&self.name, Dir::Data)?;
+        if let Some(major) = self.version {
+            path.push(major.to_string());
+        }
+        if self.extended {
+            path = crate::windows::extended(&path);
+        }
+        Some(path)
+    }
+
+    /// Returns a read-only view merging the application's data, preferring
+    /// that of the user.
+    ///
+    /// Layers are the application's data directory, followed by its [shared
+    /// data directory](Self::shared_data), followed by those within the system
+    /// data directories. See [`Overlay`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xdir::App;
+    ///
+    /// let overlay = App::new("myapp").data_overlay();
+    /// let model = overlay.read("models/base.bin").unwrap();
+    /// ```
+    #[cfg(feature = "search-path")]
+    #[must_use]
+    pub fn data_overlay(&self) -> Overlay {
+        let mut dirs = self.data_dirs();
+        if let Some(shared) = self.shared_data().filter(|path| !dirs.contains(path)) {
+            let at = usize::from(self.data().is_some());
+            dirs.insert(at, shared);
+        }
+        Overlay::new(dirs)
+    }
+
+    /// Returns the path to the application's runtime directory.
+    #[must_use]
+    pub fn runtime(&self) -> Option<PathBuf> {
+        self.scoped(Dir::Runtime, None)
+    }
+
+    /// Returns the path to the application's state directory.
+    ///
+    /// This is scoped by the application's major version, if it is
+    /// [versioned](Self::versioned).
+    #[must_use]
+    pub fn state(&self) -> Option<PathBuf> {
+        self.scoped(Dir::State, self.version)
+    }
+
+    /// Returns the path to the application's plugins directory.
+    ///
+    /// This is where user-installed plugins should be placed.
+    #[must_use]
+    pub fn plugins(&self) -> Option<PathBuf> {
+        self.data().map(|path| path.join("plugins"))
+    }
+
+    /// Lists the application's installed plugins, in order of preference.
+    ///
+    /// Plugins are searched for across the application's [data search
+    /// path](Self::data_dirs), such that a plugin installed by the user
+    /// shadows any system-installed plugin of the same name.
+    #[must_use]
+    pub fn list_plugins(&self) -> Vec<PathBuf> {
+        let dirs = self
+            .data_dirs()
+            .into_iter()
+            .map(|path| path.join("plugins"))
+            .collect::<Vec<_>>();
+        search::list(&dirs, true)
+    }
+
+    /// Returns the path to the application's logs directory.
+    ///
+    /// As per the XDG Base Directory Specification, logs are considered state
+    /// data and are therefore placed within the state directory.
+    #[must_use]
+    pub fn logs(&self) -> Option<PathBuf> {
+        self.state().map(|path| path.join("logs"))
+    }
+
+    /// Returns the path to the application's logs directory, creating it if
+    /// it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the logs directory could not be found or created, or if
+    /// ownership is verified and the directory is owned by another user.
+    #[cfg(feature = "io-helpers")]
+    pub fn create_logs(&self) -> io::Result<PathBuf> {
+        let path = self.logs().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "could not find state directory")
+        })?;
+        self.create_dir(Dir::State, &path)?;
+        Ok(path)
+    }
+
+    /// Returns the path to a new log file, named by its prefix and the current
+    /// time.
+    ///
+    /// Files are named `<prefix>-<timestamp>.log`, where the timestamp is the
+    /// UTC time in ISO 8601 basic format (e.g. `20240421T135900Z`). Log files
+    /// with the same prefix thus sort chronologically, making it simple to
+    /// rotate out the oldest.
+    #[cfg(feature = "io-helpers")]
+    #[must_use]
+    pub fn log_file(&self, prefix: &str) -> Option<PathBuf> {
+        let time = DateTime::utc(SystemTime::now()).basic();
+        self.logs()
+            .map(|path| path.join(format!("{prefix}-{time}.log")))
+    }
+
+    /// Returns the path to the application's initialization marker.
+    ///
+    /// The marker is a file within the state directory, containing the version
+    /// of the application which initialized it.
+    #[cfg(feature = "io-helpers")]
+    #[must_use]
+    pub fn marker(&self) -> Option<PathBuf> {
+        self.state().map(|path| path.join("initialized"))
+    }
+
+    /// Checks whether this is the first run of the application.
+    ///
+    /// This is the case if the application has not been [marked as
+    /// initialized](Self::mark_initialized), and none of its directories exist
+    /// yet. The latter allows installations which predate the marker to be
+    /// recognized.
+    #[cfg(feature = "io-helpers")]
+    #[must_use]
+    pub fn first_run(&self) -> bool {
+        let base = self.resolve_all();
+        self.marker().is_none_or(|path| !path.exists())
+            && [Dir::Cache, Dir::Config, Dir::Data, Dir::State]
+                .into_iter()
+                .filter_map(|dir| self.dir_in(&base, dir))
+                .all(|path| !path.exists())
+    }
+
+    /// Marks the application as initialized by the given version.
+    ///
+    /// The marker is created atomically, such that only a single caller will
+    /// succeed even if several processes race to initialize the application.
+    /// Returns whether this call created the marker.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the state directory could not be found, or if the marker
+    /// could not be written.
+    #[cfg(feature = "io-helpers")]
+    pub fn mark_initialized(&self, version: &str) -> io::Result<bool> {
+        let path = self.marker().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "could not find state directory")
+        })?;
+        self.check_writable()?;
+        if let Some(parent) = path.parent() {
+            self.create_dir(Dir::State, parent)?;
+        }
+        let mode = self.permissions.file_mode(Dir::State);
+        self.retry
+            .run(|| atomic::create(&path, format!("{version}\n"), mode))
+    }
+
+    /// Returns the version which initialized the application, if any.
+    #[cfg(feature = "io-helpers")]
+    #[must_use]
+    pub fn initialized_version(&self) -> Option<String> {
+        let text = fs::read_to_string(self.marker()?).ok()?;
+        Some(text.trim().to_string())
+    }
+
+    /// Returns the path to a directory scoped by the application's name (or
+    /// variable, portable root, or machine) and profile, and optionally a
+    /// version.
+    pub(crate) fn scoped(&self, dir: Dir, version: Option<u64>) -> Option<PathBuf> {
+        self.scoped_with(dir, version, || self.base(dir))
+            .map(Resolved::into_path)
+    }
+
+    /// Resolves one of the user's directories, reporting fallbacks to the
+    /// application's hook (if any).
+    fn base(&self, dir: Dir) -> Option<Resolved> {
+        self.resolving(|| dir.resolve())
+    }
+
+    /// Resolves every directory of the user in a single pass, reporting
+    /// fallbacks to the application's hook (if any).
+    pub(crate) fn resolve_all(&self) -> Dirs {
+        self.resolving(crate::resolve_all)
+    }
+
+    /// Runs a resolution, reporting fallbacks to the application's hook (if
+    /// any).
+    fn resolving<T>(&self, f: impl FnOnce() -> T) -> T {
+        match &self.fallback {
+            Some(hook) => hook.scope(f),
+            None => f(),
+        }
+    }
+
+    /// Returns a scoped directory, as located within the user's directories.
+    #[cfg(feature = "migrate")]
+    pub(crate) fn scoped_in(&self, dirs: &Dirs, dir: Dir, version: Option<u64>) -> Option<PathBuf> {
+        self.scoped_with(dir, version, || dirs.resolved(dir).cloned())
+            .map(Resolved::into_path)
+    }
+
+    /// Returns the application's executable directory along with its source,
+    /// using the given lookup of the user's executable directory, recording it
+    /// if located for the application (see `record`).
+    fn bin_with(&self, base: impl FnOnce() -> Option<Resolved>) -> Option<Resolved> {
+        let Some(root) = self.portable_root() else {
+            return base();
+        };
+        let resolved = Resolved::new(root, Source::Portable);
+        #[cfg(feature = "record")]
+        crate::record::app(&self.name, Dir::Bin, Some(&resolved));
+        Some(resolved)
+    }
+
+    /// Returns a scoped directory along with its source, using the given
+    /// lookup of the user's corresponding directory, recording it (see
+    /// `record`).
+    fn scoped_with(
+        &self,
+        dir: Dir,
+        version: Option<u64>,
+        base: impl FnOnce() -> Option<Resolved>,
+    ) -> Option<Resolved> {
+        let resolved = self.locate(dir, version, base);
+        #[cfg(feature = "record")]
+        crate::record::app(&self.name, dir, resolved.as_ref());
+        resolved
+    }
+
+    /// Locates a scoped directory along with its source.
+    fn locate(
+        &self,
+        dir: Dir,
+        version: Option<u64>,
+        base: impl FnOnce() -> Option<Resolved>,
+    ) -> Option<Resolved> {
+        let (mut path, source) = if let Some(path) = self.var_dir(dir) {
+            (path, Source::AppVar(self.dir_var(dir)?))
+        } else {
+            match self.portable_root() {
+                Some(root) => (root.join(dir.to_string()), Source::Portable),
+                None if self.is_machine() => (scope::machine(&self.name, dir)?, Source::Machine),
+                None => {
+                    if let Some(path) = self.overridden(dir) {
+                        (path, Source::Override)
+                    } else {
+                        let base = base()?;
+                        (base.path().join(&self.name), base.source().clone())
+                    }
+                }
+            }
+        };
+        if let Some(profile) = &self.profile {
+            path.push(profile);
+        }
+        if let Some(major) = version {
+            path.push(major.to_string());
+        }
+        if self.extended {
+            path = crate::windows::extended(&path);
+        }
+        Some(Resolved::new(path, source))
+    }
+
+    #[cfg(feature = "overrides")]
+    fn overridden(&self, dir: Dir) -> Option<PathBuf> {
+        crate::overrides::app(&self.name, dir)
+    }
+
+    #[cfg(not(feature = "overrides"))]
+    #[allow(clippy::unused_self)]
+    fn overridden(&self, _: Dir) -> Option<PathBuf> {
+        None
+    }
+
+    /// Recursively creates a directory within one of the application's
+    /// directories, applying its policies.
+    pub(crate) fn create_dir(&self, dir: Dir, path: &Path) -> io::Result<()> {
+        self.create_dir_within(dir, self.dir(dir).as_deref(), path)
+    }
+
+    /// Recursively creates a directory within one of the application's
+    /// directories (given by its root), applying its policies.
+    pub(crate) fn create_dir_within(
+        &self,
+        dir: Dir,
+        root: Option<&Path>,
+        path: &Path,
+    ) -> io::Result<()> {
+        if !path.is_dir() {
+            self.check_writable()?;
+        }
+        // Only directories the application owns are subject to its policy.
+        let created = path
+            .ancestors()
+            .take_while(|path| !path.as_os_str().is_empty() && !path.exists())
+            .filter(|path| root.is_none_or(|root| path.starts_with(root)))
+            .collect::<Vec<_>>();
+        self.retry
+            .run(|| {
+                if self.is_machine() {
+                    sys::create_restricted_dir_all(path)?;
+                }
+                #[cfg(feature = "owner")]
+                if self.verify_owner {
+                    return owner::create_dir_all(path);
+                }
+                sys::create_dir_all(path)
+            })
+            .inspect_err(|_| crate::telemetry::create_failed(dir))?;
+        if let Some(mode) = self.permissions.dir_mode(dir) {
+            created
+                .into_iter()
+                .rev()
+                .try_for_each(|path| perms::apply(path, mode))?;
+        }
+        Ok(())
+    }
+
+    /// Fails if the application (or the crate) is in read-only mode.
+    pub(crate) fn check_writable(&self) -> io::Result<()> {
+        if self.read_only {
+            return Err(readonly::error());
+        }
+        readonly::check()
+    }
+
+    /// Writes a file within one of the application's directories atomically,
+    /// returning its path.
+    ///
+    /// Its parent directories are created if they do not exist. The file is
+    /// created with the mode given by the application's
+    /// [permissions](Self::permissions), if any.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is invalid, if its parent directories could not be
+    /// created, or if the file could not be written.
+    #[cfg(feature = "io-helpers")]
+    pub fn write_file(
+        &self,
+        dir: Dir,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> io::Result<PathBuf> {
+        self.check_writable()?;
+        let path = self.create_parents(dir, path)?;
+        let mode = self.permissions.file_mode(dir);
+        self.retry
+            .run(|| atomic::write_mode(&path, contents.as_ref(), mode))?;
+        Ok(path)
+    }
+}
+
+/// Normalizes an application's name for use as a directory name.
+///
+/// Names are lowercased, and each run of whitespace or punctuation is replaced
+/// by a single hyphen, except for `.` and `_`, which are kept. Leading and
+/// trailing separators are removed, as are leading and trailing dots. Names
+/// reserved by Windows (e.g. `con` or `lpt1`), which are reserved by their stem
+/// before the first dot, have their stem suffixed by an underscore on every
+/// platform, such that names are identical everywhere. Names without any other
+/// characters become `app`.
+///
+/// Tools that create directories on behalf of an application should use this
+/// to agree with [`App::sanitized`]. Note that [`App::new`] uses names as-is.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(xdir::sanitize_app_name("My App! v2"), "my-app-v2");
+/// assert_eq!(xdir::sanitize_app_name("  foo_bar.rs  "), "foo_bar.rs");
+/// assert_eq!(xdir::sanitize_app_name("CON"), "con_");
+/// assert_eq!(xdir::sanitize_app_name("nul.tar.gz"), "nul_.tar.gz");
+/// assert_eq!(xdir::sanitize_app_name("!!!"), "app");
+/// ```
+#[must_use]
+pub fn sanitize_app_name(name: &str) -> String {
+    const RESERVED: [&str; 4] = ["con", "prn", "aux", "nul"];
+
+    let mut out = String::with_capacity(name.len());
+    let mut sep = false;
+    for ch in name.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() || matches!(ch, '.' | '_') {
+            if sep && !out.is_empty() {
+                out.push('-');
+            }
+            sep = false;
+            out.push(ch);
+        } else {
+            sep = true;
+        }
+    }
+    // Leading dots would hide the directory, and trailing dots are stripped by
+    // Windows.
+    let out = out.trim_matches('.');
+    if out.is_empty() {
+        return String::from("app");
+    }
+    // Windows reserves device names, even when followed by an extension.
+    let (stem, ext) = out.split_at(out.find('.').unwrap_or(out.len()));
+    let device = matches!(
+        stem.as_bytes(),
+        [b'c', b'o', b'm', b'1'..=b'9'] | [b'l', b'p', b't', b'1'..=b'9']
+    );
+    if RESERVED.contains(&stem) || device {
+        format!("{stem}_{ext}")
+    } else {
+        out.to_string()
+    }
+}
+
+/// Returns the prefix of the variables named after an application.
+///
+/// This is the application's name in uppercase, with any other characters than
+/// letters and digits replaced by `_`.
+pub(crate) fn var_prefix(name: &str) -> String {
+    name.chars()
+        .map(|char| {
+            if char.is_ascii_alphanumeric() {
+                char.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Joins a relative path onto a directory, refusing any which would traverse
+/// outside of it.
+pub(crate) fn join(base: &Path, path: &Path) -> io::Result<PathBuf> {
+    if path.as_os_str().is_empty()
+        || !path
+            .components()
+            .all(|part| matches!(part, Component::Normal(_)))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid path: {}", path.display()),
+        ));
+    }
+    Ok(base.join(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_app_name_normalizes() {
+        assert_eq!(sanitize_app_name("My App! v2"), "my-app-v2");
+        assert_eq!(sanitize_app_name("  foo_bar.rs  "), "foo_bar.rs");
+        assert_eq!(sanitize_app_name("a -- b"), "a-b");
+        assert_eq!(sanitize_app_name("...hidden..."), "hidden");
+        assert_eq!(sanitize_app_name("!!!"), "app");
+        assert_eq!(sanitize_app_name(""), "app");
+    }
+
+    #[test]
+    fn sanitize_app_name_avoids_devices() {
+        let devices = ["con", "prn", "aux", "nul"]
+            .map(String::from)
+            .into_iter()
+            .chain((1..=9).flat_map(|n| [format!("com{n}"), format!("lpt{n}")]));
+        for device in devices {
+            let upper = device.to_uppercase();
+            assert_eq!(sanitize_app_name(&device), format!("{device}_"));
+            assert_eq!(sanitize_app_name(&upper), format!("{device}_"));
+            assert_eq!(
+                sanitize_app_name(&format!("{upper}.txt")),
+                format!("{device}_.txt")
+            );
+            assert_eq!(
+                sanitize_app_name(&format!("{device}.tar.gz")),
+                format!("{device}_.tar.gz")
+            );
+        }
+    }
+
+    #[test]
+    fn sanitize_app_name_keeps_non_devices() {
+        for name in [
+            "com",
+            "com0",
+            "com10",
+            "lpt",
+            "console",
+            "nully",
+            "con_",
+            "auxiliary.txt",
+        ] {
+            assert_eq!(sanitize_app_name(name), name);
+        }
+    }
+
+    #[test]
+    fn dir_in_matches_dir() {
+        let base = crate::resolve_all();
+        let apps = [
+            App::new("myapp"),
+            App::new("myapp").versioned(2).profile("work"),
+            App::new("myapp").scope(Scope::Machine),
+        ];
+        for app in apps {
+            for dir in Dir::ALL {
+                assert_eq!(app.dir_in(&base, dir), app.dir(dir), "{dir}");
+                let resolved = app.dir_resolved(dir).map(Resolved::into_path);
+                assert_eq!(resolved, app.dir(dir), "{dir}");
+            }
+        }
+    }
+
+    #[test]
+    fn dir_resolved_reports_machine() {
+        let app = App::new("myapp").scope(Scope::Machine);
+        let resolved = app.dir_resolved(Dir::Config).unwrap();
+        assert_eq!(resolved.source(), &Source::Machine);
+    }
+
+    #[cfg(feature = "io-helpers")]
+    #[test]
+    fn seed_config_rejects_traversal() {
+        let app = App::new("myapp");
+        for name in ["../escape.toml", "/tmp/escape.toml", ""] {
+            let err = app.seed_config("", name).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{name}");
+        }
+    }
+
+    #[test]
+    fn new_keeps_name() {
+        assert_eq!(App::new("My App").name(), "My App");
+        assert_eq!(App::sanitized("My App").name(), "my-app");
+    }
+}
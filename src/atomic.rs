@@ -0,0 +1,291 @@
+//! Atomic file operations.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{readonly, sys};
+
+/// Writes a file atomically, creating its leading directories.
+///
+/// Contents are first written to a temporary file alongside the destination,
+/// which is then renamed into place. Readers will therefore only ever observe
+/// either the previous or the new contents.
+#[cfg(any(
+    feature = "backup",
+    feature = "cache",
+    feature = "desktop",
+    feature = "install",
+    feature = "mime",
+    feature = "shell",
+    feature = "systemd",
+    feature = "toml"
+))]
+pub(crate) fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    replace(path, contents.as_ref(), false, None)
+}
+
+/// Writes a file atomically, optionally with the given mode.
+///
+/// The mode is applied to the temporary file, such that the file never
+/// appears with any other permissions.
+#[cfg(feature = "io-helpers")]
+pub(crate) fn write_mode(
+    path: &Path,
+    contents: impl AsRef<[u8]>,
+    mode: Option<u32>,
+) -> io::Result<()> {
+    replace(path, contents.as_ref(), false, mode)
+}
+
+/// Writes a file atomically, such that only its owner can ever access it.
+///
+/// As the temporary file is created with private permissions, its contents are
+/// never exposed, even before being renamed into place.
+#[cfg(any(feature = "backup", feature = "io-helpers"))]
+pub(crate) fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    replace(path, contents.as_ref(), true, None)
+}
+
+/// Writes a file atomically, optionally with private permissions or a mode.
+fn replace(path: &Path, contents: &[u8], private: bool, mode: Option<u32>) -> io::Result<()> {
+    readonly::check()?;
+    let temp = temp(path)?;
+    let result = (|| {
+        let mut file = create_temp(&temp, private, mode)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Creates a file atomically (optionally with the given mode), unless it
+/// already exists.
+///
+/// The file is only ever observed with its complete contents, except on
+/// filesystems without hard links (e.g. FAT), where it is instead written in
+/// place, and may therefore briefly be observed partially written. Returns
+/// whether the file was created by this call, such that concurrent callers can
+/// agree on a single winner.
+#[cfg(feature = "io-helpers")]
+pub(crate) fn create(
+    path: &Path,
+    contents: impl AsRef<[u8]>,
+    mode: Option<u32>,
+) -> io::Result<bool> {
+    if path.exists() {
+        return Ok(false);
+    }
+    readonly::check()?;
+    let temp = temp(path)?;
+    let result = (|| {
+        let mut file = create_temp(&temp, false, mode)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        // Unlike renaming, linking fails if the destination exists.
+        match fs::hard_link(&temp, path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            // Without hard links, the file is created in place, which is not
+            // atomic.
+            Err(_) => match create_temp(path, false, mode) {
+                Ok(mut file) => {
+                    file.write_all(contents.as_ref())?;
+                    file.sync_all()?;
+                    Ok(true)
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+                Err(err) => Err(err),
+            },
+        }
+    })();
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+/// Creates a new file, with private permissions or the given mode.
+///
+/// Permissions are applied before anything is written, such that the file's
+/// contents are never exposed with any other permissions.
+fn create_temp(path: &Path, private: bool, mode: Option<u32>) -> io::Result<File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        let Some(mode) = mode else {
+            return if private {
+                sys::create_private(path)
+            } else {
+                File::create_new(path)
+            };
+        };
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(path)?;
+        // The mode is masked by the umask on creation, so is applied exactly.
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    {
+        // Modes granting no access to others are mapped to private files.
+        #[allow(clippy::verbose_bit_mask)]
+        if private || mode.is_some_and(|mode| mode & 0o077 == 0) {
+            sys::create_private(path)
+        } else {
+            File::create_new(path)
+        }
+    }
+}
+
+/// Copies a file atomically, creating the destination's leading directories.
+///
+/// Permissions of the source file are copied along with its contents.
+#[cfg(feature = "install")]
+pub(crate) fn copy(src: &Path, dst: &Path) -> io::Result<()> {
+    readonly::check()?;
+    let temp = temp(dst)?;
+    let result = fs::copy(src, &temp).and_then(|_| fs::rename(&temp, dst));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Returns a unique temporary path alongside the destination, creating its
+/// leading directories.
+fn temp(path: &Path) -> io::Result<PathBuf> {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid file path: {}", path.display()),
+        ));
+    };
+    sys::create_dir_all(parent)?;
+    let mut temp = std::ffi::OsString::from(".");
+    temp.push(name);
+    temp.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    Ok(parent.join(temp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// Returns the names of the entries of a directory.
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Returns the permission bits of a file.
+    #[cfg(unix)]
+    fn mode(path: &Path) -> u32 {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[cfg(any(
+        feature = "backup",
+        feature = "cache",
+        feature = "desktop",
+        feature = "install",
+        feature = "mime",
+        feature = "shell",
+        feature = "systemd",
+        feature = "toml"
+    ))]
+    #[test]
+    fn write_replaces() {
+        let tmp = TempDir::new();
+        let path = tmp.join("a/b/file");
+        write(&path, "old").unwrap();
+        write(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(entries(&tmp.join("a/b")), ["file"]);
+    }
+
+    #[cfg(feature = "io-helpers")]
+    #[test]
+    fn create_keeps_existing() {
+        let tmp = TempDir::new();
+        let path = tmp.join("file");
+        assert!(create(&path, "first", None).unwrap());
+        assert!(!create(&path, "second", None).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert_eq!(entries(&tmp), ["file"]);
+    }
+
+    #[cfg(feature = "io-helpers")]
+    #[test]
+    fn create_has_single_winner() {
+        let tmp = TempDir::new();
+        let path = tmp.join("file");
+        let wins = std::thread::scope(|scope| {
+            let handles = (0..8)
+                .map(|n| {
+                    let path = &path;
+                    scope.spawn(move || create(path, n.to_string(), None).unwrap())
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(|&won| won)
+                .count()
+        });
+        assert_eq!(wins, 1);
+        assert_eq!(entries(&tmp), ["file"]);
+    }
+
+    #[cfg(all(unix, any(feature = "backup", feature = "io-helpers")))]
+    #[test]
+    fn write_applies_mode() {
+        let tmp = TempDir::new();
+        let path = tmp.join("file");
+        write_private(&path, "secret").unwrap();
+        assert_eq!(mode(&path), 0o600);
+        #[cfg(feature = "io-helpers")]
+        {
+            write_mode(&path, "shared", Some(0o644)).unwrap();
+            assert_eq!(mode(&path), 0o644);
+            // Modes are applied exactly, regardless of the umask.
+            write_mode(&path, "open", Some(0o666)).unwrap();
+            assert_eq!(mode(&path), 0o666);
+            assert!(create(&tmp.join("other"), "", Some(0o640)).unwrap());
+            assert_eq!(mode(&tmp.join("other")), 0o640);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_temp_is_never_exposed() {
+        let tmp = TempDir::new();
+        let path = tmp.join("file");
+        let file = create_temp(&path, false, Some(0o600)).unwrap();
+        // The empty file already has its final mode, before being written.
+        assert_eq!(mode(&path), 0o600);
+        drop(file);
+        assert!(create_temp(&path, false, None).is_err());
+    }
+}
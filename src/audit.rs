@@ -0,0 +1,135 @@
+//! Security audit of directories.
+//!
+//! Applications which store sensitive data, or which execute code found within
+//! their directories, may [audit] the user's directories at startup to detect
+//! unsafe configurations.
+//!
+//! # Examples
+//!
+//! ```
+//! for finding in xdir::audit() {
+//!     eprintln!("warning: {finding}");
+//! }
+//! ```
+
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{home, Dir, DirSet};
+
+/// A problem detected within a directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Finding {
+    /// Kind of directory.
+    pub dir: Dir,
+    /// Path to the directory.
+    pub path: PathBuf,
+    /// Detected problem.
+    pub issue: Issue,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} directory `{}` ", self.dir, self.path.display())?;
+        match &self.issue {
+            Issue::Writable { mode } => {
+                write!(f, "is writable by other users (mode {mode:04o})")
+            }
+            Issue::Runtime { mode } => {
+                write!(f, "is accessible by other users (mode {mode:04o})")
+            }
+            Issue::Owner { uid } => write!(f, "is owned by another user (uid {uid})"),
+            Issue::Symlink { target } => {
+                write!(f, "links outside the home (`{}`)", target.display())
+            }
+        }
+    }
+}
+
+/// Kinds of problems.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Issue {
+    /// Config or state directory is group- or world-writable.
+    Writable {
+        /// Permission bits of the directory.
+        mode: u32,
+    },
+    /// Runtime directory does not have mode `0700`.
+    Runtime {
+        /// Permission bits of the directory.
+        mode: u32,
+    },
+    /// Directory is not owned by the effective user.
+    Owner {
+        /// User ID of the directory's owner.
+        uid: u32,
+    },
+    /// Directory is a symbolic link to outside the home directory.
+    Symlink {
+        /// Resolved target of the link.
+        target: PathBuf,
+    },
+}
+
+/// Audits all existing directories, returning any problems found.
+///
+/// Directories which do not exist are skipped.
+#[must_use]
+pub fn audit() -> Vec<Finding> {
+    audit_dirs(DirSet::ALL)
+}
+
+/// Audits the given directories, returning any problems found.
+///
+/// See [`audit`](fn@audit) for details.
+#[must_use]
+pub fn audit_dirs(dirs: DirSet) -> Vec<Finding> {
+    let home = home().and_then(|path| fs::canonicalize(path).ok());
+    let mut findings = Vec::new();
+    for dir in dirs {
+        let Some(path) = dir.path() else {
+            continue;
+        };
+        let Ok(link) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        let mut report = |issue| {
+            findings.push(Finding {
+                dir,
+                path: path.clone(),
+                issue,
+            });
+        };
+        if link.is_symlink() && dir != Dir::Runtime {
+            if let (Some(home), Ok(target)) = (&home, fs::canonicalize(&path)) {
+                if !target.starts_with(home) {
+                    report(Issue::Symlink { target });
+                }
+            }
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let Ok(meta) = fs::metadata(&path) else {
+                continue;
+            };
+            let mode = meta.mode() & 0o7777;
+            match dir {
+                Dir::Config | Dir::State if mode & 0o022 != 0 => {
+                    report(Issue::Writable { mode });
+                }
+                Dir::Runtime if mode & 0o777 != 0o700 => {
+                    report(Issue::Runtime { mode });
+                }
+                _ => (),
+            }
+            if meta.uid() != crate::sys::euid() {
+                report(Issue::Owner { uid: meta.uid() });
+            }
+        }
+    }
+    findings
+}
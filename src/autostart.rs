@@ -0,0 +1,70 @@
+//! Autostart of applications.
+//!
+//! Applications are started automatically by the desktop at login when a
+//! [desktop entry](Entry) is present in an autostart directory, as per the
+//! [Desktop Application Autostart Specification][spec]. Entries in the user's
+//! [autostart directory](crate::config::autostart) take precedence over those
+//! of the system with the same name.
+//!
+//! [spec]: https://specifications.freedesktop.org/autostart-spec/latest/
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::{autostart, search};
+use crate::desktop::Entry;
+
+/// Enables autostart of an application, returning the path of its entry.
+///
+/// # Errors
+///
+/// Errors if the entry or its identifier are invalid, if the autostart
+/// directory could not be found, or if the file could not be written.
+pub fn enable(id: &str, entry: &Entry) -> io::Result<PathBuf> {
+    entry.clone().hidden(false).write(&dir()?, id)
+}
+
+/// Disables autostart of an application.
+///
+/// If an entry is also installed by the system, it is hidden by a user entry
+/// with `Hidden=true`. Otherwise, the user's entry is removed.
+///
+/// # Errors
+///
+/// Errors if the autostart directory could not be found, or if the entry could
+/// not be written or removed.
+pub fn disable(id: &str) -> io::Result<()> {
+    let dir = dir()?;
+    let system = search("autostart")
+        .into_iter()
+        .skip(1)
+        .any(|path| path.join(format!("{id}.desktop")).is_file());
+    if system {
+        Entry::new(id, ["true"]).hidden(true).write(&dir, id)?;
+        return Ok(());
+    }
+    crate::readonly::check()?;
+    match fs::remove_file(dir.join(format!("{id}.desktop"))) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+/// Checks whether autostart of an application is enabled.
+///
+/// This is the case if the most preferred entry exists and is not hidden.
+#[must_use]
+pub fn is_enabled(id: &str) -> bool {
+    search("autostart")
+        .into_iter()
+        .map(|path| path.join(format!("{id}.desktop")))
+        .find(|path| path.is_file())
+        .and_then(|path| fs::read_to_string(path).ok())
+        .is_some_and(|entry| !entry.lines().any(|line| line.trim() == "Hidden=true"))
+}
+
+fn dir() -> io::Result<PathBuf> {
+    autostart()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find config directory"))
+}
@@ -0,0 +1,351 @@
+//! Backup and restore of applications.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::tar::{self, Header, Kind};
+use crate::{atomic, App, Dir, DirSet, Dirs};
+
+/// Directories which are backed up by default.
+const DEFAULT: DirSet = DirSet::CONFIG.union(DirSet::STATE);
+
+impl App {
+    /// Backs up the application's config and state into a tar archive.
+    ///
+    /// Entries are named relative to the user's home directory, following the
+    /// default layout of each directory (e.g. `.config/myapp/config.toml`),
+    /// such that archives can be restored regardless of where the directories
+    /// are located. Cache and runtime directories are never backed up, as
+    /// their contents are not worth preserving.
+    ///
+    /// # Errors
+    ///
+    /// Errors if any directory could not be read, or if the archive could not
+    /// be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use xdir::App;
+    ///
+    /// let app = App::new("myapp");
+    /// app.backup(File::create("settings.tar").unwrap()).unwrap();
+    /// app.restore(File::open("settings.tar").unwrap()).unwrap();
+    /// ```
+    pub fn backup(&self, writer: impl Write) -> io::Result<()> {
+        self.backup_dirs(writer, DEFAULT)
+    }
+
+    /// Backs up the given directories of the application into a tar archive.
+    ///
+    /// This allows the data directory to be included alongside config and
+    /// state. See [`backup`](Self::backup) for details.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a cache, executable or runtime directory is given, if any
+    /// directory could not be read, or if the archive could not be written.
+    pub fn backup_dirs(&self, writer: impl Write, dirs: DirSet) -> io::Result<()> {
+        self.backup_in(&self.resolve_all(), writer, dirs)
+    }
+
+    /// Backs up the given directories of the application, as located within
+    /// the user's directories, into a tar archive.
+    fn backup_in(&self, base: &Dirs, writer: impl Write, dirs: DirSet) -> io::Result<()> {
+        let mut archive = tar::Writer::new(writer);
+        for dir in dirs {
+            let (src, name) = self.archived(base, dir)?;
+            if src.is_dir() {
+                append(&mut archive, &src, &name)?;
+            }
+        }
+        archive.finish().map(drop)
+    }
+
+    /// Restores the application's directories from a tar archive, returning
+    /// the paths of the restored files.
+    ///
+    /// Only entries within the application's config, data and state
+    /// directories are restored, into wherever those are currently located.
+    /// Existing files are replaced.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the archive is invalid or contains any other entries, or if a
+    /// file could not be written.
+    pub fn restore(&self, reader: impl Read) -> io::Result<Vec<PathBuf>> {
+        self.restore_in(&self.resolve_all(), reader)
+    }
+
+    /// Restores the application's directories, as located within the user's
+    /// directories, from a tar archive.
+    fn restore_in(&self, base: &Dirs, reader: impl Read) -> io::Result<Vec<PathBuf>> {
+        let targets = [Dir::Config, Dir::Data, Dir::State]
+            .into_iter()
+            .map(|dir| self.archived(base, dir).map(|(dst, name)| (dir, dst, name)))
+            .collect::<io::Result<Vec<_>>>()?;
+        self.check_writable()?;
+        let mut archive = tar::Reader::new(reader);
+        let mut restored = Vec::new();
+        while let Some(header) = archive.next_entry()? {
+            if header.kind == Kind::Other {
+                continue;
+            }
+            let entry = entry_path(&header.path)?;
+            if !entry
+                .components()
+                .all(|part| matches!(part, Component::Normal(_)))
+            {
+                return Err(invalid(format!(
+                    "invalid archive entry: {}",
+                    entry.display()
+                )));
+            }
+            let (dir, root, path) = targets
+                .iter()
+                .find_map(|(dir, dst, name)| {
+                    Some((*dir, dst, dst.join(entry.strip_prefix(name).ok()?)))
+                })
+                .ok_or_else(|| invalid(format!("unexpected archive entry: {}", entry.display())))?;
+            if header.kind == Kind::Dir {
+                self.create_dir_within(dir, Some(root), &path)?;
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                self.create_dir_within(dir, Some(root), parent)?;
+            }
+            let contents = archive.contents(&header)?;
+            if header.mode & 0o077 != 0 {
+                atomic::write(&path, contents)?;
+            } else {
+                // Private files must never be exposed, even briefly.
+                atomic::write_private(&path, contents)?;
+            }
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+
+                fs::set_permissions(&path, fs::Permissions::from_mode(header.mode & 0o777))?;
+            }
+            restored.push(path);
+        }
+        Ok(restored)
+    }
+
+    /// Returns the location of an archived directory, along with its name
+    /// within the archive.
+    fn archived(&self, base: &Dirs, dir: Dir) -> io::Result<(PathBuf, PathBuf)> {
+        if matches!(dir, Dir::Bin | Dir::Cache | Dir::Runtime) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot back up {dir} directory"),
+            ));
+        }
+        let path = self.dir_in(base, dir).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("could not find {dir} directory"),
+            )
+        })?;
+        let mut name = dir
+            .default()
+            .into_iter()
+            .flat_map(|suffix| suffix.split('/'))
+            .collect::<PathBuf>();
+        name.push(self.name());
+        if let Some(profile) = self.profile_name() {
+            name.push(profile);
+        }
+        if let Some(major) = self
+            .version()
+            .filter(|_| matches!(dir, Dir::Data | Dir::State))
+        {
+            name.push(major.to_string());
+        }
+        Ok((path, name))
+    }
+}
+
+/// Appends a directory to an archive, recursively.
+fn append<W: Write>(archive: &mut tar::Writer<W>, src: &Path, name: &Path) -> io::Result<()> {
+    let meta = fs::metadata(src)?;
+    let header = |kind, size| -> io::Result<Header> {
+        Ok(Header {
+            path: archive_path(name)?,
+            kind,
+            mode: mode(&meta),
+            size,
+            mtime: meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |time| time.as_secs()),
+        })
+    };
+    if meta.is_file() {
+        return archive.append(&header(Kind::File, meta.len())?, File::open(src)?);
+    }
+    archive.append(&header(Kind::Dir, 0)?, io::empty())?;
+    let mut entries = fs::read_dir(src)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+    for entry in entries {
+        let path = entry.path();
+        // Symlinked directories are not followed, to avoid cycles.
+        if entry.file_type()?.is_symlink() && path.is_dir() {
+            continue;
+        }
+        if path.is_file() || path.is_dir() {
+            append(archive, &path, &name.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of an entry within an archive.
+///
+/// Names are stored as raw bytes, such that those which are not valid UTF-8
+/// are preserved.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn archive_path(name: &Path) -> io::Result<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Ok(name.as_os_str().as_bytes().to_vec())
+}
+
+/// Returns the name of an entry within an archive.
+///
+/// Names are stored as UTF-8, separated by `/`.
+#[cfg(not(unix))]
+fn archive_path(name: &Path) -> io::Result<Vec<u8>> {
+    let name = name
+        .to_str()
+        .ok_or_else(|| invalid(format!("invalid path: {}", name.display())))?;
+    Ok(name.replace('\\', "/").into_bytes())
+}
+
+/// Returns the path of an entry within an archive.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn entry_path(name: &[u8]) -> io::Result<PathBuf> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    Ok(PathBuf::from(OsStr::from_bytes(name)))
+}
+
+/// Returns the path of an entry within an archive.
+#[cfg(not(unix))]
+fn entry_path(name: &[u8]) -> io::Result<PathBuf> {
+    std::str::from_utf8(name)
+        .map(|name| name.split('/').collect())
+        .map_err(|_| {
+            invalid(format!(
+                "invalid archive entry: {}",
+                String::from_utf8_lossy(name)
+            ))
+        })
+}
+
+#[cfg(unix)]
+fn mode(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    meta.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn mode(meta: &fs::Metadata) -> u32 {
+    if meta.is_dir() {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn round_trips() {
+        let app = App::new("xdir-test-backup");
+        let (src, dst) = (TempDir::new(), TempDir::new());
+        let config = Path::new(".config").join(app.name());
+        let long = "nested/".repeat(20);
+        sys::create_dir_all(&src.join(&config).join(&long)).unwrap();
+        sys::create_dir_all(&src.join(&config).join("empty")).unwrap();
+        fs::write(src.join(&config).join("config.toml"), "key = 1").unwrap();
+        fs::write(src.join(&config).join(&long).join("file.txt"), "long").unwrap();
+
+        let mut data = Vec::new();
+        app.backup_in(&Dirs::at(&src), &mut data, DEFAULT).unwrap();
+        let restored = app.restore_in(&Dirs::at(&dst), &data[..]).unwrap();
+        let config = dst.join(&config);
+        assert_eq!(
+            restored,
+            [
+                config.join("config.toml"),
+                config.join(&long).join("file.txt"),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(config.join("config.toml")).unwrap(),
+            "key = 1"
+        );
+        assert_eq!(
+            fs::read_to_string(config.join(&long).join("file.txt")).unwrap(),
+            "long"
+        );
+        assert!(config.join("empty").is_dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn round_trips_binary_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let app = App::new("xdir-test-backup");
+        let (src, dst) = (TempDir::new(), TempDir::new());
+        let config = Path::new(".config").join(app.name());
+        let name = OsStr::from_bytes(b"caf\xe9.toml");
+        sys::create_dir_all(&src.join(&config)).unwrap();
+        fs::write(src.join(&config).join(name), "key = 1").unwrap();
+
+        let mut data = Vec::new();
+        app.backup_in(&Dirs::at(&src), &mut data, DEFAULT).unwrap();
+        let restored = app.restore_in(&Dirs::at(&dst), &data[..]).unwrap();
+        assert_eq!(restored, [dst.join(&config).join(name)]);
+        assert_eq!(fs::read(dst.join(&config).join(name)).unwrap(), b"key = 1");
+    }
+
+    #[test]
+    fn rejects_escaping_entries() {
+        let app = App::new("xdir-test-backup");
+        let dst = TempDir::new();
+        let mut archive = tar::Writer::new(Vec::new());
+        let header = Header {
+            path: b".config/xdir-test-backup/../../escaped".to_vec(),
+            kind: Kind::File,
+            mode: 0o644,
+            size: 0,
+            mtime: 0,
+        };
+        archive.append(&header, io::empty()).unwrap();
+        let data = archive.finish().unwrap();
+        let err = app.restore_in(&Dirs::at(&dst), &data[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(!dst.join("escaped").exists());
+    }
+}
@@ -0,0 +1,51 @@
+//! Directory locations baked at build time.
+//!
+//! Firmware and appliance images often run without a meaningful environment,
+//! or must not consult it at all. With the `bake` feature enabled, the build
+//! script resolves each directory from the environment in which the crate is
+//! compiled, following the same rules as at runtime, and bakes the result into
+//! `const` paths. As such, the build environment should describe the target
+//! rather than the build machine:
+//!
+//! ```sh
+//! HOME=/home/device XDG_RUNTIME_DIR=/run/device cargo build --features bake
+//! ```
+//!
+//! Directories which could not be resolved while building are [`None`].
+//! Changing any of the variables rebuilds the crate.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::Path;
+//!
+//! const CONFIG: Option<&str> = xdir::baked::CONFIG;
+//!
+//! let config = CONFIG.map(Path::new);
+//! ```
+
+use std::path::Path;
+
+use crate::Dir;
+
+include!(concat!(env!("OUT_DIR"), "/baked.rs"));
+
+/// Returns the baked location of one of the user's directories.
+#[must_use]
+pub fn get(dir: Dir) -> Option<&'static Path> {
+    match dir {
+        Dir::Bin => BIN,
+        Dir::Cache => CACHE,
+        Dir::Config => CONFIG,
+        Dir::Data => DATA,
+        Dir::Runtime => RUNTIME,
+        Dir::State => STATE,
+    }
+    .map(Path::new)
+}
+
+/// Returns the baked location of the user's home directory.
+#[must_use]
+pub fn home() -> Option<&'static Path> {
+    HOME.map(Path::new)
+}
@@ -0,0 +1,783 @@
+//! Cached values.
+//!
+//! The most common use of the cache directory is to store the result of an
+//! expensive computation, recomputing it once stale. Entries are stored within
+//! the application's cache directory, named by a digest of their key.
+//!
+//! Caches holding many entries (e.g. build artifacts) should instead use a
+//! content-addressed [`Store`], which spreads its entries across
+//! subdirectories. Either way, caches can be kept in check at startup by
+//! `maybe_prune` (with the `cache-gc` feature).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! let index = xdir::cache::get_or_insert_with("myapp", "index", Duration::from_secs(3600), || {
+//!     b"expensive".to_vec()
+//! })
+//! .unwrap();
+//! ```
+
+use std::fmt::Write as _;
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "cache-gc")]
+use std::time::UNIX_EPOCH;
+use std::time::{Duration, SystemTime};
+
+use crate::journal::Journal;
+#[cfg(feature = "cache-gc")]
+use crate::telemetry;
+use crate::{atomic, md5, readonly, sys, App};
+
+/// A digest of keys, used to name the entries of a [`Store`].
+///
+/// Digests must be stable across releases, as they name persistent files. As
+/// such, [`std::hash::Hasher`] implementations (whose output may change) are
+/// unsuitable. Any function from keys to bytes can be used as a digest.
+pub trait Digest {
+    /// Computes the digest of a key.
+    fn digest(&self, key: &[u8]) -> Vec<u8>;
+}
+
+impl<F: Fn(&[u8]) -> Vec<u8>> Digest for F {
+    fn digest(&self, key: &[u8]) -> Vec<u8> {
+        self(key)
+    }
+}
+
+/// The MD5 digest, used by default.
+///
+/// MD5 is not collision-resistant against adversarial keys; should keys be
+/// untrusted, use a cryptographic digest instead.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Md5;
+
+impl Digest for Md5 {
+    fn digest(&self, key: &[u8]) -> Vec<u8> {
+        md5::digest(key).to_vec()
+    }
+}
+
+/// A content-addressed store of cached entries.
+///
+/// Each entry is named by the hex-encoded [digest](Digest) of its key, and
+/// placed in a subdirectory named by the digest's first byte (e.g.
+/// `ab/cdef...`), such that no single directory grows too large. Entries are
+/// written atomically, so concurrent writers never observe partial entries.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xdir::cache::Store;
+///
+/// let store = Store::for_app("mytool", "artifacts").unwrap();
+/// store.insert(b"main.o", b"...").unwrap();
+/// assert!(store.contains(b"main.o"));
+/// // Keep at most 256 MiB of entries.
+/// # #[cfg(feature = "cache-gc")]
+/// # {
+/// use xdir::cache::Policy;
+///
+/// store.prune(&Policy::new().max_size(256 << 20)).unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Store<D = Md5> {
+    root: PathBuf,
+    digest: D,
+    journal: Option<Journal>,
+}
+
+impl Store {
+    /// Constructs a store at the given root.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            digest: Md5,
+            journal: None,
+        }
+    }
+
+    /// Constructs a named store within an application's cache directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the cache directory could not be found, or if the name is not
+    /// relative to it.
+    pub fn for_app(app: &str, name: impl AsRef<Path>) -> io::Result<Self> {
+        App::new(app).cache_file(name).map(Self::new)
+    }
+}
+
+impl<D: Digest> Store<D> {
+    /// Sets the digest used to name entries.
+    ///
+    /// Changing the digest of an existing store orphans its entries, until they
+    /// are pruned (see `prune`).
+    #[must_use]
+    pub fn digest<E: Digest>(self, digest: E) -> Store<E> {
+        Store {
+            root: self.root,
+            digest,
+            journal: self.journal,
+        }
+    }
+
+    /// Records removed entries into a journal.
+    ///
+    /// See [`journal`](crate::journal) for details.
+    #[cfg(feature = "journal")]
+    #[must_use]
+    pub fn journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Returns the root of the store.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the path to the entry of a key, whether or not it exists.
+    #[must_use]
+    pub fn path(&self, key: impl AsRef<[u8]>) -> PathBuf {
+        let hex = self
+            .digest
+            .digest(key.as_ref())
+            .iter()
+            .fold(String::new(), |mut out, byte| {
+                let _ = write!(out, "{byte:02x}");
+                out
+            });
+        match (hex.get(..2), hex.get(2..)) {
+            (Some(dir), Some(name)) if !name.is_empty() => self.root.join(dir).join(name),
+            _ => self.root.join(hex),
+        }
+    }
+
+    /// Checks whether the store contains an entry for a key.
+    #[must_use]
+    pub fn contains(&self, key: impl AsRef<[u8]>) -> bool {
+        self.path(key).is_file()
+    }
+
+    /// Reads the entry of a key, if any.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the entry exists, but could not be read.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Writes the entry of a key atomically, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the entry could not be written.
+    pub fn insert(&self, key: impl AsRef<[u8]>, data: impl AsRef<[u8]>) -> io::Result<PathBuf> {
+        let path = self.path(key);
+        atomic::write(&path, data)?;
+        Ok(path)
+    }
+
+    /// Removes the entry of a key, if any.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the entry could not be removed.
+    pub fn remove(&self, key: impl AsRef<[u8]>) -> io::Result<()> {
+        readonly::check()?;
+        let path = self.path(key);
+        match fs::remove_file(&path) {
+            Ok(()) => self.removed(&path),
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Prunes the store by a policy, returning how many entries were removed.
+    ///
+    /// Entries are pruned as by [`App::maybe_prune`], such that a store within
+    /// the cache directory can be held to its own limits. Subdirectories left
+    /// empty are removed as well.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the store could not be read, or if an entry could not be
+    /// removed.
+    #[cfg(feature = "cache-gc")]
+    pub fn prune(&self, policy: &Policy) -> io::Result<usize> {
+        readonly::check()?;
+        prune(&self.root, *policy, self.journal.as_ref()).map(|(removed, _)| removed)
+    }
+
+    /// Records a removed entry into the journal, if any.
+    fn removed(&self, path: &Path) -> io::Result<()> {
+        match &self.journal {
+            Some(journal) => journal.removed(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns a cached value if it is fresh, otherwise computing and storing it.
+///
+/// Entries are fresh if they were stored within `ttl`. Should several
+/// processes miss the same entry at once, only one computes it while the others
+/// wait, before reading the stored value. The lock file held meanwhile (i.e.
+/// `*.lock`) is removed once done.
+///
+/// # Errors
+///
+/// Errors if the cache directory could not be found or created, or if the
+/// entry could not be read or written.
+pub fn get_or_insert_with<F>(app: &str, key: &str, ttl: Duration, f: F) -> io::Result<Vec<u8>>
+where
+    F: FnOnce() -> Vec<u8>,
+{
+    try_get_or_insert_with(app, key, ttl, || Ok(f()))
+}
+
+/// Returns a cached value if it is fresh, otherwise computing and storing it
+/// with a fallible function.
+///
+/// Errors from the function are returned as-is, without storing anything. See
+/// [`get_or_insert_with`] for details.
+///
+/// # Errors
+///
+/// Errors if the cache directory could not be found or created, if the entry
+/// could not be read or written, or if it could not be computed.
+pub fn try_get_or_insert_with<F>(app: &str, key: &str, ttl: Duration, f: F) -> io::Result<Vec<u8>>
+where
+    F: FnOnce() -> io::Result<Vec<u8>>,
+{
+    get_or_insert_at(&entry(app, key)?, ttl, f)
+}
+
+/// Returns the cache entry at a path if it is fresh, otherwise computing and
+/// storing it.
+fn get_or_insert_at<F>(path: &Path, ttl: Duration, f: F) -> io::Result<Vec<u8>>
+where
+    F: FnOnce() -> io::Result<Vec<u8>>,
+{
+    if let Some(data) = fresh(path, ttl)? {
+        return Ok(data);
+    }
+    readonly::check()?;
+    if let Some(parent) = path.parent() {
+        sys::create_dir_all(parent)?;
+    }
+    // Misses are computed by a single process at a time, holding the lock.
+    let lock = path.with_extension("lock");
+    let _guard = acquire(&lock)?;
+    let data = match fresh(path, ttl) {
+        Ok(Some(data)) => Ok(data),
+        Ok(None) => f().and_then(|data| atomic::write(path, &data).map(|()| data)),
+        Err(err) => Err(err),
+    };
+    // Waiters find the entry fresh once they acquire the lock, and later
+    // misses create the lock anew.
+    remove_lock(&lock)?;
+    data
+}
+
+/// Acquires the lock of a cache entry, creating its lock file.
+///
+/// As lock files are removed while held, the lock may be acquired on a file
+/// which was since removed; acquisition is then retried on a new one.
+fn acquire(path: &Path) -> io::Result<File> {
+    loop {
+        let lock = File::create(path)?;
+        lock.lock()?;
+        if held(&lock, path)? {
+            return Ok(lock);
+        }
+    }
+}
+
+/// Checks whether a locked file is still the lock file at a path.
+#[cfg(unix)]
+fn held(lock: &File, path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let held = lock.metadata()?;
+    Ok(match fs::metadata(path) {
+        Ok(meta) => (meta.dev(), meta.ino()) == (held.dev(), held.ino()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+        Err(err) => return Err(err),
+    })
+}
+
+/// Checks whether a locked file is still the lock file at a path.
+///
+/// Without file identifiers, the lock file is assumed to be held as long as
+/// it exists.
+#[cfg(not(unix))]
+fn held(_: &File, path: &Path) -> io::Result<bool> {
+    match fs::metadata(path) {
+        Ok(_) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Removes a lock file, unless it was already removed.
+fn remove_lock(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Removes a cached value, if any.
+///
+/// # Errors
+///
+/// Errors if the cache directory could not be found, or if the entry could not
+/// be removed.
+pub fn remove(app: &str, key: &str) -> io::Result<()> {
+    readonly::check()?;
+    let path = entry(app, key)?;
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => (),
+    }
+    // Lock files left behind (e.g. by a crashed process) are removed too,
+    // unless held.
+    abandoned(&path.with_extension("lock"))
+}
+
+/// Removes a lock file of a cache entry if it is not held, such that it was
+/// abandoned (e.g. by a crashed process).
+fn abandoned(path: &Path) -> io::Result<()> {
+    let lock = match File::options().write(true).open(path) {
+        Ok(lock) => lock,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    match lock.try_lock() {
+        Ok(()) if held(&lock, path)? => remove_lock(path),
+        Ok(()) | Err(TryLockError::WouldBlock) => Ok(()),
+        Err(TryLockError::Error(err)) => Err(err),
+    }
+}
+
+/// Policy of which cached files to prune.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use xdir::cache::Policy;
+///
+/// // Keep at most 1 GiB, and nothing older than 30 days.
+/// let policy = Policy::new()
+///     .max_age(Duration::from_secs(30 * 24 * 60 * 60))
+///     .max_size(1 << 30);
+/// ```
+#[cfg(feature = "cache-gc")]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Policy {
+    max_age: Option<Duration>,
+    max_size: Option<u64>,
+}
+
+#[cfg(feature = "cache-gc")]
+impl Policy {
+    /// Constructs an empty policy, which prunes nothing.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_age: None,
+            max_size: None,
+        }
+    }
+
+    /// Prunes files last modified longer ago than the given age.
+    #[must_use]
+    pub const fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Prunes the least recently modified files, until the cache is no larger
+    /// than the given size in bytes.
+    #[must_use]
+    pub const fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+}
+
+/// Prunes an application's cache directory by a policy, unless it was pruned
+/// within the given interval.
+///
+/// See [`App::maybe_prune`] for details, and to record removed files into a
+/// journal.
+///
+/// # Errors
+///
+/// Errors if the cache or state directory could not be found, or if the cache
+/// could not be pruned.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use xdir::cache::Policy;
+///
+/// let policy = Policy::new().max_size(512 << 20);
+/// const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+/// if let Some(removed) = xdir::cache::maybe_prune("myapp", policy, DAY).unwrap() {
+///     eprintln!("pruned {removed} cached files");
+/// }
+/// ```
+#[cfg(feature = "cache-gc")]
+pub fn maybe_prune(app: &str, policy: Policy, min_interval: Duration) -> io::Result<Option<usize>> {
+    App::new(app).maybe_prune(policy, min_interval)
+}
+
+#[cfg(feature = "cache-gc")]
+impl App {
+    /// Prunes the application's cache directory by a policy, unless it was
+    /// pruned within the given interval.
+    ///
+    /// The time of the last pruning of each cache directory is recorded within
+    /// the state directory, such that this can be called unconditionally at
+    /// startup without walking the cache on every run. Should several processes
+    /// call this at once, only one prunes while the others skip it. Lock files
+    /// (i.e. `*.lock`) and the cache directory itself are left in place, except
+    /// for those of [`get_or_insert_with`] which were abandoned (e.g. by a
+    /// crashed process). Removed files are recorded into the application's
+    /// journal (see `journal`), if any. Returns how many files were removed, or
+    /// [`None`] if pruning was skipped.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the cache or state directory could not be found, or if the
+    /// cache could not be pruned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use xdir::cache::Policy;
+    /// use xdir::App;
+    ///
+    /// let app = App::new("myapp");
+    /// let policy = Policy::new().max_size(512 << 20);
+    /// const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    /// if let Some(removed) = app.maybe_prune(policy, DAY).unwrap() {
+    ///     eprintln!("pruned {removed} cached files");
+    /// }
+    /// ```
+    pub fn maybe_prune(&self, policy: Policy, min_interval: Duration) -> io::Result<Option<usize>> {
+        let app = self.name();
+        let cache = self.cache().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "could not find cache directory")
+        })?;
+        let dir = crate::state()
+            .map(|path| path.join("xdir").join("prune"))
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "could not find state directory")
+            })?;
+        // Differently scoped applications (e.g. profiles) share a name, so the
+        // stamp is keyed by the cache directory itself.
+        let stamp = dir.join(md5::hex(cache.as_os_str().as_encoded_bytes()));
+        if !due(&stamp, min_interval) {
+            return Ok(None);
+        }
+        self.check_writable()?;
+        sys::create_dir_all(&dir)?;
+        let lock = File::create(stamp.with_extension("lock"))?;
+        match lock.try_lock() {
+            Ok(()) => (),
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+        // Another process may have pruned since.
+        if !due(&stamp, min_interval) {
+            return Ok(None);
+        }
+        let (removed, size) = prune(&cache, policy, self.journal_ref())?;
+        telemetry::pruned(app, removed, size);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        atomic::write(&stamp, now.as_secs().to_string())?;
+        Ok(Some(removed))
+    }
+}
+
+/// Checks whether pruning is due, as recorded by its stamp.
+#[cfg(feature = "cache-gc")]
+fn due(stamp: &Path, interval: Duration) -> bool {
+    let Some(last) = fs::read_to_string(stamp)
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+    else {
+        return true;
+    };
+    // Stamps from the future (e.g. after the clock was changed) are due.
+    SystemTime::now()
+        .duration_since(last)
+        .map_or(true, |elapsed| elapsed >= interval)
+}
+
+/// Prunes a directory by a policy, returning how many files were removed, and
+/// the size of those kept. Removed files are recorded into the journal, if
+/// any.
+#[cfg(feature = "cache-gc")]
+fn prune(root: &Path, policy: Policy, journal: Option<&Journal>) -> io::Result<(usize, u64)> {
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    walk(root, &mut files, &mut dirs)?;
+    let now = SystemTime::now();
+    let mut removed = 0;
+    if let Some(max_age) = policy.max_age {
+        let mut kept = Vec::new();
+        for (path, modified, len) in files {
+            // Files from the future (e.g. after the clock was changed) are stale.
+            let age = now.duration_since(modified).ok();
+            if age.is_none_or(|age| age > max_age) {
+                remove_file(&path, journal)?;
+                removed += 1;
+            } else {
+                kept.push((path, modified, len));
+            }
+        }
+        files = kept;
+    }
+    let mut size = files.iter().map(|&(_, _, len)| len).sum::<u64>();
+    if let Some(max_size) = policy.max_size {
+        files.sort_by_key(|&(_, modified, _)| modified);
+        for (path, _, len) in files {
+            if size <= max_size {
+                break;
+            }
+            remove_file(&path, journal)?;
+            size -= len;
+            removed += 1;
+        }
+    }
+    // Deepest first, such that emptied parents are removed too. Fails
+    // harmlessly unless the directory is now empty.
+    for dir in dirs.iter().rev() {
+        let _ = fs::remove_dir(dir);
+    }
+    Ok((removed, size))
+}
+
+/// Collects the files (along with their modification time and size) and
+/// subdirectories within a directory, recursively.
+#[cfg(feature = "cache-gc")]
+fn walk(
+    dir: &Path,
+    files: &mut Vec<(PathBuf, SystemTime, u64)>,
+    dirs: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            dirs.push(path.clone());
+            walk(&path, files, dirs)?;
+        } else if path.extension().is_some_and(|ext| ext == "lock") {
+            if is_entry_lock(&path) {
+                abandoned(&path)?;
+            }
+        } else {
+            let modified = meta.modified().unwrap_or(UNIX_EPOCH);
+            files.push((path, modified, meta.len()));
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether a lock file is that of a cache entry, as named by
+/// [`entry`].
+#[cfg(feature = "cache-gc")]
+fn is_entry_lock(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.len() == 32 && stem.bytes().all(|byte| byte.is_ascii_hexdigit()))
+}
+
+/// Removes a file, unless it was already removed, recording it into the
+/// journal (if any).
+#[cfg(feature = "cache-gc")]
+fn remove_file(path: &Path, journal: Option<&Journal>) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => journal.map_or(Ok(()), |journal| journal.removed(path)),
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Returns the path to a cache entry.
+fn entry(app: &str, key: &str) -> io::Result<PathBuf> {
+    let dir = App::new(app)
+        .cache()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find cache directory"))?;
+    Ok(dir.join(md5::hex(key.as_bytes())))
+}
+
+/// Reads a cache entry, if it is fresh.
+fn fresh(path: &Path, ttl: Duration) -> io::Result<Option<Vec<u8>>> {
+    let modified = match fs::metadata(path).and_then(|meta| meta.modified()) {
+        Ok(modified) => modified,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    // Entries from the future (e.g. after the clock was changed) are stale.
+    let age = SystemTime::now().duration_since(modified).ok();
+    if age.is_none_or(|age| age > ttl) {
+        return Ok(None);
+    }
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    const TTL: Duration = Duration::MAX;
+
+    #[test]
+    fn get_or_insert_removes_lock() {
+        let tmp = TempDir::new();
+        let path = tmp.join(md5::hex(b"key"));
+        let data = get_or_insert_at(&path, TTL, || Ok(b"value".to_vec())).unwrap();
+        assert_eq!(data, b"value");
+        assert!(!path.with_extension("lock").exists());
+        // Fresh entries are not recomputed.
+        let data = get_or_insert_at(&path, TTL, || unreachable!()).unwrap();
+        assert_eq!(data, b"value");
+    }
+
+    #[test]
+    fn get_or_insert_computes_once() {
+        let tmp = TempDir::new();
+        let path = tmp.join(md5::hex(b"key"));
+        let calls = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let data = get_or_insert_at(&path, TTL, || {
+                        calls.fetch_add(1, Ordering::Relaxed);
+                        thread::sleep(Duration::from_millis(20));
+                        Ok(b"value".to_vec())
+                    })
+                    .unwrap();
+                    assert_eq!(data, b"value");
+                });
+            }
+        });
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(!path.with_extension("lock").exists());
+    }
+
+    #[test]
+    fn abandoned_keeps_held_locks() {
+        let tmp = TempDir::new();
+        let path = tmp.join("entry.lock");
+        let lock = acquire(&path).unwrap();
+        abandoned(&path).unwrap();
+        assert!(path.exists());
+        drop(lock);
+        abandoned(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[cfg(feature = "cache-gc")]
+    #[test]
+    fn prune_removes_abandoned_entry_locks() {
+        let tmp = TempDir::new();
+        let entry = tmp.join(md5::hex(b"key")).with_extension("lock");
+        let other = tmp.join("other.lock");
+        File::create(&entry).unwrap();
+        File::create(&other).unwrap();
+        prune(&tmp, Policy::new(), None).unwrap();
+        assert!(!entry.exists());
+        assert!(other.exists());
+    }
+
+    #[cfg(feature = "cache-gc")]
+    #[test]
+    fn store_prunes_by_policy() {
+        let tmp = TempDir::new();
+        let store = Store::new(tmp.join("store"));
+        let old = store.insert(b"old", b"data").unwrap();
+        File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(UNIX_EPOCH)
+            .unwrap();
+        let new = store.insert(b"new", b"data").unwrap();
+        assert_eq!(store.prune(&Policy::new().max_size(4)).unwrap(), 1);
+        assert!(!old.exists());
+        assert!(new.exists());
+        // Emptied subdirectories are removed too.
+        assert!(!old.parent().unwrap().exists());
+        assert_eq!(
+            store.prune(&Policy::new().max_age(Duration::MAX)).unwrap(),
+            0
+        );
+    }
+
+    #[cfg(feature = "cache-gc")]
+    #[test]
+    fn maybe_prune_respects_read_only() {
+        let app = App::new("xdir-test-read-only-prune").read_only(true);
+        let err = app.maybe_prune(Policy::new(), Duration::ZERO).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ReadOnlyFilesystem);
+    }
+
+    #[cfg(all(feature = "cache-gc", feature = "journal"))]
+    #[test]
+    fn prune_records_removed_files() {
+        let tmp = TempDir::new();
+        let cache = tmp.join("cache");
+        let stale = cache.join("ab").join("stale");
+        sys::create_dir_all(stale.parent().unwrap()).unwrap();
+        fs::write(&stale, b"data").unwrap();
+        let journal = Journal::at(tmp.join("journal")).unwrap();
+        let (removed, size) = prune(&cache, Policy::new().max_size(0), Some(&journal)).unwrap();
+        assert_eq!((removed, size), (1, 0));
+        assert!(!stale.exists());
+        assert_eq!(
+            journal.records().unwrap(),
+            [crate::journal::Record::Removed(stale)]
+        );
+    }
+}
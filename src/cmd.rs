@@ -0,0 +1,58 @@
+//! External commands.
+
+use std::process::Command;
+
+use crate::Env;
+
+/// Runs an optional command, doing nothing if it is not installed.
+#[cfg(any(feature = "desktop", feature = "mime"))]
+pub(crate) fn run_if_installed(
+    program: &str,
+    arg: impl AsRef<std::ffi::OsStr>,
+) -> std::io::Result<()> {
+    use std::io;
+
+    match Command::new(program).arg(arg).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(io::Error::other(format!("{program} failed: {status}"))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Extension of [`Command`], passing on resolved directories to child
+/// processes.
+///
+/// Child processes (e.g. editors, pagers or plugins) resolve their directories
+/// from their own environment, which may disagree with that of the parent, as
+/// where the parent's directories were relocated by the override file, or by
+/// network redirection. Injecting the parent's resolution ensures that they
+/// see exactly the same layout.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::process::Command;
+///
+/// use xdir::CommandExt as _;
+///
+/// Command::new("myapp-plugin").xdg_env().status().unwrap();
+/// ```
+pub trait CommandExt {
+    /// Sets the child's variables to the directories resolved by the current
+    /// process (see [`Env::current`]).
+    fn xdg_env(&mut self) -> &mut Self;
+
+    /// Sets the child's variables to those of an environment.
+    fn xdg_env_of(&mut self, env: &Env) -> &mut Self;
+}
+
+impl CommandExt for Command {
+    fn xdg_env(&mut self) -> &mut Self {
+        self.xdg_env_of(&Env::current())
+    }
+
+    fn xdg_env_of(&mut self, env: &Env) -> &mut Self {
+        self.envs(env.to_vars())
+    }
+}
@@ -0,0 +1,51 @@
+//! Well-known subdirectories of the config directory.
+//!
+//! Functions in this module return the standard locations of configuration
+//! that is read by the desktop or system services, rather than by a single
+//! application.
+//!
+//! With the `toml` feature, this module can also load typed configuration
+//! files from the config search path (see `load`). Further enabling the
+//! `notify` feature allows subscribing to changes of configuration files (see
+//! `subscribe`).
+
+use std::path::PathBuf;
+
+use crate::config;
+
+/// Returns the path to the user's autostart directory.
+#[must_use]
+pub fn autostart() -> Option<PathBuf> {
+    config().map(|path| path.join("autostart"))
+}
+
+/// Returns the path to the user's environment directory.
+///
+/// Files in this directory configure the environment of services started by
+/// the systemd user manager.
+#[must_use]
+pub fn environment_d() -> Option<PathBuf> {
+    config().map(|path| path.join("environment.d"))
+}
+
+/// Returns the path to the user's systemd unit directory.
+#[must_use]
+pub fn systemd_user() -> Option<PathBuf> {
+    config().map(|path| path.join("systemd/user"))
+}
+
+/// Returns a subdirectory of the user and system config directories, in order
+/// of preference.
+#[cfg(feature = "autostart")]
+pub(crate) fn search(dir: &str) -> Vec<PathBuf> {
+    config()
+        .into_iter()
+        .chain(crate::config_dirs())
+        .map(|path| path.join(dir))
+        .collect()
+}
+
+#[cfg(feature = "toml")]
+pub use crate::load::{load, LoadError};
+#[cfg(all(feature = "toml", feature = "notify"))]
+pub use crate::load::{subscribe, Subscription};
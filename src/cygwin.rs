@@ -0,0 +1,207 @@
+//! Cygwin and MSYS2 environments.
+//!
+//! Shells provided by Cygwin and MSYS2 (including Git for Windows) present the
+//! filesystem with POSIX-style paths (e.g. `/home/me/.config`), which native
+//! Windows processes cannot open. This module detects such environments, and
+//! translates paths between their POSIX and Windows forms (as per `cygpath`).
+//!
+//! Within these environments, directories set through the environment in
+//! POSIX form are translated to their Windows form automatically.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::{cygwin, Dir};
+//!
+//! if let Some(path) = cygwin::translate(&Dir::Config.path().unwrap()) {
+//!     println!("native: {}", path.native().display());
+//!     println!("posix: {}", path.posix());
+//! }
+//! ```
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// POSIX-style environment running on Windows.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Environment {
+    /// Cygwin, which mounts drives at `/cygdrive/<drive>`.
+    Cygwin,
+    /// MSYS2 (e.g. Git for Windows), which mounts drives at `/<drive>`.
+    Msys,
+}
+
+impl Environment {
+    /// Returns the prefix at which drives are mounted.
+    fn drives(self) -> &'static str {
+        match self {
+            Self::Cygwin => "/cygdrive/",
+            Self::Msys => "/",
+        }
+    }
+}
+
+/// Detects the POSIX-style environment the process is running within, if any.
+#[must_use]
+pub fn detect() -> Option<Environment> {
+    if !cfg!(windows) {
+        return None;
+    }
+    if env::var_os("MSYSTEM").is_some_and(|var| !var.is_empty()) {
+        Some(Environment::Msys)
+    } else if env::var_os("CYGWIN").is_some()
+        || env::var("OSTYPE").is_ok_and(|var| var.contains("cygwin"))
+    {
+        Some(Environment::Cygwin)
+    } else {
+        None
+    }
+}
+
+/// Returns the Windows path of the environment's root directory (e.g.
+/// `C:\msys64`).
+///
+/// This is queried once from `cygpath`.
+#[must_use]
+pub fn root() -> Option<&'static Path> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        detect()?;
+        let output = Command::new("cygpath")
+            .args(["-w", "/"])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let root = String::from_utf8(output.stdout).ok()?;
+        Some(PathBuf::from(root.trim().trim_end_matches('\\')))
+    })
+    .as_deref()
+}
+
+/// A path in both its Windows and POSIX forms.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Translated {
+    native: PathBuf,
+    posix: String,
+}
+
+impl Translated {
+    /// Returns the Windows form of the path, for use by native processes.
+    #[must_use]
+    pub fn native(&self) -> &Path {
+        &self.native
+    }
+
+    /// Returns the POSIX form of the path, for use within the environment.
+    #[must_use]
+    pub fn posix(&self) -> &str {
+        &self.posix
+    }
+}
+
+/// Translates a path, given in either form, into both forms.
+///
+/// Returns [`None`] outside of a POSIX-style environment, or if the path could
+/// not be translated.
+#[must_use]
+pub fn translate(path: &Path) -> Option<Translated> {
+    let text = path.to_str()?;
+    let (native, posix) = if text.starts_with('/') && !text.starts_with("//") {
+        (to_windows(text)?, text.to_string())
+    } else {
+        (path.to_path_buf(), to_posix(path)?)
+    };
+    Some(Translated { native, posix })
+}
+
+/// Translates a POSIX path into its Windows form.
+///
+/// Paths on a mounted drive (e.g. `/c/Users`) are translated to that drive
+/// (`C:\Users`), network paths (e.g. `//server/share`) are translated to UNC
+/// paths, and all others are translated relative to the environment's
+/// [root](root()). Returns [`None`] outside of a POSIX-style environment, or if
+/// the path is relative.
+#[must_use]
+pub fn to_windows(path: &str) -> Option<PathBuf> {
+    let env = detect()?;
+    if let Some(share) = path.strip_prefix("//") {
+        return Some(PathBuf::from(format!(r"\\{}", share.replace('/', "\\"))));
+    }
+    if !path.starts_with('/') {
+        return None;
+    }
+    let drive = path.strip_prefix(env.drives()).and_then(|rest| {
+        let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        is_drive(drive).then_some((drive, rest))
+    });
+    let (base, rest) = match drive {
+        Some((drive, rest)) => (
+            PathBuf::from(format!(r"{}:\", drive.to_ascii_uppercase())),
+            rest,
+        ),
+        None => (root()?.to_path_buf(), &path[1..]),
+    };
+    Some(
+        rest.split('/')
+            .filter(|part| !part.is_empty())
+            .fold(base, |path, part| path.join(part)),
+    )
+}
+
+/// Translates a Windows path into its POSIX form.
+///
+/// Paths within the environment's [root](root()) are translated relative to
+/// it, and all others to their mounted drive (e.g. `/c/Users`) or network path
+/// (e.g. `//server/share`). Returns [`None`] outside of a POSIX-style
+/// environment, or if the path is not absolute.
+#[must_use]
+pub fn to_posix(path: &Path) -> Option<String> {
+    let env = detect()?;
+    let text = path.to_str()?.replace('\\', "/");
+    if let Some(share) = text.strip_prefix("//") {
+        return Some(format!("//{share}"));
+    }
+    if let Some(root) = root()
+        .and_then(Path::to_str)
+        .map(|root| root.replace('\\', "/"))
+    {
+        let within = text
+            .get(..root.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&root));
+        if within && text[root.len()..].is_empty() {
+            return Some("/".to_string());
+        }
+        if within && text[root.len()..].starts_with('/') {
+            return Some(text[root.len()..].to_string());
+        }
+    }
+    let (drive, rest) = text.split_once(':')?;
+    if !is_drive(drive) || !(rest.is_empty() || rest.starts_with('/')) {
+        return None;
+    }
+    let mut posix = format!("{}{}", env.drives(), drive.to_ascii_lowercase());
+    for part in rest.split('/').filter(|part| !part.is_empty()) {
+        posix.push('/');
+        posix.push_str(part);
+    }
+    Some(posix)
+}
+
+/// Translates an environment-provided path into its Windows form, if it is
+/// given in POSIX form within a POSIX-style environment.
+#[cfg(windows)]
+pub(crate) fn native(path: PathBuf) -> PathBuf {
+    path.to_str()
+        .filter(|text| text.starts_with('/'))
+        .and_then(to_windows)
+        .unwrap_or(path)
+}
+
+/// Checks whether a path component names a drive.
+fn is_drive(part: &str) -> bool {
+    part.len() == 1 && part.bytes().all(|byte| byte.is_ascii_alphabetic())
+}
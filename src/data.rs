@@ -0,0 +1,102 @@
+//! Well-known subdirectories of the data directory.
+//!
+//! Functions in this module return the standard locations of resources that
+//! are shared between applications, such as fonts. Each location has a
+//! corresponding search path, which includes the user's directory followed by
+//! those of the system [data directories](crate::data_dirs).
+
+use std::path::PathBuf;
+
+use crate::{data, data_dirs, home};
+
+/// Returns the path to the user's applications directory.
+///
+/// This is where desktop entries (see `desktop::Entry`) are installed.
+#[must_use]
+pub fn applications() -> Option<PathBuf> {
+    data().map(|path| path.join("applications"))
+}
+
+/// Returns the path to the user's fonts directory.
+#[must_use]
+pub fn fonts() -> Option<PathBuf> {
+    data().map(|path| path.join("fonts"))
+}
+
+/// Returns the preference-ordered font search path.
+#[must_use]
+pub fn font_search_path() -> Vec<PathBuf> {
+    search("fonts")
+}
+
+/// Returns the path to the user's icons directory.
+#[must_use]
+pub fn icons() -> Option<PathBuf> {
+    data().map(|path| path.join("icons"))
+}
+
+/// Returns the preference-ordered icon search path.
+///
+/// As per the [Icon Theme Specification][spec], this includes the legacy
+/// `$HOME/.icons` directory and falls back to `/usr/share/pixmaps`.
+///
+/// [spec]: https://specifications.freedesktop.org/icon-theme-spec/latest/
+#[must_use]
+pub fn icon_search_path() -> Vec<PathBuf> {
+    icons()
+        .into_iter()
+        .chain(home().map(|path| path.join(".icons")))
+        .chain(data_dirs().into_iter().map(|path| path.join("icons")))
+        .chain(cfg!(unix).then(|| PathBuf::from("/usr/share/pixmaps")))
+        .collect()
+}
+
+/// Returns the path to a section of the user's manual pages.
+///
+/// Sections are named after their number (e.g. `1` for executables), with an
+/// optional suffix (e.g. `3p`).
+#[must_use]
+pub fn man(section: &str) -> Option<PathBuf> {
+    data().map(|path| path.join("man").join(format!("man{section}")))
+}
+
+/// Returns the path to the user's MIME database directory.
+#[must_use]
+pub fn mime() -> Option<PathBuf> {
+    data().map(|path| path.join("mime"))
+}
+
+/// Returns the preference-ordered MIME database search path.
+#[must_use]
+pub fn mime_search_path() -> Vec<PathBuf> {
+    search("mime")
+}
+
+/// Returns the path to the user's themes directory.
+#[must_use]
+pub fn themes() -> Option<PathBuf> {
+    data().map(|path| path.join("themes"))
+}
+
+/// Returns the preference-ordered theme search path.
+///
+/// This includes the legacy `$HOME/.themes` directory, which is still searched
+/// by GTK.
+#[must_use]
+pub fn theme_search_path() -> Vec<PathBuf> {
+    themes()
+        .into_iter()
+        .chain(home().map(|path| path.join(".themes")))
+        .chain(data_dirs().into_iter().map(|path| path.join("themes")))
+        .collect()
+}
+
+/// Returns a subdirectory of the user and system data directories, in order of
+/// preference.
+fn search(dir: &str) -> Vec<PathBuf> {
+    data()
+        .into_iter()
+        .chain(data_dirs())
+        .map(|path| path.join(dir))
+        .collect()
+}
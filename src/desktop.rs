@@ -0,0 +1,406 @@
+//! Desktop entries.
+//!
+//! This module provides an [`Entry`] type for generating `.desktop` files as
+//! per the [Desktop Entry Specification][spec], with all values escaped
+//! correctly.
+//!
+//! # Examples
+//!
+//! To install a launcher for an application:
+//!
+//! ```no_run
+//! use xdir::desktop::{self, Entry};
+//!
+//! let path = Entry::new("My App", ["myapp", "--gui", "%U"])
+//!     .comment("Does things")
+//!     .categories(["Utility"])
+//!     .install("org.example.MyApp")
+//!     .unwrap();
+//! desktop::update_database().unwrap();
+//! ```
+//!
+//! [spec]: https://specifications.freedesktop.org/desktop-entry-spec/latest/
+
+use std::fmt::{self, Display, Write as _};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::data::applications;
+use crate::{atomic, cmd};
+
+/// Field codes passed through unescaped when used as an argument.
+const FIELD_CODES: [&str; 6] = ["%f", "%F", "%u", "%U", "%i", "%c"];
+
+/// Keys written before all others, whose values are given by the entry unless
+/// set explicitly.
+const LEADING: [&str; 4] = ["Type", "Version", "Name", "Exec"];
+
+/// A desktop entry of type `Application`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Entry {
+    name: String,
+    exec: Vec<String>,
+    fields: Vec<(String, String)>,
+}
+
+impl Entry {
+    /// Constructs a new desktop entry.
+    ///
+    /// Arguments of the command are taken literally, with the exception of
+    /// bare field codes (e.g. `%U`), which are expanded by the launcher.
+    pub fn new<I, S>(name: impl Into<String>, exec: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            exec: exec.into_iter().map(Into::into).collect(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Sets the `Comment` key.
+    #[must_use]
+    pub fn comment(self, comment: &str) -> Self {
+        self.set("Comment", escape(comment))
+    }
+
+    /// Sets the `Icon` key.
+    #[must_use]
+    pub fn icon(self, icon: &str) -> Self {
+        self.set("Icon", escape(icon))
+    }
+
+    /// Sets the `Terminal` key.
+    #[must_use]
+    pub fn terminal(self, terminal: bool) -> Self {
+        self.set("Terminal", terminal.to_string())
+    }
+
+    /// Sets the `Hidden` key.
+    ///
+    /// A hidden entry is treated as if it were deleted, which is used to mask
+    /// an entry of the same name in a less preferred directory.
+    #[must_use]
+    pub fn hidden(self, hidden: bool) -> Self {
+        self.set("Hidden", hidden.to_string())
+    }
+
+    /// Sets the `NoDisplay` key.
+    #[must_use]
+    pub fn no_display(self, no_display: bool) -> Self {
+        self.set("NoDisplay", no_display.to_string())
+    }
+
+    /// Sets the `Categories` key.
+    #[must_use]
+    pub fn categories<'a>(self, categories: impl IntoIterator<Item = &'a str>) -> Self {
+        self.set("Categories", list(categories))
+    }
+
+    /// Sets the `MimeType` key.
+    #[must_use]
+    pub fn mime_types<'a>(self, types: impl IntoIterator<Item = &'a str>) -> Self {
+        self.set("MimeType", list(types))
+    }
+
+    /// Sets an arbitrary string key.
+    ///
+    /// Keys may be localized (e.g. `Name[de]`), and are validated when the
+    /// entry is written; the value will be escaped. Setting a key which is
+    /// otherwise given by the entry (i.e. `Type`, `Version`, `Name` or `Exec`)
+    /// replaces its value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::desktop::Entry;
+    ///
+    /// let entry = Entry::new("My App", ["myapp"])
+    ///     .key("Name[de]", "Meine App")
+    ///     .key("Exec", "myapp --gui");
+    /// assert!(entry.to_string().contains("Name[de]=Meine App\n"));
+    /// assert_eq!(entry.to_string().matches("Exec=").count(), 1);
+    /// ```
+    #[must_use]
+    pub fn key(mut self, key: &str, value: &str) -> Self {
+        if key == "Name" {
+            self.name = value.to_string();
+            return self;
+        }
+        self.set(key, escape(value))
+    }
+
+    /// Installs the entry into the user's applications directory, returning
+    /// its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the entry or its identifier are invalid, if the applications
+    /// directory could not be found, or if the file could not be written.
+    pub fn install(&self, id: &str) -> io::Result<PathBuf> {
+        let dir = applications().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "could not find data directory")
+        })?;
+        self.write(&dir, id)
+    }
+
+    /// Writes the entry into a directory as `<id>.desktop`, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the entry or its identifier are invalid, or if the file could
+    /// not be written.
+    pub fn write(&self, dir: &Path, id: &str) -> io::Result<PathBuf> {
+        validate_id(id)?;
+        self.validate()?;
+        let path = dir.join(format!("{id}.desktop"));
+        atomic::write(&path, self.to_string())?;
+        Ok(path)
+    }
+
+    /// Checks that the entry is well-formed.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the entry has an empty name or command, or contains an invalid
+    /// key.
+    pub fn validate(&self) -> io::Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(invalid("entry name must not be empty"));
+        }
+        let exec = self
+            .field("Exec")
+            .map_or(!self.exec.is_empty(), |exec| !exec.is_empty());
+        if !exec {
+            return Err(invalid("entry command must not be empty"));
+        }
+        for (key, _) in &self.fields {
+            if !is_valid_key(key) {
+                return Err(invalid(format!("invalid entry key: {key:?}")));
+            }
+        }
+        Ok(())
+    }
+
+    fn set(mut self, key: &str, value: String) -> Self {
+        self.fields.retain(|(other, _)| other != key);
+        self.fields.push((key.to_string(), value));
+        self
+    }
+
+    /// Returns the value of an explicitly set key, if any.
+    fn field(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(other, _)| other == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Desktop Entry]")?;
+        writeln!(f, "Type={}", self.field("Type").unwrap_or("Application"))?;
+        writeln!(f, "Version={}", self.field("Version").unwrap_or("1.5"))?;
+        writeln!(f, "Name={}", escape(&self.name))?;
+        match self.field("Exec") {
+            Some(exec) => writeln!(f, "Exec={exec}")?,
+            None => writeln!(f, "Exec={}", escape(&exec(&self.exec)))?,
+        }
+        for (key, value) in &self.fields {
+            if !LEADING.contains(&key.as_str()) {
+                writeln!(f, "{key}={value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Updates the cache database of the user's applications directory.
+///
+/// This runs `update-desktop-database` if it is installed, and does nothing
+/// otherwise.
+///
+/// # Errors
+///
+/// Errors if the command could not be run or did not succeed.
+pub fn update_database() -> io::Result<()> {
+    let Some(dir) = applications() else {
+        return Ok(());
+    };
+    cmd::run_if_installed("update-desktop-database", dir)
+}
+
+/// Escapes a value of type `string`.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for (idx, ch) in value.chars().enumerate() {
+        match ch {
+            ' ' if idx == 0 => out.push_str("\\s"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\\' => out.push_str("\\\\"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Formats a list of values, each terminated by a semicolon.
+fn list<'a>(items: impl IntoIterator<Item = &'a str>) -> String {
+    items.into_iter().fold(String::new(), |mut out, item| {
+        let _ = write!(out, "{};", escape(item).replace(';', "\\;"));
+        out
+    })
+}
+
+/// Formats a command line for the `Exec` key, prior to string escaping.
+fn exec(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if FIELD_CODES.contains(&arg.as_str()) {
+                return arg.clone();
+            }
+            let arg = arg.replace('%', "%%");
+            let reserved = arg.is_empty()
+                || arg
+                    .chars()
+                    .any(|ch| ch.is_whitespace() || "\"'\\><~|&;$*?#()`".contains(ch));
+            if reserved {
+                let mut quoted = String::from('"');
+                for ch in arg.chars() {
+                    if matches!(ch, '"' | '`' | '$' | '\\') {
+                        quoted.push('\\');
+                    }
+                    quoted.push(ch);
+                }
+                quoted.push('"');
+                quoted
+            } else {
+                arg
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Checks whether a key is valid, optionally localized as `Key[locale]`.
+fn is_valid_key(key: &str) -> bool {
+    let (key, locale) = match key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+        Some((key, locale)) => (key, Some(locale)),
+        None => (key, None),
+    };
+    // Locales are of the form `lang_COUNTRY.ENCODING@MODIFIER`.
+    let locale = locale.is_none_or(|locale| {
+        !locale.is_empty()
+            && locale
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | '@' | '-'))
+    });
+    locale
+        && !key.is_empty()
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+}
+
+/// Checks that a desktop file identifier is valid.
+fn validate_id(id: &str) -> io::Result<()> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!("invalid desktop file id: {id:?}")))
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_entry() {
+        let entry = Entry::new(" My App", ["my app", "--name=%u", "%U"])
+            .comment("Line\nbreak")
+            .categories(["Utility", "A;B"])
+            .terminal(false);
+        assert_eq!(
+            entry.to_string(),
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Version=1.5\n\
+             Name=\\sMy App\n\
+             Exec=\"my app\" --name=%%u %U\n\
+             Comment=Line\\nbreak\n\
+             Categories=Utility;A\\;B;\n\
+             Terminal=false\n"
+        );
+    }
+
+    #[test]
+    fn replaces_leading_keys() {
+        let entry = Entry::new("My App", ["myapp"])
+            .key("Type", "Link")
+            .key("Version", "1.0")
+            .key("Name", "Other")
+            .key("Exec", "other %U")
+            .key("Type", "Application");
+        assert_eq!(
+            entry.to_string(),
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Version=1.0\n\
+             Name=Other\n\
+             Exec=other %U\n"
+        );
+        assert!(entry.validate().is_ok());
+        assert!(Entry::new("My App", ["myapp"])
+            .key("Exec", "")
+            .validate()
+            .is_err());
+        assert!(Entry::new("My App", ["myapp"])
+            .key("Name", " ")
+            .validate()
+            .is_err());
+        assert!(Entry::new("My App", Vec::<String>::new())
+            .key("Exec", "myapp")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn accepts_localized_keys() {
+        for key in [
+            "Name[de]",
+            "Comment[sr_RS@latin]",
+            "X-Key[en_US.UTF-8]",
+            "GenericName",
+        ] {
+            assert!(is_valid_key(key), "{key}");
+        }
+        for key in [
+            "",
+            "Name[]",
+            "Name[de",
+            "[de]",
+            "Name[d e]",
+            "Na me",
+            "Name[de][fr]",
+        ] {
+            assert!(!is_valid_key(key), "{key}");
+        }
+        let entry = Entry::new("My App", ["myapp"]).key("Name[de]", "Meine App");
+        assert!(entry.validate().is_ok());
+        assert!(entry.to_string().ends_with("Name[de]=Meine App\n"));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::{self, Debug, Display};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Sub, SubAssign};
+use std::path::PathBuf;
+
+use crate::resolve::{self, Resolved};
+
+/// Kinds of standard directories.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Dir {
+    /// User's executable directory.
+    Bin,
+    /// User's cache directory.
+    Cache,
+    /// User's config directory.
+    Config,
+    /// User's data directory.
+    Data,
+    /// User's runtime directory.
+    Runtime,
+    /// User's state directory.
+    State,
+}
+
+impl Dir {
+    /// All kinds of directories.
+    pub const ALL: [Self; 6] = [
+        Self::Bin,
+        Self::Cache,
+        Self::Config,
+        Self::Data,
+        Self::Runtime,
+        Self::State,
+    ];
+
+    /// Returns the path to the directory.
+    #[must_use]
+    pub fn path(self) -> Option<PathBuf> {
+        match self {
+            Self::Bin => crate::bin(),
+            Self::Cache => crate::cache(),
+            Self::Config => crate::config(),
+            Self::Data => crate::data(),
+            Self::Runtime => crate::runtime(),
+            Self::State => crate::state(),
+        }
+    }
+
+    /// Returns the path to the directory, along with its source.
+    #[must_use]
+    pub fn resolve(self) -> Option<Resolved> {
+        resolve::resolve(self, true)
+    }
+
+    /// Returns the environment variable used to configure the directory.
+    #[must_use]
+    pub fn var(self) -> &'static str {
+        match self {
+            Self::Bin => "XDG_BIN_HOME",
+            Self::Cache => "XDG_CACHE_HOME",
+            Self::Config => "XDG_CONFIG_HOME",
+            Self::Data => "XDG_DATA_HOME",
+            Self::Runtime => "XDG_RUNTIME_DIR",
+            Self::State => "XDG_STATE_HOME",
+        }
+    }
+
+    /// Returns the default location of the directory, relative to the user's
+    /// home directory.
+    pub(crate) fn default(self) -> Option<&'static str> {
+        match self {
+            Self::Bin => Some(".local/bin"),
+            Self::Cache => Some(".cache"),
+            Self::Config => Some(".config"),
+            Self::Data => Some(".local/share"),
+            Self::Runtime => None,
+            Self::State => Some(".local/state"),
+        }
+    }
+}
+
+impl Display for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bin => "bin",
+            Self::Cache => "cache",
+            Self::Config => "config",
+            Self::Data => "data",
+            Self::Runtime => "runtime",
+            Self::State => "state",
+        })
+    }
+}
+
+/// A set of kinds of directories.
+///
+/// Sets are combined as flags, allowing operations to be applied to several
+/// directories at once.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::{Dir, DirSet};
+///
+/// // Everything except the cache and runtime directories.
+/// let set = !(DirSet::CACHE | DirSet::RUNTIME);
+/// assert!(set.contains(Dir::Config));
+/// assert!(!set.contains(Dir::Cache));
+/// assert_eq!(set.iter().count(), 4);
+/// ```
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct DirSet(u8);
+
+impl DirSet {
+    /// Empty set.
+    pub const EMPTY: Self = Self(0);
+    /// User's executable directory.
+    pub const BIN: Self = Self::of(Dir::Bin);
+    /// User's cache directory.
+    pub const CACHE: Self = Self::of(Dir::Cache);
+    /// User's config directory.
+    pub const CONFIG: Self = Self::of(Dir::Config);
+    /// User's data directory.
+    pub const DATA: Self = Self::of(Dir::Data);
+    /// User's runtime directory.
+    pub const RUNTIME: Self = Self::of(Dir::Runtime);
+    /// User's state directory.
+    pub const STATE: Self = Self::of(Dir::State);
+    /// Set of all directories.
+    pub const ALL: Self = Self(0b11_1111);
+
+    const fn of(dir: Dir) -> Self {
+        Self(1 << dir as u8)
+    }
+
+    /// Returns the set's flags.
+    #[cfg(feature = "volume")]
+    pub(crate) const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Constructs a set from its flags.
+    #[cfg(feature = "volume")]
+    pub(crate) const fn from_bits(bits: u8) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// Returns the union of two sets.
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Checks whether the set contains a directory.
+    #[must_use]
+    pub fn contains(self, dir: Dir) -> bool {
+        self.0 & Self::of(dir).0 != 0
+    }
+
+    /// Checks whether the set is empty.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Adds a directory to the set.
+    pub fn insert(&mut self, dir: Dir) {
+        *self |= Self::of(dir);
+    }
+
+    /// Removes a directory from the set.
+    pub fn remove(&mut self, dir: Dir) {
+        *self -= Self::of(dir);
+    }
+
+    /// Returns an iterator over the directories of the set, in the order of
+    /// [`Dir::ALL`].
+    pub fn iter(self) -> impl Iterator<Item = Dir> {
+        Dir::ALL.into_iter().filter(move |&dir| self.contains(dir))
+    }
+}
+
+impl Debug for DirSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl From<Dir> for DirSet {
+    fn from(dir: Dir) -> Self {
+        Self::of(dir)
+    }
+}
+
+impl IntoIterator for DirSet {
+    type Item = Dir;
+    type IntoIter = std::vec::IntoIter<Dir>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter().collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl FromIterator<Dir> for DirSet {
+    fn from_iter<I: IntoIterator<Item = Dir>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(Self::of)
+            .fold(Self::EMPTY, Self::union)
+    }
+}
+
+impl BitOr for DirSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for DirSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl BitAnd for DirSet {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for DirSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = *self & rhs;
+    }
+}
+
+impl Sub for DirSet {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 & !rhs.0)
+    }
+}
+
+impl SubAssign for DirSet {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Not for DirSet {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::ALL - self
+    }
+}
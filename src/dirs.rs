@@ -0,0 +1,97 @@
+//! Batch resolution of directories.
+
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+
+use crate::resolve::{self, Resolved};
+use crate::Dir;
+
+/// Every resolved directory of the user, as materialized by [`resolve_all`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dirs {
+    home: Option<PathBuf>,
+    paths: [Option<Resolved>; 6],
+    system_config: Vec<PathBuf>,
+    system_data: Vec<PathBuf>,
+}
+
+impl Dirs {
+    /// Returns the path to the user's home directory.
+    #[must_use]
+    pub fn home(&self) -> Option<&Path> {
+        self.home.as_deref()
+    }
+
+    /// Returns the path to one of the user's directories.
+    #[must_use]
+    pub fn get(&self, dir: Dir) -> Option<&Path> {
+        self.resolved(dir).map(Resolved::path)
+    }
+
+    /// Returns the path to one of the user's directories, along with its
+    /// source.
+    #[must_use]
+    pub fn resolved(&self, dir: Dir) -> Option<&Resolved> {
+        // Directories are stored in declaration order, as in `Dir::ALL`.
+        self.paths[dir as usize].as_ref()
+    }
+
+    /// Returns the preference-ordered system config directories.
+    #[must_use]
+    pub fn config_dirs(&self) -> &[PathBuf] {
+        &self.system_config
+    }
+
+    /// Returns the preference-ordered system data directories.
+    #[must_use]
+    pub fn data_dirs(&self) -> &[PathBuf] {
+        &self.system_data
+    }
+
+    /// Constructs the default directories within a home directory.
+    #[cfg(all(test, any(feature = "backup", feature = "migrate", feature = "xdg")))]
+    pub(crate) fn at(home: &Path) -> Self {
+        Self {
+            home: Some(home.to_path_buf()),
+            paths: Dir::ALL.map(|dir| {
+                let path = home.join(dir.default()?);
+                Some(Resolved::new(path, resolve::Source::Default))
+            }),
+            system_config: Vec::new(),
+            system_data: Vec::new(),
+        }
+    }
+}
+
+/// Resolves every directory of the user in a single pass.
+///
+/// Individually resolving each directory looks up the home directory (and
+/// checks whether the override file is cached) every time. Instead, this does
+/// so at most once, which suits applications sensitive to their startup time.
+/// Directories are resolved exactly as by their corresponding functions.
+/// Operations of an `App` spanning several of its directories (e.g.
+/// `App::ensure`) also use this.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::Dir;
+///
+/// let dirs = xdir::resolve_all();
+/// assert_eq!(dirs.get(Dir::Config), xdir::config().as_deref());
+/// ```
+#[must_use]
+pub fn resolve_all() -> Dirs {
+    let home = OnceCell::new();
+    let home = || home.get_or_init(crate::home).clone();
+    let text = OnceCell::new();
+    let overridden =
+        |dir| resolve::overridden(text.get_or_init(resolve::read).as_ref(), dir, &home);
+    let paths = Dir::ALL.map(|dir| resolve::process(dir, home, &overridden));
+    Dirs {
+        home: home(),
+        paths,
+        system_config: crate::config_dirs(),
+        system_data: crate::data_dirs(),
+    }
+}
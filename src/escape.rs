@@ -0,0 +1,73 @@
+//! Display of paths which are not valid Unicode.
+
+use std::path::Path;
+
+/// Renders a path for display, escaping anything which is not valid Unicode.
+///
+/// Unlike [`Path::display`], which replaces invalid sequences with `�`, this
+/// keeps distinct paths distinct, such that logs and serialized output remain
+/// unambiguous. Invalid bytes on Unix are escaped as `\xNN`, and unpaired
+/// surrogates on Windows as `\u{NNNN}`. Paths which are valid Unicode are
+/// rendered as-is.
+///
+/// This form is intended for humans. To pass a path on losslessly, use its
+/// [`OsStr`](std::ffi::OsStr) instead (e.g. [`Resolved::as_os_str`]).
+///
+/// [`Resolved::as_os_str`]: crate::Resolved::as_os_str
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// use std::ffi::OsStr;
+/// use std::os::unix::ffi::OsStrExt;
+/// use std::path::Path;
+///
+/// let path = Path::new(OsStr::from_bytes(b"/tmp/caf\xe9"));
+/// assert_eq!(xdir::escape_path(path), r"/tmp/caf\xe9");
+/// # }
+/// ```
+#[must_use]
+pub fn escape_path(path: &Path) -> String {
+    if let Some(path) = path.to_str() {
+        return path.to_string();
+    }
+    escape(path)
+}
+
+#[cfg(unix)]
+fn escape(path: &Path) -> String {
+    use std::fmt::Write as _;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut out = String::new();
+    for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+        out.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            let _ = write!(out, "\\x{byte:02x}");
+        }
+    }
+    out
+}
+
+#[cfg(windows)]
+fn escape(path: &Path) -> String {
+    use std::fmt::Write as _;
+    use std::os::windows::ffi::OsStrExt;
+
+    char::decode_utf16(path.as_os_str().encode_wide()).fold(String::new(), |mut out, char| {
+        match char {
+            Ok(char) => out.push(char),
+            Err(err) => {
+                let _ = write!(out, "\\u{{{:04x}}}", err.unpaired_surrogate());
+            }
+        }
+        out
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn escape(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
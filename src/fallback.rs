@@ -0,0 +1,304 @@
+//! Notification of fallbacks.
+//!
+//! Falling back to a default location is usually silent, which can leave users
+//! wondering why an application wrote to an unexpected place (e.g. when a
+//! variable was set to a relative path, and so ignored). A global hook can be
+//! registered to surface these fallbacks, such as by logging a warning. Hooks
+//! may also be registered for a single application (see `App::on_fallback`),
+//! taking precedence over the global hook for its resolutions.
+//!
+//! Only variables which were set, but rejected as empty or invalid, are
+//! reported; unset variables are the ordinary case, and are not. Each distinct
+//! fallback is reported once per process (and hook).
+//!
+//! # Examples
+//!
+//! ```
+//! xdir::fallback::set_hook(|fallback| eprintln!("warning: {fallback}"));
+//! ```
+
+#[cfg(feature = "core")]
+use std::cell::RefCell;
+use std::ffi::OsString;
+#[cfg(feature = "core")]
+use std::fmt::Debug;
+use std::fmt::{self, Display};
+#[cfg(feature = "core")]
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// Registered hook, if any.
+type Hook = Arc<dyn Fn(&Fallback) + Send + Sync>;
+
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// Fallbacks which have already been reported to a hook.
+type Reported = Mutex<Vec<(&'static str, OsString)>>;
+
+static REPORTED: Reported = Mutex::new(Vec::new());
+
+#[cfg(feature = "core")]
+thread_local! {
+    /// Hook of the application whose directories are being resolved, if any.
+    static SCOPED: RefCell<Option<AppHook>> = const { RefCell::new(None) };
+}
+
+/// A fallback to a default location, as a variable was rejected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fallback {
+    var: &'static str,
+    value: OsString,
+    paths: Vec<PathBuf>,
+}
+
+impl Fallback {
+    /// Returns the environment variable which was rejected.
+    #[must_use]
+    pub fn var(&self) -> &'static str {
+        self.var
+    }
+
+    /// Returns the variable's value, if it was ignored as invalid (e.g. a
+    /// relative path).
+    ///
+    /// This is [`None`] if the variable was empty.
+    #[must_use]
+    pub fn invalid(&self) -> Option<&OsString> {
+        Some(&self.value).filter(|value| !value.is_empty())
+    }
+
+    /// Checks whether the variable was ignored as it was empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Returns the default paths used instead.
+    #[must_use]
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+}
+
+impl Display for Fallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.value.is_empty() {
+            write!(f, "ignoring empty ${}", self.var)?;
+        } else {
+            write!(
+                f,
+                "ignoring invalid ${}=\"{}\"",
+                self.var,
+                self.value.display()
+            )?;
+        }
+        let paths = self
+            .paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        write!(f, "; using default: {}", paths.join(", "))
+    }
+}
+
+/// Registers a global hook, invoked whenever resolution rejects a variable,
+/// falling back to a default location.
+///
+/// This replaces any previously registered hook.
+pub fn set_hook(hook: impl Fn(&Fallback) + Send + Sync + 'static) {
+    *HOOK.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(hook));
+}
+
+/// Unregisters the global hook, if any.
+pub fn clear_hook() {
+    *HOOK.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Hook of a single application, as registered by `App::on_fallback`.
+///
+/// Hooks are compared by identity, such that applications sharing a hook
+/// compare equal.
+#[cfg(feature = "core")]
+#[derive(Clone)]
+pub(crate) struct AppHook {
+    hook: Hook,
+    reported: Arc<Reported>,
+}
+
+#[cfg(feature = "core")]
+impl AppHook {
+    /// Constructs an application's hook.
+    pub(crate) fn new(hook: impl Fn(&Fallback) + Send + Sync + 'static) -> Self {
+        Self {
+            hook: Arc::new(hook),
+            reported: Arc::default(),
+        }
+    }
+
+    /// Runs a resolution, reporting its fallbacks to this hook in place of the
+    /// global hook.
+    pub(crate) fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        /// Restores the previous hook, even if resolution panics.
+        struct Restore(Option<AppHook>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SCOPED.set(self.0.take());
+            }
+        }
+
+        let _restore = Restore(SCOPED.replace(Some(self.clone())));
+        f()
+    }
+}
+
+#[cfg(feature = "core")]
+impl Debug for AppHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppHook").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "core")]
+impl PartialEq for AppHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.hook, &other.hook)
+    }
+}
+
+#[cfg(feature = "core")]
+impl Eq for AppHook {}
+
+#[cfg(feature = "core")]
+impl Hash for AppHook {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.hook).cast::<()>().hash(state);
+    }
+}
+
+/// Reports a rejected variable to the registered hook, unless it was already
+/// reported to it.
+///
+/// The hook of the application being resolved, if any, is used in place of
+/// the global hook.
+pub(crate) fn report(var: &'static str, value: OsString, paths: &[&Path]) {
+    crate::telemetry::rejected(var);
+    #[cfg(feature = "core")]
+    if let Some(app) = SCOPED.with_borrow(Clone::clone) {
+        return notify(&app.hook, &app.reported, var, value, paths);
+    }
+    let Some(hook) = HOOK.read().unwrap_or_else(PoisonError::into_inner).clone() else {
+        return;
+    };
+    notify(&hook, &REPORTED, var, value, paths);
+}
+
+/// Invokes a hook, unless the fallback was already reported to it.
+fn notify(hook: &Hook, reported: &Reported, var: &'static str, value: OsString, paths: &[&Path]) {
+    {
+        let mut reported = reported.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (var, value.clone());
+        if reported.contains(&key) {
+            return;
+        }
+        reported.push(key);
+    }
+    hook(&Fallback {
+        var,
+        value,
+        paths: paths.iter().map(|path| path.to_path_buf()).collect(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve::{self, EmptyVars};
+    use crate::Dir;
+
+    #[test]
+    fn reports_rejected_values() {
+        static SEEN: Mutex<Vec<Fallback>> = Mutex::new(Vec::new());
+
+        set_hook(|fallback| SEEN.lock().unwrap().push(fallback.clone()));
+        let home = || {
+            Some(PathBuf::from(if cfg!(windows) {
+                r"C:\home"
+            } else {
+                "/home"
+            }))
+        };
+        let vars = |var: &str| match var {
+            "XDG_CACHE_HOME" => Some(OsString::from("relative/fallback-test")),
+            "XDG_STATE_HOME" => Some(OsString::new()),
+            _ => None,
+        };
+        for _ in 0..2 {
+            for dir in [Dir::Cache, Dir::State, Dir::Data] {
+                resolve::lookup(dir, vars, home, EmptyVars::Unset, Some(&|_| None)).unwrap();
+            }
+        }
+        clear_hook();
+        let seen = SEEN.lock().unwrap();
+        let seen = |var| {
+            seen.iter()
+                .filter(|fallback| fallback.var() == var)
+                .collect::<Vec<_>>()
+        };
+        // Each rejection is reported once, whereas unset variables are not.
+        let [cache] = seen("XDG_CACHE_HOME")[..] else {
+            panic!("expected a single fallback of $XDG_CACHE_HOME");
+        };
+        assert_eq!(cache.invalid().unwrap(), "relative/fallback-test");
+        assert!(!cache.is_empty());
+        assert!(cache
+            .to_string()
+            .starts_with("ignoring invalid $XDG_CACHE_HOME=\"relative"));
+        let [state] = seen("XDG_STATE_HOME")[..] else {
+            panic!("expected a single fallback of $XDG_STATE_HOME");
+        };
+        assert!(state.is_empty());
+        assert_eq!(state.invalid(), None);
+        assert!(state
+            .to_string()
+            .starts_with("ignoring empty $XDG_STATE_HOME; using default: "));
+        assert!(seen("XDG_DATA_HOME").is_empty());
+    }
+
+    #[cfg(feature = "core")]
+    #[test]
+    fn app_hooks_take_precedence() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let hook = {
+            let seen = Arc::clone(&seen);
+            AppHook::new(move |fallback| seen.lock().unwrap().push(fallback.clone()))
+        };
+        let other = AppHook::new(|_| ());
+        assert_eq!(hook, hook.clone());
+        assert_ne!(hook, other);
+        let home = || {
+            Some(PathBuf::from(if cfg!(windows) {
+                r"C:\home"
+            } else {
+                "/home"
+            }))
+        };
+        let vars = |var: &str| {
+            (var == "XDG_CONFIG_HOME").then(|| OsString::from("relative/app-hook-test"))
+        };
+        let lookup = || resolve::lookup(Dir::Config, vars, home, EmptyVars::Unset, Some(&|_| None));
+        for _ in 0..2 {
+            hook.scope(lookup).unwrap();
+            other.scope(lookup).unwrap();
+        }
+        // The hook is no longer consulted once its resolution is done.
+        lookup().unwrap();
+        let seen = seen.lock().unwrap();
+        let [fallback] = &seen[..] else {
+            panic!("expected a single fallback of $XDG_CONFIG_HOME");
+        };
+        assert_eq!(fallback.var(), "XDG_CONFIG_HOME");
+        assert_eq!(fallback.invalid().unwrap(), "relative/app-hook-test");
+    }
+}
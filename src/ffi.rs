@@ -0,0 +1,222 @@
+//! C foreign function interface.
+//!
+//! Exposes directory resolution to other languages, such that they share the
+//! same rules as Rust code. The interface is declared by `include/xdir.h`, and
+//! built as a shared library with:
+//!
+//! ```sh
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! Paths are written into caller-provided buffers, following the conventions
+//! of `snprintf`: each function returns the length of the path in bytes
+//! (excluding the terminating NUL), writing it only if the buffer is large
+//! enough to also hold the terminator. A return value of zero indicates that
+//! the directory could not be found. As such, the buffer may be sized by first
+//! calling with a null pointer.
+//!
+//! On Windows, paths are encoded as UTF-8; those which cannot be are treated as
+//! not found.
+//!
+//! # Examples
+//!
+//! ```c
+//! #include <stdio.h>
+//! #include <stdlib.h>
+//!
+//! #include "xdir.h"
+//!
+//! int main(void) {
+//!     size_t len = xdir_app_dir("myapp", XDIR_CONFIG, NULL, 0);
+//!     if (len == 0)
+//!         return 1;
+//!     char *path = malloc(len + 1);
+//!     xdir_app_dir("myapp", XDIR_CONFIG, path, len + 1);
+//!     puts(path);
+//!     free(path);
+//! }
+//! ```
+
+use std::ffi::{c_char, c_int, CStr};
+use std::path::Path;
+
+use crate::{App, Dir};
+
+/// User's executable directory.
+pub const XDIR_BIN: c_int = 0;
+/// User's cache directory.
+pub const XDIR_CACHE: c_int = 1;
+/// User's config directory.
+pub const XDIR_CONFIG: c_int = 2;
+/// User's data directory.
+pub const XDIR_DATA: c_int = 3;
+/// User's runtime directory.
+pub const XDIR_RUNTIME: c_int = 4;
+/// User's state directory.
+pub const XDIR_STATE: c_int = 5;
+
+/// Writes the path to the user's executable directory into a buffer.
+///
+/// See [`xdir_dir`] for details.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_bin(buf: *mut c_char, len: usize) -> usize {
+    // SAFETY: Upheld by the caller.
+    unsafe { xdir_dir(XDIR_BIN, buf, len) }
+}
+
+/// Writes the path to the user's cache directory into a buffer.
+///
+/// See [`xdir_dir`] for details.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_cache(buf: *mut c_char, len: usize) -> usize {
+    // SAFETY: Upheld by the caller.
+    unsafe { xdir_dir(XDIR_CACHE, buf, len) }
+}
+
+/// Writes the path to the user's config directory into a buffer.
+///
+/// See [`xdir_dir`] for details.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_config(buf: *mut c_char, len: usize) -> usize {
+    // SAFETY: Upheld by the caller.
+    unsafe { xdir_dir(XDIR_CONFIG, buf, len) }
+}
+
+/// Writes the path to the user's data directory into a buffer.
+///
+/// See [`xdir_dir`] for details.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_data(buf: *mut c_char, len: usize) -> usize {
+    // SAFETY: Upheld by the caller.
+    unsafe { xdir_dir(XDIR_DATA, buf, len) }
+}
+
+/// Writes the path to the user's runtime directory into a buffer.
+///
+/// See [`xdir_dir`] for details.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_runtime(buf: *mut c_char, len: usize) -> usize {
+    // SAFETY: Upheld by the caller.
+    unsafe { xdir_dir(XDIR_RUNTIME, buf, len) }
+}
+
+/// Writes the path to the user's state directory into a buffer.
+///
+/// See [`xdir_dir`] for details.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_state(buf: *mut c_char, len: usize) -> usize {
+    // SAFETY: Upheld by the caller.
+    unsafe { xdir_dir(XDIR_STATE, buf, len) }
+}
+
+/// Writes the path to a directory into a buffer, returning its length.
+///
+/// The directory is given by one of the `XDIR_*` constants. Returns zero if
+/// the directory is unknown or could not be found.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_dir(dir: c_int, buf: *mut c_char, len: usize) -> usize {
+    let path = kind(dir).and_then(Dir::path);
+    // SAFETY: Upheld by the caller.
+    unsafe { write(path.as_deref(), buf, len) }
+}
+
+/// Writes the path to an application's directory into a buffer, returning its
+/// length.
+///
+/// The application is given by its name, as per [`App::new`], and the
+/// directory by one of the `XDIR_*` constants. Returns zero if the name is not
+/// valid UTF-8, or if the directory is unknown or could not be found.
+///
+/// # Safety
+///
+/// `app` must be a valid NUL-terminated string, and `buf` must be null, or
+/// valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdir_app_dir(
+    app: *const c_char,
+    dir: c_int,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    if app.is_null() {
+        return 0;
+    }
+    // SAFETY: Upheld by the caller.
+    let Ok(name) = unsafe { CStr::from_ptr(app) }.to_str() else {
+        return 0;
+    };
+    let path = kind(dir).and_then(|dir| App::new(name).dir(dir));
+    // SAFETY: Upheld by the caller.
+    unsafe { write(path.as_deref(), buf, len) }
+}
+
+/// Converts a directory constant into its kind.
+fn kind(dir: c_int) -> Option<Dir> {
+    usize::try_from(dir)
+        .ok()
+        .and_then(|idx| Dir::ALL.get(idx).copied())
+}
+
+/// Writes a path into a buffer if it fits, returning its length.
+///
+/// # Safety
+///
+/// `buf` must be null, or valid for writes of `len` bytes.
+unsafe fn write(path: Option<&Path>, buf: *mut c_char, len: usize) -> usize {
+    let Some(bytes) = path.and_then(encode) else {
+        return 0;
+    };
+    if bytes.contains(&0) {
+        return 0;
+    }
+    if !buf.is_null() && bytes.len() < len {
+        // SAFETY: The buffer holds at least `bytes.len() + 1` bytes.
+        unsafe {
+            buf.cast::<u8>()
+                .copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+            buf.add(bytes.len()).write(0);
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn encode(path: &Path) -> Option<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Some(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn encode(path: &Path) -> Option<&[u8]> {
+    path.to_str().map(str::as_bytes)
+}
@@ -0,0 +1,149 @@
+//! Fingerprints of configuration.
+
+use std::fmt::{self, Display};
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::{md5, App};
+
+/// A digest of an application's configuration, used to detect changes.
+///
+/// Fingerprints are computed from the metadata of the files within the
+/// application's config search path (i.e. their paths, kinds, sizes and
+/// modification times), without reading their contents. As such, they are
+/// cheap enough to compute periodically, allowing long-running processes to
+/// detect out-of-band edits without a file watcher. Edits which preserve a
+/// file's size and modification time (e.g. within the granularity of the
+/// filesystem's timestamps) go undetected.
+///
+/// A fingerprint is stable for the same configuration, and may be persisted
+/// through its [`Display`] form.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use xdir::App;
+///
+/// let app = App::new("myapp");
+/// let mut fingerprint = app.config_fingerprint().unwrap();
+/// loop {
+///     thread::sleep(Duration::from_secs(10));
+///     if app.changed_since(&fingerprint).unwrap() {
+///         println!("configuration changed; reloading");
+///         fingerprint = app.config_fingerprint().unwrap();
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Fingerprint([u8; 16]);
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl App {
+    /// Computes a [fingerprint](Fingerprint) of the application's
+    /// configuration.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the config directory could not be found, or if any directory
+    /// within the config search path could not be read.
+    pub fn config_fingerprint(&self) -> io::Result<Fingerprint> {
+        if self.config().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not find config directory",
+            ));
+        }
+        let mut msg = Vec::new();
+        for root in self.config_dirs() {
+            msg.extend_from_slice(root.as_os_str().as_encoded_bytes());
+            msg.push(0);
+            match fs::symlink_metadata(&root) {
+                Ok(meta) => {
+                    record(&mut msg, &meta);
+                    if meta.is_dir() {
+                        walk(&mut msg, &root, Path::new(""))?;
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => msg.push(b'-'),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(Fingerprint(md5::digest(&msg)))
+    }
+
+    /// Checks whether the application's configuration changed since a
+    /// [fingerprint](Fingerprint) was computed.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the configuration's fingerprint could not be computed.
+    pub fn changed_since(&self, fingerprint: &Fingerprint) -> io::Result<bool> {
+        Ok(self.config_fingerprint()? != *fingerprint)
+    }
+}
+
+/// Recursively records the entries of a directory, in order of name.
+fn walk(msg: &mut Vec<u8>, root: &Path, rel: &Path) -> io::Result<()> {
+    let mut entries = fs::read_dir(root.join(rel))?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+    for entry in entries {
+        let rel = rel.join(entry.file_name());
+        let meta = match entry.metadata() {
+            Ok(meta) => meta,
+            // Entries may be removed while walking.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        msg.extend_from_slice(rel.as_os_str().as_encoded_bytes());
+        msg.push(0);
+        record(msg, &meta);
+        if meta.is_symlink() {
+            if let Ok(target) = fs::read_link(entry.path()) {
+                msg.extend_from_slice(target.as_os_str().as_encoded_bytes());
+            }
+            msg.push(0);
+        } else if meta.is_dir() {
+            walk(msg, root, &rel)?;
+        }
+    }
+    Ok(())
+}
+
+/// Records the metadata of an entry.
+fn record(msg: &mut Vec<u8>, meta: &Metadata) {
+    msg.push(if meta.is_dir() {
+        b'd'
+    } else if meta.is_symlink() {
+        b'l'
+    } else {
+        b'f'
+    });
+    msg.extend_from_slice(&meta.len().to_le_bytes());
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    msg.extend_from_slice(&mtime.as_secs().to_le_bytes());
+    msg.extend_from_slice(&mtime.subsec_nanos().to_le_bytes());
+    // Files replaced atomically are new files, even with the same metadata.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        msg.extend_from_slice(&meta.ino().to_le_bytes());
+    }
+}
@@ -0,0 +1,198 @@
+//! Pattern matching across search paths.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::Dir;
+
+/// Finds the files matching a pattern across a directory's search path.
+///
+/// The pattern is matched relative to the user's directory of the given kind,
+/// followed by each of its system directories (for [`Dir::Config`] and
+/// [`Dir::Data`]). Matches are ordered by their directory's preference, then
+/// by path. Matches shadowed by a more preferred one of the same relative path
+/// are skipped.
+///
+/// Each component of the pattern may contain the following wildcards:
+///
+/// - `*` matches any sequence of characters.
+/// - `?` matches any single character.
+/// - `[abc]` matches any character within the brackets, which may include
+///   ranges (e.g. `[a-z]`), and be negated with a leading `!` (e.g. `[!a]`).
+///
+/// A component of exactly `**` matches any number of nested directories.
+/// Wildcards do not match a leading `.`, such that hidden files are only
+/// matched by patterns which name them explicitly. Patterns which are absolute,
+/// or escape their directory, match nothing.
+///
+/// # Examples
+///
+/// ```no_run
+/// for theme in xdir::glob(xdir::Dir::Data, "myapp/themes/*.toml") {
+///     println!("{}", theme.display());
+/// }
+/// ```
+#[must_use]
+pub fn glob(dir: Dir, pattern: impl AsRef<Path>) -> Vec<PathBuf> {
+    let Some(pattern) = parse(pattern.as_ref()) else {
+        return Vec::new();
+    };
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    for root in roots(dir) {
+        let mut matches = Vec::new();
+        walk(&root, &PathBuf::new(), &pattern, &mut matches);
+        matches.sort();
+        for path in matches {
+            if seen.insert(path.clone()) {
+                found.push(root.join(path));
+            }
+        }
+    }
+    found
+}
+
+/// Returns the search path of a directory, in order of preference.
+fn roots(dir: Dir) -> Vec<PathBuf> {
+    let system = match dir {
+        Dir::Config => crate::config_dirs(),
+        Dir::Data => crate::data_dirs(),
+        _ => Vec::new(),
+    };
+    dir.path().into_iter().chain(system).collect()
+}
+
+/// Splits a pattern into its components, if it is relative.
+fn parse(pattern: &Path) -> Option<Vec<String>> {
+    let mut parts = Vec::new();
+    for part in pattern.components() {
+        match part {
+            Component::Normal(part) => parts.push(part.to_str()?.to_string()),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then_some(parts)
+}
+
+/// Collects the paths (relative to `dir`) matching the remaining components.
+fn walk(dir: &Path, rel: &Path, parts: &[String], out: &mut Vec<PathBuf>) {
+    let Some((part, rest)) = parts.split_first() else {
+        return;
+    };
+    if part == "**" {
+        if !rest.is_empty() {
+            walk(dir, rel, rest, out);
+        }
+        for (name, is_dir) in entries(dir) {
+            if name.starts_with('.') {
+                continue;
+            }
+            if rest.is_empty() {
+                out.push(rel.join(&name));
+            }
+            if is_dir {
+                walk(&dir.join(&name), &rel.join(&name), parts, out);
+            }
+        }
+    } else if !part.contains(['*', '?', '[']) {
+        let path = dir.join(part);
+        if rest.is_empty() {
+            if fs::symlink_metadata(&path).is_ok() {
+                out.push(rel.join(part));
+            }
+        } else if path.is_dir() {
+            walk(&path, &rel.join(part), rest, out);
+        }
+    } else {
+        let pattern = part.chars().collect::<Vec<_>>();
+        for (name, is_dir) in entries(dir) {
+            if name.starts_with('.') && !part.starts_with('.') {
+                continue;
+            }
+            if !matches(&pattern, &name.chars().collect::<Vec<_>>()) {
+                continue;
+            }
+            if rest.is_empty() {
+                out.push(rel.join(&name));
+            } else if is_dir {
+                walk(&dir.join(&name), &rel.join(&name), rest, out);
+            }
+        }
+    }
+}
+
+/// Lists the names of a directory's entries, along with whether each is a
+/// directory.
+///
+/// Symbolic links are not followed, such that `**` cannot loop.
+fn entries(dir: &Path) -> Vec<(String, bool)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+            Some((name, is_dir))
+        })
+        .collect()
+}
+
+/// Checks whether a name matches a component's pattern.
+fn matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+        Some(('[', rest)) => match class(rest) {
+            Some((set, rest)) => name
+                .split_first()
+                .is_some_and(|(&char, name)| set(char) && matches(rest, name)),
+            None => literal('[', rest, name),
+        },
+        Some((&char, rest)) => literal(char, rest, name),
+    }
+}
+
+/// Matches a literal character, followed by the rest of the pattern.
+fn literal(char: char, pattern: &[char], name: &[char]) -> bool {
+    name.split_first()
+        .is_some_and(|(&next, name)| next == char && matches(pattern, name))
+}
+
+/// Parses a bracketed character class, returning its predicate and the rest of
+/// the pattern, if it is closed.
+fn class(pattern: &[char]) -> Option<(impl Fn(char) -> bool + '_, &[char])> {
+    let (negated, body) = match pattern.split_first() {
+        Some(('!' | '^', body)) => (true, body),
+        _ => (false, pattern),
+    };
+    // A leading `]` is part of the class, rather than closing it.
+    let end = body
+        .iter()
+        .skip(1)
+        .position(|&char| char == ']')
+        .map(|end| end + 1)?;
+    let (set, rest) = (&body[..end], &body[end + 1..]);
+    let contains = move |char: char| {
+        let mut i = 0;
+        while i < set.len() {
+            if i + 2 < set.len() && set[i + 1] == '-' {
+                if (set[i]..=set[i + 2]).contains(&char) {
+                    return true;
+                }
+                i += 3;
+            } else {
+                if set[i] == char {
+                    return true;
+                }
+                i += 1;
+            }
+        }
+        false
+    };
+    Some((move |char| contains(char) != negated, rest))
+}
@@ -0,0 +1,104 @@
+//! Installation of executables.
+//!
+//! Self-installing applications can copy an executable into the user's
+//! [executable directory](crate::bin), along with its manual page.
+//!
+//! # Examples
+//!
+//! For an application to install itself:
+//!
+//! ```no_run
+//! use xdir::install::Binary;
+//!
+//! let exe = std::env::current_exe().unwrap();
+//! let path = Binary::new("myapp", exe)
+//!     .man("1", ".TH MYAPP 1\n.SH NAME\nmyapp \\- does things\n")
+//!     .install()
+//!     .unwrap();
+//! ```
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::data::man;
+use crate::{atomic, bin};
+
+/// An executable to be installed.
+#[derive(Clone, Debug)]
+pub struct Binary {
+    name: String,
+    src: PathBuf,
+    man: Option<(String, String)>,
+}
+
+impl Binary {
+    /// Constructs a new installable executable from its source path.
+    pub fn new(name: impl Into<String>, src: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            src: src.into(),
+            man: None,
+        }
+    }
+
+    /// Sets a manual page to be installed alongside the executable.
+    #[must_use]
+    pub fn man(mut self, section: impl Into<String>, page: impl Into<String>) -> Self {
+        self.man = Some((section.into(), page.into()));
+        self
+    }
+
+    /// Installs the executable, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the name is invalid, if the executable or manual directories
+    /// could not be found, or if any file could not be written.
+    pub fn install(&self) -> io::Result<PathBuf> {
+        validate_name(&self.name)?;
+        let mut path = bin()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find bin directory"))?
+            .join(&self.name);
+        if cfg!(windows) && path.extension().is_none() {
+            path.set_extension("exe");
+        }
+        atomic::copy(&self.src, &path)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        if let Some((section, page)) = &self.man {
+            man_page(&self.name, section, page)?;
+        }
+        Ok(path)
+    }
+}
+
+/// Installs a manual page, returning its path.
+///
+/// # Errors
+///
+/// Errors if the name or section are invalid, if the manual directory could not
+/// be found, or if the file could not be written.
+pub fn man_page(name: &str, section: &str, page: &str) -> io::Result<PathBuf> {
+    validate_name(name)?;
+    validate_name(section)?;
+    let path = man(section)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find data directory"))?
+        .join(format!("{name}.{section}"));
+    atomic::write(&path, page)?;
+    Ok(path)
+}
+
+/// Checks that a name is a single, non-empty path component.
+fn validate_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid name: {name:?}"),
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+//! Journals of destructive operations.
+//!
+//! Automated cleanup is easier to trust with a safety net. When given a
+//! [`Journal`], operations which remove or move files (such as
+//! [purging](crate::App::purge), pruning caches with the `cache-gc` feature,
+//! and migrating with the `migrate` feature) record what they did. A journal
+//! can later be [undone](undo), restoring moved files to where they were, and
+//! reporting those which were removed for good.
+//!
+//! Journals are stored within the state directory, and are identified by the
+//! time they were started.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::journal::Journal;
+//! use xdir::{App, DirSet};
+//!
+//! let journal = Journal::new().unwrap();
+//! let app = App::new("myapp").journal(journal.clone());
+//! app.purge(DirSet::CACHE).unwrap();
+//!
+//! let undone = xdir::undo(journal.id()).unwrap();
+//! for path in undone.lost() {
+//!     eprintln!("could not restore {}", path.display());
+//! }
+//! ```
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use crate::time::DateTime;
+use crate::{readonly, sys, transfer, uri};
+
+/// A journal of destructive operations.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Journal {
+    id: String,
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Starts a new journal.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the state directory could not be found, or if the journal
+    /// could not be created.
+    pub fn new() -> io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        readonly::check()?;
+        let dir = dir()?;
+        sys::create_dir_all(&dir)?;
+        let time = DateTime::utc(SystemTime::now()).basic();
+        let id = format!(
+            "{time}-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.join(&id);
+        File::create_new(&path)?;
+        Ok(Self { id, path })
+    }
+
+    /// Opens an existing journal.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the identifier is invalid, if the state directory could not be
+    /// found, or if the journal does not exist.
+    pub fn open(id: &str) -> io::Result<Self> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid journal: {id:?}"),
+            ));
+        }
+        let path = dir()?.join(id);
+        fs::metadata(&path)?;
+        Ok(Self {
+            id: id.to_string(),
+            path,
+        })
+    }
+
+    /// Starts a new journal at a path, outside of the state directory.
+    #[cfg(test)]
+    pub(crate) fn at(path: PathBuf) -> io::Result<Self> {
+        File::create_new(&path)?;
+        Ok(Self {
+            id: String::from("test"),
+            path,
+        })
+    }
+
+    /// Returns the journal's identifier.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the path to the journal.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the records of the journal, in the order they were made.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the journal could not be read, or is invalid.
+    pub fn records(&self) -> io::Result<Vec<Record>> {
+        fs::read_to_string(&self.path)?
+            .lines()
+            .map(|line| {
+                let mut parts = line.split(' ');
+                let path = |part: Option<&str>| part.and_then(uri::decode);
+                match (parts.next(), path(parts.next()), path(parts.next())) {
+                    (Some("moved"), Some(from), Some(to)) => Some(Record::Moved { from, to }),
+                    (Some("removed"), Some(path), None) => Some(Record::Removed(path)),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid journal record: {line}"),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Records that a file (or directory) was moved.
+    #[cfg(any(test, feature = "migrate"))]
+    pub(crate) fn moved(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.append(&format!("moved {} {}", uri::encode(from), uri::encode(to)))
+    }
+
+    /// Records that a file (or directory) was removed.
+    pub(crate) fn removed(&self, path: &Path) -> io::Result<()> {
+        self.append(&format!("removed {}", uri::encode(path)))
+    }
+
+    /// Appends a record to the journal.
+    fn append(&self, record: &str) -> io::Result<()> {
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        file.write_all(format!("{record}\n").as_bytes())?;
+        file.sync_data()
+    }
+}
+
+/// A record of a journal.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Record {
+    /// A file (or directory) was moved.
+    Moved {
+        /// Original location.
+        from: PathBuf,
+        /// New location.
+        to: PathBuf,
+    },
+    /// A file (or directory) was removed.
+    Removed(PathBuf),
+}
+
+/// Outcome of [undoing](undo) a journal.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Undone {
+    restored: Vec<PathBuf>,
+    lost: Vec<PathBuf>,
+}
+
+impl Undone {
+    /// Returns the paths which were restored.
+    #[must_use]
+    pub fn restored(&self) -> &[PathBuf] {
+        &self.restored
+    }
+
+    /// Returns the paths which could not be restored, as they were removed (or
+    /// have since been moved or replaced).
+    #[must_use]
+    pub fn lost(&self) -> &[PathBuf] {
+        &self.lost
+    }
+}
+
+/// Undoes a journal, restoring moved files to their original location.
+///
+/// Records are undone in reverse. Files which were moved are moved back,
+/// unless their original location has since been occupied. Files which were
+/// removed cannot be restored, and are reported as lost. Once undone, the
+/// journal is removed.
+///
+/// # Errors
+///
+/// Errors if the journal could not be opened or read, or if a file could not
+/// be moved back. In the latter case the journal is kept, such that undoing
+/// can be retried.
+pub fn undo(id: &str) -> io::Result<Undone> {
+    let journal = Journal::open(id)?;
+    let mut undone = Undone::default();
+    for record in journal.records()?.into_iter().rev() {
+        match record {
+            Record::Moved { from, to } => {
+                let exists = |path: &Path| fs::symlink_metadata(path).is_ok();
+                match (exists(&from), exists(&to)) {
+                    (false, true) => {
+                        if let Some(parent) = from.parent() {
+                            sys::create_dir_all(parent)?;
+                        }
+                        transfer::move_path(&to, &from)?;
+                        undone.restored.push(from);
+                    }
+                    // Restored by an earlier attempt.
+                    (true, false) => undone.restored.push(from),
+                    _ => undone.lost.push(from),
+                }
+            }
+            Record::Removed(path) => undone.lost.push(path),
+        }
+    }
+    fs::remove_file(journal.path())?;
+    Ok(undone)
+}
+
+/// Lists the journals which have not been undone, oldest first.
+///
+/// # Errors
+///
+/// Errors if the state directory could not be found, or if the journals could
+/// not be read.
+pub fn list() -> io::Result<Vec<Journal>> {
+    let dir = dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut journals = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let Some(id) = entry.file_name().to_str().map(String::from) else {
+            continue;
+        };
+        if entry.file_type()?.is_file() && !id.starts_with('.') {
+            journals.push(Journal {
+                id,
+                path: entry.path(),
+            });
+        }
+    }
+    journals.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(journals)
+}
+
+/// Returns the directory of journals.
+fn dir() -> io::Result<PathBuf> {
+    crate::state()
+        .map(|path| path.join("xdir").join("journal"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find state directory"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn records_round_trip() {
+        let tmp = TempDir::new();
+        let journal = Journal::at(tmp.join("journal")).unwrap();
+        let (from, to) = (tmp.join("from dir"), tmp.join("to%dir"));
+        journal.moved(&from, &to).unwrap();
+        journal.removed(&tmp.join("gone")).unwrap();
+        assert_eq!(
+            journal.records().unwrap(),
+            [
+                Record::Moved { from, to },
+                Record::Removed(tmp.join("gone")),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_reject_invalid() {
+        let tmp = TempDir::new();
+        let journal = Journal::at(tmp.join("journal")).unwrap();
+        fs::write(journal.path(), "copied /a /b\n").unwrap();
+        assert_eq!(
+            journal.records().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
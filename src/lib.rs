@@ -12,22 +12,152 @@
 //!
 //! ## Directories
 //!
-//! |  Function   |    Environment     |        Default       |
-//! |-------------|--------------------|----------------------|
-//! | [`fn@home`] | `$HOME`            | Platform-specific    |
-//! | [`cache`]   | `$XDG_CACHE_HOME`  | `$HOME/.cache`       |
-//! | [`config`]  | `$XDG_CONFIG_HOME` | `$HOME/.config`      |
-//! | [`bin`]     | `$XDG_BIN_HOME`    | `$HOME/.local/bin`   |
-//! | [`data`]    | `$XDG_DATA_HOME`   | `$HOME/.local/share` |
-//! | [`state`]   | `$XDG_STATE_HOME`  | `$HOME/.local/state` |
-//! | [`runtime`] | `$XDG_RUNTIME_DIR` | None                 |
+//! |    Function    |    Environment     |        Default       |
+//! |----------------|--------------------|----------------------|
+//! | [`fn@home`]    | `$HOME`            | Platform-specific    |
+//! | [`fn@cache`]   | `$XDG_CACHE_HOME`  | `$HOME/.cache`       |
+//! | [`fn@config`]  | `$XDG_CONFIG_HOME` | `$HOME/.config`      |
+//! | [`bin`]        | `$XDG_BIN_HOME`    | `$HOME/.local/bin`   |
+//! | [`fn@data`]    | `$XDG_DATA_HOME`   | `$HOME/.local/share` |
+//! | [`fn@state`]   | `$XDG_STATE_HOME`  | `$HOME/.local/state` |
+//! | [`fn@runtime`] | `$XDG_RUNTIME_DIR` | None                 |
+//!
+//! Relative paths set through the environment are ignored, and empty ones are
+//! treated as unset (unless [configured](EmptyVars) otherwise). Unless set
+//! through the environment, directories may also be relocated by the user's
+//! override file. To find out why a particular directory was chosen, the
+//! corresponding `_resolved` functions (e.g. [`config_resolved`]) also return
+//! its [source](Source). Directories can also be resolved from an explicit
+//! [environment](Env), rather than that of the current process.
+//!
+//! Directories are resolved anew on every call. Where resolution may instead be
+//! frozen at first use, `statics` (with the `statics` feature) provides each
+//! directory resolved once. Each resolution made by the process may also be
+//! recorded for later diagnosis (see `record`). Paths within the directories
+//! may be serialized portably (see `portable`), such that they remain valid on
+//! other machines.
+//!
+//! Well-known subdirectories, such as the user's [fonts](data::fonts), are
+//! provided by the modules of their corresponding directory.
+//!
+//! ## Applications
+//!
+//! Directories belonging to a single application are provided by `App` (with
+//! the `core` feature), which scopes each directory by the application's name.
+//! System services may instead use machine-wide directories, by their `Scope`.
+//!
+//! ## Search Paths
+//!
+//! Preference-ordered directories in which to search for files, in addition
+//! to their corresponding user directory.
+//!
+//! |    Function    |    Environment     |           Default             |
+//! |----------------|--------------------|-------------------------------|
+//! | [`config_dirs`] | `$XDG_CONFIG_DIRS` | `/etc/xdg`                    |
+//! | [`data_dirs`]   | `$XDG_DATA_DIRS`   | `/usr/local/share:/usr/share` |
+//!
+//! These defaults only apply on Unix; other platforms have no system search
+//! path unless it is provided through the environment.
+//!
+//! Search paths are parsed by [`parse_search_path`], which ignores empty,
+//! relative and duplicate entries. Files can be discovered across a search
+//! path by `glob` (with the `search-path` feature), such that user files
+//! shadow those of the system. Where
+//! entries may refer to the same directory through different paths (e.g. by
+//! case, or by symbolic links), they can be compared by [`same_location`].
+//!
+//! ## Features
+//!
+//! Optional functionality is enabled through the following cargo features,
+//! such that each subsystem is only compiled where it is used. Without any
+//! features, only the standard directories themselves are resolved.
+//!
+//! - `audit`: Audit the user's directories for unsafe configurations (see
+//!   `audit`).
+//! - `autostart`: Manage autostart entries (see `autostart`).
+//! - `backup`: Back up and restore applications (see `App::backup`).
+//! - `bake`: Bake directory locations into constants at build time, for
+//!   embedded deployments (see `baked`).
+//! - `cache`: Cache computed values (see `cache`).
+//! - `cache-gc`: Prune caches by age and size (see `cache::maybe_prune`).
+//! - `cli`: Build the `xdir` command-line interface.
+//! - `core` (default): Scope directories by application (see `App`).
+//! - `cygwin`: Translate paths of Cygwin and MSYS2 environments (see
+//!   `cygwin`).
+//! - `desktop`: Write desktop entries (see `desktop`).
+//! - `ffi`: Expose a C interface, for use as a shared library (see `ffi`).
+//! - `full`: Enable every subsystem, besides `bake`, `ffi`, `metrics`,
+//!   `notify`, `toml` and `wasm`.
+//! - `install`: Install executables and manual pages (see `install`).
+//! - `io-helpers`: Write files within an application's directories (see
+//!   `App::write_file`), seed configuration, mark initialization, create log
+//!   files, create secret files (see `secret_file`), and promote files between
+//!   directories (see `promote`).
+//! - `journal`: Journal destructive operations, such that they can be undone
+//!   (see `journal`).
+//! - `link`: Link dotfiles into place (see `link`).
+//! - `metrics`: Emit metrics of directory health through the `metrics`
+//!   facade (see below).
+//! - `migrate`: Migrate an application's directories (see `migrate`).
+//! - `mime`: Register MIME types (see `mime`).
+//! - `notify`: Watch directories for changes (see `watch`).
+//! - `overrides`: Relocate directories through a per-user override file (see
+//!   `overrides`).
+//! - `owner`: Verify the ownership of directories (see `owner`, and
+//!   `App::verify_owner`).
+//! - `portable`: Serialize paths portably between machines (see `portable`).
+//! - `portal`: Identify documents of the document portal (see `portal`).
+//! - `preflight`: Run startup self-checks of applications (see `preflight`).
+//! - `record`: Record the process's resolutions, for later diagnosis (see
+//!   `record`).
+//! - `runtime`: Recover runtime directories (see `runtime`).
+//! - `sandbox`: Generate sandbox profile fragments (see `sandbox`).
+//! - `search-path`: Merge and search across search paths (see `Overlay`,
+//!   `glob` and `which`).
+//! - `session`: Describe the user's desktop session (see `session`).
+//! - `shell`: Integrate with command-line shells (see `shell`).
+//! - `statics`: Resolve each directory once (see `statics`).
+//! - `systemd`: Integrate with the systemd user manager (see `systemd`).
+//! - `template`: Expand path templates (see `PathTemplate`).
+//! - `thumbnail`: Share thumbnails through the thumbnail cache (see
+//!   `thumbnail`).
+//! - `toml`: Load typed configuration files (see `config::load`), and versioned
+//!   state files (see `state`). Combined with `notify`, configuration can also
+//!   be reloaded as it changes.
+//! - `trash`: Move files to the user's trash can (see `trash`).
+//! - `user-dirs`: Locate the user's documents, downloads, etc. (see
+//!   `user_dirs`).
+//! - `volume`: Classify filesystems, measure their free space, and redirect
+//!   directories off of network filesystems (see `fs_kind`, `free_space` and
+//!   `set_network_redirect`).
+//! - `wasm`: Expose directory resolution to JavaScript, through
+//!   `wasm-bindgen` (see `wasm`).
+//! - `windows`: Convert paths into their Windows forms (see `windows`).
+//! - `wsl`: Translate paths of the Windows Subsystem for Linux (see `wsl`).
+//! - `xdg`: Replace the `xdg` crate (see `xdg`).
+//!
+//! With the `metrics` feature, the following metrics are emitted, such that
+//! services can monitor the health of their directories:
+//!
+//! | Metric                          | Kind    | Labels | Emitted when             |
+//! |---------------------------------|---------|--------|--------------------------|
+//! | `xdir_cache_size_bytes`         | Gauge   | `app`  | Cache is pruned          |
+//! | `xdir_cache_prune_runs_total`   | Counter | `app`  | Cache is pruned          |
+//! | `xdir_cache_pruned_files_total` | Counter | `app`  | Cache is pruned          |
+//! | `xdir_fallbacks_total`          | Counter | `var`  | A variable is rejected   |
+//! | `xdir_create_failures_total`    | Counter | `dir`  | Directory creation fails |
+//!
+//! Caches are pruned by `cache::maybe_prune`. Variables are rejected where set
+//! to an empty or invalid value, and so ignored in favor of the default, as
+//! reported to the [fallback hook](fallback) (though each is counted every time
+//! it occurs). Unset variables are not counted.
 //!
 //! ## Examples
 //!
 //! To get the configuration file of an application:
 //!
 //! ```
-//! # use std::path::PathBuf;
+//! # use std::path::{Path, PathBuf};
 //! #
 //! fn config() -> PathBuf {
 //!     xdir::config()
@@ -44,48 +174,360 @@
 
 #![warn(clippy::pedantic)]
 
-use std::env;
-use std::path::PathBuf;
+#[cfg(feature = "core")]
+mod app;
+#[cfg(any(
+    feature = "backup",
+    feature = "cache",
+    feature = "desktop",
+    feature = "install",
+    feature = "io-helpers",
+    feature = "mime",
+    feature = "shell",
+    feature = "systemd",
+    feature = "toml"
+))]
+mod atomic;
+#[cfg(feature = "backup")]
+mod backup;
+mod cmd;
+mod dir;
+mod dirs;
+mod escape;
+#[cfg(feature = "core")]
+mod fingerprint;
+#[cfg(feature = "search-path")]
+mod glob;
+#[cfg(feature = "toml")]
+mod load;
+mod location;
+#[cfg(any(feature = "core", feature = "thumbnail"))]
+mod md5;
+#[cfg(feature = "core")]
+mod once;
+#[cfg(feature = "search-path")]
+mod overlay;
+#[cfg(feature = "core")]
+mod perms;
+#[cfg(feature = "io-helpers")]
+mod promote;
+mod readonly;
+mod resolve;
+#[cfg(feature = "core")]
+mod retry;
+#[cfg(feature = "core")]
+mod scope;
+#[cfg(any(feature = "core", feature = "xdg"))]
+mod search;
+#[cfg(feature = "io-helpers")]
+mod secret;
+mod sys;
+#[cfg(feature = "backup")]
+mod tar;
+mod telemetry;
+#[cfg(feature = "template")]
+mod template;
+#[cfg(test)]
+mod testing;
+#[cfg(any(
+    feature = "io-helpers",
+    feature = "journal",
+    feature = "record",
+    feature = "trash"
+))]
+mod time;
+#[cfg(any(
+    feature = "io-helpers",
+    feature = "journal",
+    feature = "link",
+    feature = "migrate",
+    feature = "trash"
+))]
+mod transfer;
+#[cfg(any(feature = "journal", feature = "thumbnail", feature = "trash"))]
+mod uri;
+#[cfg(feature = "volume")]
+mod volume;
+#[cfg(feature = "search-path")]
+mod which;
+
+#[cfg(feature = "audit")]
+pub mod audit;
+#[cfg(feature = "autostart")]
+pub mod autostart;
+#[cfg(feature = "bake")]
+pub mod baked;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod config;
+#[cfg(feature = "cygwin")]
+pub mod cygwin;
+pub mod data;
+#[cfg(feature = "desktop")]
+pub mod desktop;
+pub mod fallback;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "install")]
+pub mod install;
+#[cfg(feature = "journal")]
+pub mod journal;
+#[cfg(all(feature = "core", not(feature = "journal")))]
+mod journal {
+    //! Journals of destructive operations, which are disabled.
 
-pub use home::home_dir as home;
+    use std::io;
+    use std::path::Path;
 
-macro_rules! path {
-    ($var:tt, $dir:tt) => {
-        env::var($var)
-            .into_iter()
-            .filter(|path| !path.is_empty())
-            .map(PathBuf::from)
-            .next()
-            .or_else(|| home().map(|path| path.join($dir)))
-    };
+    /// Journal of destructive operations, which cannot be constructed.
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub(crate) enum Journal {}
+
+    impl Journal {
+        #[cfg(feature = "migrate")]
+        pub(crate) fn moved(&self, _: &Path, _: &Path) -> io::Result<()> {
+            match *self {}
+        }
+
+        pub(crate) fn removed(&self, _: &Path) -> io::Result<()> {
+            match *self {}
+        }
+    }
 }
+#[cfg(feature = "link")]
+pub mod link;
+#[cfg(feature = "migrate")]
+pub mod migrate;
+#[cfg(feature = "mime")]
+pub mod mime;
+#[cfg(feature = "overrides")]
+pub mod overrides;
+#[cfg(feature = "owner")]
+pub mod owner;
+#[cfg(feature = "portable")]
+pub mod portable;
+#[cfg(feature = "portal")]
+pub mod portal;
+#[cfg(feature = "preflight")]
+pub mod preflight;
+#[cfg(feature = "record")]
+pub mod record;
+#[cfg(feature = "runtime")]
+pub mod runtime;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+#[cfg(feature = "session")]
+pub mod session;
+#[cfg(feature = "shell")]
+pub mod shell;
+#[cfg(feature = "toml")]
+pub mod state;
+#[cfg(feature = "statics")]
+pub mod statics;
+#[cfg(feature = "systemd")]
+pub mod systemd;
+#[cfg(feature = "thumbnail")]
+pub mod thumbnail;
+#[cfg(feature = "trash")]
+pub mod trash;
+#[cfg(feature = "user-dirs")]
+pub mod user_dirs;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "notify")]
+pub mod watch;
+#[cfg(feature = "windows")]
+pub mod windows;
+#[cfg(not(feature = "windows"))]
+mod windows;
+#[cfg(feature = "wsl")]
+pub mod wsl;
+#[cfg(feature = "xdg")]
+pub mod xdg;
+
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "core")]
+pub use self::app::{sanitize_app_name, App};
+#[cfg(feature = "audit")]
+pub use self::audit::{audit, audit_dirs};
+pub use self::cmd::CommandExt;
+pub use self::dir::{Dir, DirSet};
+pub use self::dirs::{resolve_all, Dirs};
+pub use self::escape::escape_path;
+#[cfg(feature = "core")]
+pub use self::fingerprint::Fingerprint;
+#[cfg(feature = "search-path")]
+pub use self::glob::glob;
+#[cfg(feature = "journal")]
+pub use self::journal::undo;
+pub use self::location::{dedup_search_path, same_location};
+#[cfg(feature = "core")]
+pub use self::once::EnsureOnce;
+#[cfg(feature = "search-path")]
+pub use self::overlay::Overlay;
+#[cfg(feature = "core")]
+pub use self::perms::Permissions;
+#[cfg(feature = "io-helpers")]
+pub use self::promote::promote;
+pub use self::readonly::{is_read_only, set_read_only};
+pub use self::resolve::{
+    empty_vars, set_empty_vars, set_legacy_bin, set_symlinked_home, EmptyVars, Env, Resolved,
+    Source, SymlinkedHome,
+};
+#[cfg(feature = "core")]
+pub use self::retry::{is_transient, Retry};
+#[cfg(feature = "core")]
+pub use self::scope::Scope;
+#[cfg(feature = "io-helpers")]
+pub use self::secret::{runtime_secret_file, secret_file, SecretFile};
+#[cfg(feature = "template")]
+pub use self::template::PathTemplate;
+#[cfg(feature = "volume")]
+pub use self::volume::{choose_cache_location, free_space, fs_kind, set_network_redirect, FsKind};
+#[cfg(feature = "notify")]
+pub use self::watch::watch;
+#[cfg(feature = "search-path")]
+pub use self::which::which;
 
 /// Returns the path to the user's executable directory.
+///
+/// See [`set_legacy_bin`] to fall back to `~/bin`, where it is used instead.
 pub fn bin() -> Option<PathBuf> {
-    path!("XDG_BIN_HOME", ".local/bin")
+    bin_resolved().map(Resolved::into_path)
+}
+
+/// Returns the path to the user's executable directory, along with its source.
+#[must_use]
+pub fn bin_resolved() -> Option<Resolved> {
+    Dir::Bin.resolve()
 }
 
 /// Returns the path to the user's cache directory.
 pub fn cache() -> Option<PathBuf> {
-    path!("XDG_CACHE_HOME", ".cache")
+    cache_resolved().map(Resolved::into_path)
+}
+
+/// Returns the path to the user's cache directory, along with its source.
+#[must_use]
+pub fn cache_resolved() -> Option<Resolved> {
+    Dir::Cache.resolve()
 }
 
 /// Returns the path to the user's config directory.
 pub fn config() -> Option<PathBuf> {
-    path!("XDG_CONFIG_HOME", ".config")
+    config_resolved().map(Resolved::into_path)
+}
+
+/// Returns the path to the user's config directory, along with its source.
+#[must_use]
+pub fn config_resolved() -> Option<Resolved> {
+    Dir::Config.resolve()
+}
+
+/// Returns the preference-ordered system config directories.
+#[must_use]
+pub fn config_dirs() -> Vec<PathBuf> {
+    search_path("XDG_CONFIG_DIRS", &["/etc/xdg"])
 }
 
 /// Returns the path to the user's data directory.
 pub fn data() -> Option<PathBuf> {
-    path!("XDG_DATA_HOME", ".local/share")
+    data_resolved().map(Resolved::into_path)
+}
+
+/// Returns the path to the user's data directory, along with its source.
+#[must_use]
+pub fn data_resolved() -> Option<Resolved> {
+    Dir::Data.resolve()
+}
+
+/// Returns the preference-ordered system data directories.
+#[must_use]
+pub fn data_dirs() -> Vec<PathBuf> {
+    search_path("XDG_DATA_DIRS", &["/usr/local/share", "/usr/share"])
+}
+
+/// Returns the path to the user's home directory.
+///
+/// See [`set_symlinked_home`] to consistently resolve a home directory which is
+/// a symbolic link.
+#[must_use]
+pub fn home() -> Option<PathBuf> {
+    resolve::home_dir()
+}
+
+/// Parses a search path, as given by a list variable (e.g. `$XDG_DATA_DIRS`).
+///
+/// Entries are separated by the platform's path-list separator (i.e. `:` on
+/// Unix, and `;` on Windows). As per the XDG Base Directory Specification,
+/// empty and relative entries are ignored. Duplicate entries are removed,
+/// keeping the first (most preferred) occurrence.
+///
+/// Search path variables which yield no entries are treated as unset.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// use std::path::PathBuf;
+///
+/// let paths = xdir::parse_search_path("/usr/share::share:/opt/share:/usr/share".as_ref());
+/// assert_eq!(paths, [PathBuf::from("/usr/share"), PathBuf::from("/opt/share")]);
+/// # }
+/// ```
+#[must_use]
+pub fn parse_search_path(paths: &OsStr) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for path in env::split_paths(paths).filter(|path| !path.as_os_str().is_empty()) {
+        let path = resolve::native(path);
+        if resolve::is_absolute(&path) && !out.contains(&path) {
+            out.push(path);
+        }
+    }
+    out
 }
 
 /// Returns the path to the user's runtime directory.
 pub fn runtime() -> Option<PathBuf> {
-    env::var("XDG_RUNTIME_DIR").map(PathBuf::from).ok()
+    runtime_resolved().map(Resolved::into_path)
+}
+
+/// Returns the path to the user's runtime directory, along with its source.
+#[must_use]
+pub fn runtime_resolved() -> Option<Resolved> {
+    Dir::Runtime.resolve()
 }
 
 /// Returns the path to the user's state directory.
 pub fn state() -> Option<PathBuf> {
-    path!("XDG_STATE_HOME", ".local/state")
+    state_resolved().map(Resolved::into_path)
+}
+
+/// Returns the path to the user's state directory, along with its source.
+#[must_use]
+pub fn state_resolved() -> Option<Resolved> {
+    Dir::State.resolve()
+}
+
+/// Resolves a search path from the environment, falling back to its defaults
+/// on Unix.
+fn search_path(var: &'static str, defaults: &[&str]) -> Vec<PathBuf> {
+    let value = env::var_os(var);
+    let paths = value.as_deref().map(parse_search_path).unwrap_or_default();
+    if !paths.is_empty() {
+        return paths;
+    }
+    if cfg!(unix) {
+        let defaults = defaults.iter().map(Path::new).collect::<Vec<_>>();
+        if let Some(value) = value {
+            fallback::report(var, value, &defaults);
+        }
+        defaults.into_iter().map(Path::to_path_buf).collect()
+    } else {
+        Vec::new()
+    }
 }
@@ -0,0 +1,282 @@
+//! Linking of dotfiles.
+//!
+//! Dotfiles are commonly kept in a version-controlled repository, and linked
+//! into place at their standard location. A [`Linker`] can [adopt] existing
+//! files into such a repository, or [deploy] them from it.
+//!
+//! Within the repository, files follow the default layout of each directory,
+//! relative to the user's home (e.g. `.config/myapp`), as expected by GNU
+//! Stow. This holds regardless of where the directories are located.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::link::Linker;
+//! use xdir::Dir;
+//!
+//! // Move `~/.config/myapp` into the repository, leaving a link behind...
+//! let linker = Linker::new("/home/user/dotfiles").relative(true);
+//! linker.adopt(Dir::Config, "myapp").unwrap();
+//! // ...then link it into place on another machine.
+//! linker.deploy(Dir::Config, "myapp").unwrap();
+//! ```
+//!
+//! [adopt]: Linker::adopt
+//! [deploy]: Linker::deploy
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::{app, readonly, transfer, Dir};
+
+/// Resolution of conflicting files.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Conflict {
+    /// Fail, leaving the existing file untouched.
+    #[default]
+    Fail,
+    /// Move the existing file aside, appending `.bak` to its name.
+    Backup,
+    /// Remove the existing file.
+    Overwrite,
+}
+
+/// Links files between their standard location and a dotfiles repository.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Linker {
+    repo: PathBuf,
+    relative: bool,
+    conflict: Conflict,
+}
+
+impl Linker {
+    /// Constructs a linker for the given repository.
+    pub fn new(repo: impl Into<PathBuf>) -> Self {
+        Self {
+            repo: repo.into(),
+            relative: false,
+            conflict: Conflict::default(),
+        }
+    }
+
+    /// Sets whether links are relative to their location, rather than
+    /// absolute.
+    ///
+    /// Relative links keep working should the home directory (along with the
+    /// repository) be moved.
+    #[must_use]
+    pub fn relative(mut self, relative: bool) -> Self {
+        self.relative = relative;
+        self
+    }
+
+    /// Sets how conflicting files are resolved.
+    ///
+    /// When adopting, conflicts are files already within the repository; when
+    /// deploying, files already at the standard location.
+    #[must_use]
+    pub fn on_conflict(mut self, conflict: Conflict) -> Self {
+        self.conflict = conflict;
+        self
+    }
+
+    /// Returns the path to a file within the repository.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory has no default location (i.e. the runtime
+    /// directory), or if the path is not relative to it.
+    pub fn repo_path(&self, dir: Dir, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let suffix = dir.default().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot link within the {dir} directory"),
+            )
+        })?;
+        let base = suffix
+            .split('/')
+            .fold(self.repo.clone(), |path, part| path.join(part));
+        app::join(&base, path.as_ref())
+    }
+
+    /// Moves a file (or directory) into the repository, leaving a link at its
+    /// standard location, and returning its new path.
+    ///
+    /// Files which are already linked to the repository are left as-is.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be found, if the file does not exist,
+    /// if it conflicts with a file in the repository, or if it could not be
+    /// moved or linked.
+    pub fn adopt(&self, dir: Dir, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = path.as_ref();
+        let link = location(dir, path)?;
+        let target = self.repo_path(dir, path)?;
+        if is_linked(&link, &target) {
+            return Ok(target);
+        }
+        if fs::symlink_metadata(&link)?.is_symlink() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is already a link", link.display()),
+            ));
+        }
+        readonly::check()?;
+        self.resolve(&target)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        transfer::move_path(&link, &target)?;
+        if let Err(err) = self.link(&link, &target) {
+            // Restore the file, rather than leaving it only in the repository.
+            let _ = transfer::move_path(&target, &link);
+            return Err(err);
+        }
+        Ok(target)
+    }
+
+    /// Links a file (or directory) from the repository into its standard
+    /// location, returning the path to the link.
+    ///
+    /// Files which are already linked are left as-is.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be found, if the file does not exist
+    /// within the repository, if it conflicts with an existing file, or if it
+    /// could not be linked.
+    pub fn deploy(&self, dir: Dir, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = path.as_ref();
+        let link = location(dir, path)?;
+        let target = self.repo_path(dir, path)?;
+        fs::symlink_metadata(&target)?;
+        if is_linked(&link, &target) {
+            return Ok(link);
+        }
+        readonly::check()?;
+        self.resolve(&link)?;
+        if let Some(parent) = link.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.link(&link, &target)?;
+        Ok(link)
+    }
+
+    /// Resolves a conflict with an existing file, if any.
+    fn resolve(&self, path: &Path) -> io::Result<()> {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return Ok(());
+        };
+        match self.conflict {
+            Conflict::Fail => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            )),
+            Conflict::Backup => {
+                let mut backup = path.as_os_str().to_os_string();
+                backup.push(".bak");
+                let backup = PathBuf::from(backup);
+                if fs::symlink_metadata(&backup).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} already exists", backup.display()),
+                    ));
+                }
+                fs::rename(path, backup)
+            }
+            Conflict::Overwrite if meta.is_dir() => fs::remove_dir_all(path),
+            Conflict::Overwrite => fs::remove_file(path),
+        }
+    }
+
+    /// Creates a link to a target.
+    fn link(&self, link: &Path, target: &Path) -> io::Result<()> {
+        let target = if self.relative {
+            let parent = link.parent().unwrap_or(link);
+            relative(&fs::canonicalize(parent)?, &fs::canonicalize(target)?)
+        } else {
+            target.to_path_buf()
+        };
+        symlink(&target, link)
+    }
+}
+
+/// Moves a file into a repository, leaving an absolute link behind.
+///
+/// See [`Linker::adopt`] for details.
+///
+/// # Errors
+///
+/// Errors if the file could not be adopted.
+pub fn adopt(dir: Dir, path: impl AsRef<Path>, repo: impl Into<PathBuf>) -> io::Result<PathBuf> {
+    Linker::new(repo).adopt(dir, path)
+}
+
+/// Links a file from a repository into its standard location, using an
+/// absolute link.
+///
+/// See [`Linker::deploy`] for details.
+///
+/// # Errors
+///
+/// Errors if the file could not be deployed.
+pub fn deploy(dir: Dir, path: impl AsRef<Path>, repo: impl Into<PathBuf>) -> io::Result<PathBuf> {
+    Linker::new(repo).deploy(dir, path)
+}
+
+/// Returns the standard location of a file.
+fn location(dir: Dir, path: &Path) -> io::Result<PathBuf> {
+    let base = dir.path().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find {dir} directory"),
+        )
+    })?;
+    app::join(&base, path)
+}
+
+/// Checks whether a link already resolves to its target.
+fn is_linked(link: &Path, target: &Path) -> bool {
+    fs::symlink_metadata(link).is_ok_and(|meta| meta.is_symlink())
+        && matches!(
+            (fs::canonicalize(link), fs::canonicalize(target)),
+            (Ok(link), Ok(target)) if link == target
+        )
+}
+
+/// Computes the relative path from a directory to a target.
+fn relative(from: &Path, to: &Path) -> PathBuf {
+    let from = from.components().collect::<Vec<_>>();
+    let to = to.components().collect::<Vec<_>>();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    from[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .chain(to[common..].iter().copied())
+        .collect()
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    let resolved = link.parent().unwrap_or(link).join(target);
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_: &Path, _: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "links are not supported on this platform",
+    ))
+}
@@ -0,0 +1,210 @@
+//! Typed configuration files.
+
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+/// An error loading a configuration file.
+#[derive(Debug)]
+pub enum LoadError {
+    /// File could not be read.
+    Io(PathBuf, io::Error),
+    /// File could not be parsed.
+    Parse(PathBuf, toml::de::Error),
+}
+
+impl LoadError {
+    /// Returns the path of the file which failed to load.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Io(path, _) | Self::Parse(path, _) => path,
+        }
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, _) => write!(f, "could not read `{}`", path.display()),
+            Self::Parse(path, _) => write!(f, "could not parse `{}`", path.display()),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+        }
+    }
+}
+
+/// Loads a TOML configuration file from the config search path.
+///
+/// The path is relative to the config directories (e.g. `myapp/config.toml`),
+/// and the most preferred existing file is loaded. Returns [`None`] if no such
+/// file exists.
+///
+/// # Errors
+///
+/// Errors if the file could not be read or parsed.
+///
+/// # Examples
+///
+/// ```no_run
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     theme: String,
+/// }
+///
+/// let config: Option<Config> = xdir::config::load("myapp/config.toml").unwrap();
+/// ```
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<Option<T>, LoadError> {
+    candidates(path.as_ref())
+        .into_iter()
+        .find(|path| path.is_file())
+        .map(|path| parse(&path))
+        .transpose()
+}
+
+fn parse<T: DeserializeOwned>(path: &Path) -> Result<T, LoadError> {
+    let text = fs::read_to_string(path).map_err(|err| LoadError::Io(path.into(), err))?;
+    toml::from_str(&text).map_err(|err| LoadError::Parse(path.into(), err))
+}
+
+/// Returns the candidate locations of a file within the config search path.
+fn candidates(path: &Path) -> Vec<PathBuf> {
+    crate::config()
+        .into_iter()
+        .chain(crate::config_dirs())
+        .map(|dir| dir.join(path))
+        .collect()
+}
+
+#[cfg(feature = "notify")]
+pub use self::subscribe::{subscribe, Subscription};
+
+#[cfg(feature = "notify")]
+mod subscribe {
+    use std::io;
+    use std::path::Path;
+    use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+    use std::thread;
+    use std::time::Duration;
+
+    use serde::de::DeserializeOwned;
+
+    use super::{candidates, load, LoadError};
+    use crate::watch::{watch_path, Watcher};
+
+    /// Duration without changes after which a file is reloaded.
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+
+    /// A subscription to changes of a configuration file.
+    ///
+    /// Dropping the subscription stops watching for changes.
+    #[derive(Debug)]
+    pub struct Subscription<T> {
+        rx: Receiver<Result<Option<T>, LoadError>>,
+        _watchers: Vec<Watcher>,
+    }
+
+    impl<T> Subscription<T> {
+        /// Blocks until the configuration changes, returning its new value.
+        ///
+        /// The value is [`None`] if the file was removed from all directories.
+        #[must_use]
+        pub fn recv(&self) -> Option<Result<Option<T>, LoadError>> {
+            self.rx.recv().ok()
+        }
+
+        /// Returns the next change to the configuration, if any, without
+        /// blocking.
+        #[must_use]
+        pub fn try_recv(&self) -> Option<Result<Option<T>, LoadError>> {
+            self.rx.try_recv().ok()
+        }
+
+        /// Returns an iterator over changes to the configuration.
+        pub fn iter(&self) -> impl Iterator<Item = Result<Option<T>, LoadError>> + '_ {
+            self.rx.iter()
+        }
+    }
+
+    /// Subscribes to changes of a TOML configuration file.
+    ///
+    /// Whenever the file changes within any directory of the config search
+    /// path, it is [reloaded](load) and the result is sent to the subscription.
+    /// Bursts of changes are debounced, such that a single value is yielded
+    /// once the file settles. Errors are yielded rather than ending the
+    /// subscription, as the file may yet be fixed.
+    ///
+    /// # Errors
+    ///
+    /// Errors if any directory of the search path could not be watched.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #[derive(serde::Deserialize)]
+    /// struct Config {
+    ///     theme: String,
+    /// }
+    ///
+    /// let sub = xdir::config::subscribe::<Config>("myapp/config.toml").unwrap();
+    /// for config in sub.iter() {
+    ///     match config {
+    ///         Ok(Some(config)) => println!("theme: {}", config.theme),
+    ///         Ok(None) => println!("config removed"),
+    ///         Err(err) => eprintln!("error: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn subscribe<T>(path: impl AsRef<Path>) -> io::Result<Subscription<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let (events, changes) = mpsc::channel();
+        let watchers = candidates(&path)
+            .into_iter()
+            .map(|file| {
+                let events = events.clone();
+                watch_path(file, move |event| {
+                    // Ignore accesses, which are caused by reloading itself.
+                    if event.is_ok_and(|event| !event.kind.is_access()) {
+                        let _ = events.send(());
+                    }
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        drop(events);
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            while changes.recv().is_ok() {
+                // Wait for changes to settle before reloading.
+                loop {
+                    match changes.recv_timeout(DEBOUNCE) {
+                        Ok(()) => (),
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                if tx.send(load(&path)).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Subscription {
+            rx,
+            _watchers: watchers,
+        })
+    }
+}
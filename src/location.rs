@@ -0,0 +1,152 @@
+//! Comparison of locations.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::resolve;
+
+/// Checks whether two paths refer to the same location.
+///
+/// Paths are first normalized lexically, such that separators, trailing
+/// separators, `.` and `..` segments (and, on Windows, the extended-length
+/// form) do not affect the comparison. On filesystems which are
+/// case-insensitive (as is the default on macOS and Windows), paths differing
+/// only by case are the same. Whether a filesystem is case-insensitive is
+/// detected from the paths themselves if they exist, falling back to the
+/// platform's default otherwise.
+///
+/// If `follow_links` is set, paths which both exist are compared by the file
+/// they resolve to, such that a symbolic link is the same location as its
+/// target. Otherwise, symbolic links are not resolved, and `..` segments are
+/// resolved lexically (rather than relative to a link's target).
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// assert!(xdir::same_location("/usr/share/", "/usr/./lib/../share", false));
+/// assert!(!xdir::same_location("/usr/share", "/usr/lib", false));
+/// # }
+/// ```
+#[must_use]
+pub fn same_location(a: impl AsRef<Path>, b: impl AsRef<Path>, follow_links: bool) -> bool {
+    let (a, b) = (normalize(a.as_ref()), normalize(b.as_ref()));
+    if a == b {
+        return true;
+    }
+    if follow_links {
+        if let (Ok(a), Ok(b)) = (identity(&a, true), identity(&b, true)) {
+            return a == b;
+        }
+    }
+    match (a.to_str(), b.to_str()) {
+        (Some(x), Some(y)) => x.to_lowercase() == y.to_lowercase() && is_case_insensitive(&a),
+        _ => false,
+    }
+}
+
+/// Removes duplicate locations from a search path, keeping the first (most
+/// preferred) occurrence of each.
+///
+/// Locations are compared by [`same_location`], following symbolic links,
+/// such that a directory is not searched twice through different paths.
+/// Paths are otherwise returned as given.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// use std::path::PathBuf;
+///
+/// let paths = vec![
+///     PathBuf::from("/usr/local/share"),
+///     PathBuf::from("/usr/share"),
+///     PathBuf::from("/usr/local/share/"),
+/// ];
+/// assert_eq!(xdir::dedup_search_path(paths).len(), 2);
+/// # }
+/// ```
+#[must_use]
+pub fn dedup_search_path(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut out = Vec::<PathBuf>::with_capacity(paths.len());
+    for path in paths {
+        if !out.iter().any(|seen| same_location(seen, &path, true)) {
+            out.push(path);
+        }
+    }
+    out
+}
+
+/// Normalizes a path lexically.
+fn normalize(path: &Path) -> PathBuf {
+    let path = crate::windows::extended(&resolve::native(path.to_path_buf()));
+    let mut out = PathBuf::new();
+    for part in path.components() {
+        match part {
+            Component::CurDir => (),
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => drop(out.pop()),
+                Some(Component::RootDir | Component::Prefix(_)) => (),
+                _ => out.push(".."),
+            },
+            part => out.push(part),
+        }
+    }
+    out
+}
+
+/// Checks whether the filesystem of a path is case-insensitive.
+///
+/// This is detected by looking up the nearest existing component (with any
+/// cased letters) by another case.
+fn is_case_insensitive(path: &Path) -> bool {
+    for path in path.ancestors() {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let swapped = name
+            .chars()
+            .flat_map(|char| {
+                if char.is_lowercase() {
+                    char.to_uppercase().collect::<Vec<_>>()
+                } else {
+                    char.to_lowercase().collect()
+                }
+            })
+            .collect::<String>();
+        if swapped == name || fs::symlink_metadata(path).is_err() {
+            continue;
+        }
+        let other = path.with_file_name(swapped);
+        return match (identity(path, false), identity(&other, false)) {
+            (Ok(path), Ok(other)) => path == other,
+            _ => false,
+        };
+    }
+    cfg!(any(windows, target_os = "macos", target_os = "ios"))
+}
+
+/// Returns the identity of the file at a path.
+#[cfg(unix)]
+fn identity(path: &Path, follow_links: bool) -> io::Result<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = if follow_links {
+        fs::metadata(path)?
+    } else {
+        fs::symlink_metadata(path)?
+    };
+    Ok((meta.dev(), meta.ino()))
+}
+
+/// Returns the identity of the file at a path.
+///
+/// Without file identifiers, this is the canonical path, which reflects the
+/// case of each component as stored (and, as such, always follows links).
+#[cfg(not(unix))]
+fn identity(path: &Path, _: bool) -> io::Result<PathBuf> {
+    fs::canonicalize(path)
+}
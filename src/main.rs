@@ -0,0 +1,208 @@
+//! Command-line interface to `xdir`.
+//!
+//! Prints the standard locations of directories, such that shell scripts
+//! resolve the same paths as applications using the library.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::{env, fs};
+
+use xdir::shell::{self, Shell};
+use xdir::{App, Dir, Env};
+
+const USAGE: &str = "\
+Usage: xdir <KIND>
+       xdir app <NAME> <KIND> [--create]
+       xdir tree <NAME>
+       xdir env <SHELL>
+
+Print the standard location of a directory.
+
+Commands:
+  <KIND>             Print one of the user's directories
+  app <NAME> <KIND>  Print one of an application's directories
+  tree <NAME>        Print the files within an application's directories
+  env <SHELL>        Print a script exporting the user's directories
+
+Kinds:
+  bin, cache, config, data, runtime, state
+
+Shells:
+  bash, zsh, fish, nu, pwsh
+
+Options:
+  --create    Create the directory if it does not exist
+  -h, --help  Print help
+";
+
+fn main() -> ExitCode {
+    let result = args().and_then(|args| {
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        run(&args)
+    });
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("xdir: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Collects the command-line arguments, which must be valid UTF-8.
+fn args() -> Result<Vec<String>, String> {
+    env::args_os()
+        .skip(1)
+        .map(|arg| {
+            arg.into_string().map_err(|arg| {
+                format!(
+                    "argument is not valid UTF-8: {} (see `xdir --help`)",
+                    arg.to_string_lossy()
+                )
+            })
+        })
+        .collect()
+}
+
+fn run(args: &[&str]) -> Result<ExitCode, String> {
+    if args.iter().any(|&arg| matches!(arg, "-h" | "--help")) {
+        print!("{USAGE}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    match args {
+        [kind] => {
+            let dir = kind_of(kind)?;
+            print(dir, dir.path().as_deref())
+        }
+        ["app", name, kind, flags @ ..] => {
+            let app = App::new(*name);
+            let dir = kind_of(kind)?;
+            match flags {
+                [] => print(dir, app.dir(dir).as_deref()),
+                ["--create"] => {
+                    let path = app.create(dir).map_err(|err| err.to_string())?;
+                    print(dir, Some(&path))
+                }
+                _ => Err(usage()),
+            }
+        }
+        ["tree", name] => tree(&App::new(*name)),
+        ["env", shell] => {
+            let shell = shell.parse::<Shell>().map_err(|err| err.to_string())?;
+            let script = shell::export(shell, &Env::current()).map_err(|err| err.to_string())?;
+            print!("{script}");
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => Err(usage()),
+    }
+}
+
+/// Prints the files within an application's directories, along with their
+/// sizes.
+///
+/// The application's own directories are listed along with their source,
+/// followed by its system config and data directories, such that every file is
+/// shown under the base it came from.
+fn tree(app: &App) -> Result<ExitCode, String> {
+    let mut out = String::new();
+    for dir in [Dir::Config, Dir::Data, Dir::State, Dir::Cache] {
+        let Some(resolved) = app.dir_resolved(dir) else {
+            continue;
+        };
+        let label = format!("{dir} (user, from {})", resolved.source());
+        render(&mut out, &label, resolved.path());
+        let system = match dir {
+            Dir::Config => app.config_dirs(),
+            Dir::Data => app.data_dirs(),
+            _ => continue,
+        };
+        // The first entry of the search path is the user's directory.
+        for path in system.iter().skip(1) {
+            render(&mut out, &format!("{dir} (system)"), path);
+        }
+    }
+    io::stdout()
+        .write_all(out.as_bytes())
+        .map_err(|err| err.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Renders a base directory under a label, followed by its entries.
+fn render(out: &mut String, label: &str, path: &Path) {
+    if path.is_dir() {
+        let _ = writeln!(out, "{label}: {}", path.display());
+        walk(out, path, "");
+    } else {
+        let _ = writeln!(out, "{label}: {} (missing)", path.display());
+    }
+}
+
+/// Renders the entries of a directory, recursively.
+fn walk(out: &mut String, path: &Path, indent: &str) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    let mut entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+    entries.sort_by_key(fs::DirEntry::file_name);
+    for (idx, entry) in entries.iter().enumerate() {
+        let last = idx + 1 == entries.len();
+        let branch = if last { "└── " } else { "├── " };
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            let _ = writeln!(out, "{indent}{branch}{name}/");
+            let indent = format!("{indent}{}", if last { "    " } else { "│   " });
+            walk(out, &entry.path(), &indent);
+        } else if meta.is_symlink() {
+            let target = fs::read_link(entry.path()).unwrap_or_default();
+            let _ = writeln!(out, "{indent}{branch}{name} -> {}", target.display());
+        } else {
+            let _ = writeln!(out, "{indent}{branch}{name} ({})", size(meta.len()));
+        }
+    }
+}
+
+/// Formats a size in bytes, using binary units.
+fn size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Parses the kind of a directory.
+fn kind_of(arg: &str) -> Result<Dir, String> {
+    Dir::ALL
+        .into_iter()
+        .find(|dir| dir.to_string() == arg)
+        .ok_or_else(|| format!("unknown directory: {arg} (see `xdir --help`)"))
+}
+
+/// Prints the path of a directory, failing if it could not be found.
+fn print(dir: Dir, path: Option<&Path>) -> Result<ExitCode, String> {
+    let path = path.ok_or_else(|| format!("could not find {dir} directory"))?;
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(path.as_os_str().as_encoded_bytes())
+        .and_then(|()| stdout.write_all(b"\n"))
+        .map_err(|err| err.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn usage() -> String {
+    String::from("invalid arguments (see `xdir --help`)")
+}
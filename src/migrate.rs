@@ -0,0 +1,480 @@
+//! Migration of application directories.
+//!
+//! Applications which are [versioned](App::versioned) store their data and
+//! state separately for each major version. When a new major version first
+//! runs, [`Migrations`] carry forward the directories of the most recent
+//! earlier version, one step at a time.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::fs;
+//!
+//! use xdir::migrate::Migrations;
+//! use xdir::{App, Dir};
+//!
+//! let app = App::new("myapp").versioned(3);
+//! Migrations::new(&app)
+//!     .register_migration(1, 2, |step| {
+//!         // Version 2 renamed the history file.
+//!         let (src, dst) = (step.source(Dir::State).unwrap(), step.target(Dir::State).unwrap());
+//!         fs::create_dir_all(&dst)?;
+//!         fs::copy(src.join("history"), dst.join("history.txt"))?;
+//!         Ok(())
+//!     })
+//!     .register_migration(2, 3, |step| step.carry_over())
+//!     .run()
+//!     .unwrap();
+//! ```
+//!
+//! Applications which were renamed may instead move their directories from
+//! the old name to the new one with [`rename_app`].
+
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{transfer, App, Dir, DirSet, Dirs};
+
+/// Directories which are versioned.
+const VERSIONED: [Dir; 2] = [Dir::Data, Dir::State];
+
+/// Directories which are moved by default when renaming an application.
+const RENAMED: DirSet = DirSet::CACHE
+    .union(DirSet::CONFIG)
+    .union(DirSet::DATA)
+    .union(DirSet::STATE);
+
+/// A migration function.
+type Migrate<'a> = Box<dyn Fn(&Step<'_>) -> io::Result<()> + 'a>;
+
+/// A step of migration between two versions of an application.
+#[derive(Debug)]
+pub struct Step<'a> {
+    app: &'a App,
+    from: u64,
+    to: u64,
+}
+
+impl Step<'_> {
+    /// Returns the version being migrated from.
+    #[must_use]
+    pub fn from(&self) -> u64 {
+        self.from
+    }
+
+    /// Returns the version being migrated to.
+    #[must_use]
+    pub fn to(&self) -> u64 {
+        self.to
+    }
+
+    /// Returns the path to a directory of the version being migrated from.
+    ///
+    /// Only data and state directories are versioned; [`None`] is returned for
+    /// other kinds of directories.
+    #[must_use]
+    pub fn source(&self, dir: Dir) -> Option<PathBuf> {
+        VERSIONED
+            .contains(&dir)
+            .then(|| self.app.scoped(dir, Some(self.from)))
+            .flatten()
+    }
+
+    /// Returns the path to a directory of the version being migrated to.
+    ///
+    /// Only data and state directories are versioned; [`None`] is returned for
+    /// other kinds of directories.
+    #[must_use]
+    pub fn target(&self, dir: Dir) -> Option<PathBuf> {
+        VERSIONED
+            .contains(&dir)
+            .then(|| self.app.scoped(dir, Some(self.to)))
+            .flatten()
+    }
+
+    /// Moves all directories of the source version to the target version
+    /// unchanged.
+    ///
+    /// This is useful when a new major version does not change the on-disk
+    /// format.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a directory could not be moved.
+    pub fn carry_over(&self) -> io::Result<()> {
+        for dir in VERSIONED {
+            let (Some(src), Some(dst)) = (self.source(dir), self.target(dir)) else {
+                continue;
+            };
+            if src.exists() && !dst.exists() {
+                self.app.check_writable()?;
+                fs::rename(&src, &dst)?;
+                if let Some(journal) = self.app.journal_ref() {
+                    journal.moved(&src, &dst)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Registered migrations of an application.
+pub struct Migrations<'a> {
+    app: &'a App,
+    steps: Vec<(u64, u64, Migrate<'a>)>,
+}
+
+impl Debug for Migrations<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("app", &self.app)
+            .field(
+                "steps",
+                &self
+                    .steps
+                    .iter()
+                    .map(|(from, to, _)| (from, to))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<'a> Migrations<'a> {
+    /// Constructs an empty set of migrations for an application.
+    #[must_use]
+    pub fn new(app: &'a App) -> Self {
+        Self {
+            app,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Registers a migration between two versions.
+    ///
+    /// The migration is responsible for populating the target version's
+    /// directories from those of the source version, which are left in place.
+    #[must_use]
+    pub fn register_migration<F>(mut self, from: u64, to: u64, migrate: F) -> Self
+    where
+        F: Fn(&Step<'_>) -> io::Result<()> + 'a,
+    {
+        self.steps.push((from, to, Box::new(migrate)));
+        self
+    }
+
+    /// Runs any migrations required to reach the application's version,
+    /// returning the version migrated from.
+    ///
+    /// Nothing is done if the application's directories already exist, or if
+    /// no earlier version is present. If a step fails, the directories it
+    /// created are removed so that it will be retried on the next run.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the application is not versioned, if no migration is
+    /// registered to continue from some version, or if a migration fails.
+    pub fn run(&self) -> io::Result<Option<u64>> {
+        let current = self.app.version().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "application is not versioned")
+        })?;
+        let exists = |version| {
+            VERSIONED
+                .iter()
+                .filter_map(|&dir| self.app.scoped(dir, Some(version)))
+                .any(|path| path.exists())
+        };
+        if exists(current) {
+            return Ok(None);
+        }
+        let Some(from) = self.previous(current) else {
+            return Ok(None);
+        };
+        self.app.check_writable()?;
+
+        let mut version = from;
+        while version < current {
+            let (_, to, migrate) = self
+                .steps
+                .iter()
+                .filter(|(src, dst, _)| *src == version && *dst <= current && *dst > version)
+                .max_by_key(|(_, dst, _)| *dst)
+                .ok_or_else(|| {
+                    io::Error::other(format!("no migration registered from version {version}"))
+                })?;
+            let step = Step {
+                app: self.app,
+                from: version,
+                to: *to,
+            };
+            if let Err(err) = migrate(&step) {
+                for path in VERSIONED.iter().filter_map(|&dir| step.target(dir)) {
+                    if fs::remove_dir_all(&path).is_ok() {
+                        if let Some(journal) = self.app.journal_ref() {
+                            let _ = journal.removed(&path);
+                        }
+                    }
+                }
+                return Err(err);
+            }
+            version = *to;
+        }
+        Ok(Some(from))
+    }
+
+    /// Returns the most recent version present before the current one.
+    pub(crate) fn previous(&self, current: u64) -> Option<u64> {
+        VERSIONED
+            .iter()
+            .filter_map(|&dir| self.app.scoped(dir, None))
+            .filter_map(|path| fs::read_dir(path).ok())
+            .flat_map(|entries| entries.filter_map(Result::ok))
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u64>().ok())
+            .filter(|&version| version < current)
+            .max()
+    }
+}
+
+/// Renames an application, moving its directories from the old name to the
+/// new one.
+///
+/// The cache, config, data and state directories are moved by default (see
+/// [`Rename::dirs`]). Directories are moved as a whole, including those of
+/// each [version](App::versioned) and [profile](App::profile).
+///
+/// # Examples
+///
+/// ```no_run
+/// let rename = xdir::migrate::rename_app("oldname", "newname");
+/// for planned in rename.plan() {
+///     println!("{planned}");
+/// }
+/// rename.run().unwrap();
+/// ```
+#[must_use]
+pub fn rename_app(from: &str, to: &str) -> Rename {
+    Rename {
+        from: App::new(from),
+        to: App::new(to),
+        dirs: RENAMED,
+    }
+}
+
+/// A renaming of an application's directories.
+#[derive(Clone, Debug)]
+pub struct Rename {
+    from: App,
+    to: App,
+    dirs: DirSet,
+}
+
+impl Rename {
+    /// Sets which directories are moved.
+    ///
+    /// As executables and runtime directories are not scoped by application,
+    /// they are never moved.
+    #[must_use]
+    pub fn dirs(mut self, dirs: DirSet) -> Self {
+        self.dirs = dirs - (DirSet::BIN | DirSet::RUNTIME);
+        self
+    }
+
+    /// Plans the renaming without moving anything (i.e. a dry run), returning
+    /// each directory which would be moved.
+    ///
+    /// Directories which do not exist under the old name are skipped.
+    /// Directories which already exist under the new name are reported as
+    /// [collisions](Move::is_collision).
+    #[must_use]
+    pub fn plan(&self) -> Vec<Move> {
+        self.plan_in(&crate::resolve_all())
+    }
+
+    /// Plans the renaming, as located within the user's directories.
+    fn plan_in(&self, base: &Dirs) -> Vec<Move> {
+        self.dirs
+            .into_iter()
+            .filter_map(|dir| {
+                let source = self.from.scoped_in(base, dir, None)?;
+                let target = self.to.scoped_in(base, dir, None)?;
+                fs::symlink_metadata(&source).ok()?;
+                let collision = fs::symlink_metadata(&target).is_ok();
+                Some(Move {
+                    dir,
+                    source,
+                    target,
+                    collision,
+                })
+            })
+            .collect()
+    }
+
+    /// Renames the application, returning each directory which was moved.
+    ///
+    /// Nothing is moved if any directory collides. If a move fails, the
+    /// directories already moved are moved back.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the names are the same, if any directory collides, or if a
+    /// directory could not be moved.
+    pub fn run(&self) -> io::Result<Vec<Move>> {
+        self.run_in(&crate::resolve_all())
+    }
+
+    /// Renames the application, as located within the user's directories.
+    fn run_in(&self, base: &Dirs) -> io::Result<Vec<Move>> {
+        if self.from.name() == self.to.name() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot rename {} to itself", self.from.name()),
+            ));
+        }
+        let moves = self.plan_in(base);
+        if let Some(collision) = moves.iter().find(|planned| planned.collision) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("cannot rename: {collision}"),
+            ));
+        }
+        self.to.check_writable()?;
+        for (idx, planned) in moves.iter().enumerate() {
+            if let Err(err) = planned.apply() {
+                for done in moves[..idx].iter().rev() {
+                    let _ = transfer::move_path(&done.target, &done.source);
+                }
+                return Err(err);
+            }
+        }
+        Ok(moves)
+    }
+}
+
+/// A directory moved when renaming an application.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Move {
+    dir: Dir,
+    source: PathBuf,
+    target: PathBuf,
+    collision: bool,
+}
+
+impl Move {
+    /// Returns the kind of directory.
+    #[must_use]
+    pub fn dir(&self) -> Dir {
+        self.dir
+    }
+
+    /// Returns the path to the directory under the old name.
+    #[must_use]
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Returns the path to the directory under the new name.
+    #[must_use]
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Checks whether the directory already exists under the new name, such
+    /// that it cannot be moved.
+    #[must_use]
+    pub fn is_collision(&self) -> bool {
+        self.collision
+    }
+
+    /// Moves the directory, creating the new name's parent if needed.
+    fn apply(&self) -> io::Result<()> {
+        if let Some(parent) = self.target.parent() {
+            crate::sys::create_dir_all(parent)?;
+        }
+        transfer::move_path(&self.source, &self.target)
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} directory {} -> {}",
+            self.dir,
+            self.source.display(),
+            self.target.display()
+        )?;
+        if self.collision {
+            write!(f, " (already exists)")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys;
+    use crate::testing::TempDir;
+
+    /// Creates the given directories of an application within a home.
+    fn populate(home: &Path, app: &str, dirs: &[&str]) {
+        for dir in dirs {
+            let path = home.join(dir).join(app);
+            sys::create_dir_all(&path).unwrap();
+            fs::write(path.join("file"), *dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn renames_dirs() {
+        let home = TempDir::new();
+        let base = Dirs::at(&home);
+        populate(&home, "old", &[".cache", ".config", ".local/state"]);
+        let moves = rename_app("old", "new").run_in(&base).unwrap();
+        let dirs = moves.iter().map(Move::dir).collect::<Vec<_>>();
+        assert_eq!(dirs, [Dir::Cache, Dir::Config, Dir::State]);
+        for dir in [".cache", ".config", ".local/state"] {
+            assert!(!home.join(dir).join("old").exists());
+            let file = home.join(dir).join("new").join("file");
+            assert_eq!(fs::read_to_string(file).unwrap(), dir);
+        }
+    }
+
+    #[test]
+    fn rejects_collisions() {
+        let home = TempDir::new();
+        let base = Dirs::at(&home);
+        populate(&home, "old", &[".cache", ".config"]);
+        populate(&home, "new", &[".config"]);
+        let rename = rename_app("old", "new");
+        let collisions = rename
+            .plan_in(&base)
+            .into_iter()
+            .map(|planned| planned.is_collision())
+            .collect::<Vec<_>>();
+        assert_eq!(collisions, [false, true]);
+        let err = rename.run_in(&base).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        // Nothing is moved.
+        assert!(home.join(".cache").join("old").exists());
+        assert!(!home.join(".cache").join("new").exists());
+    }
+
+    #[test]
+    fn rolls_back_failed_moves() {
+        let home = TempDir::new();
+        let base = Dirs::at(&home);
+        populate(&home, "old", &[".cache", ".config", ".local/state"]);
+        // The state directory cannot be moved, as its new parent is a file.
+        fs::write(home.join(".local/state/new"), "").unwrap();
+        rename_app("old", "new/app").run_in(&base).unwrap_err();
+        for dir in [".cache", ".config", ".local/state"] {
+            let file = home.join(dir).join("old").join("file");
+            assert_eq!(fs::read_to_string(file).unwrap(), dir);
+            assert!(!home.join(dir).join("new").join("app").exists());
+        }
+    }
+}
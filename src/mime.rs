@@ -0,0 +1,70 @@
+//! Registration of MIME types.
+//!
+//! Custom MIME types are described by XML packages installed into the user's
+//! [MIME database](crate::data::mime), as per the [Shared MIME-info Database
+//! Specification][spec]. The database must be rebuilt for changes to take
+//! effect.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+//! <mime-info xmlns="http://www.freedesktop.org/standards/shared-mime-info">
+//!   <mime-type type="application/x-myapp">
+//!     <comment>My App document</comment>
+//!     <glob pattern="*.myapp"/>
+//!   </mime-type>
+//! </mime-info>
+//! "#;
+//! xdir::mime::install("myapp", xml).unwrap();
+//! ```
+//!
+//! [spec]: https://specifications.freedesktop.org/shared-mime-info-spec/latest/
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::data::mime;
+use crate::{atomic, cmd};
+
+/// Installs a MIME package into the user's database, returning its path.
+///
+/// The package is written to `packages/<name>.xml`, after which the database
+/// is [updated](update_database).
+///
+/// # Errors
+///
+/// Errors if the name is invalid, if the MIME directory could not be found, or
+/// if the package could not be written.
+pub fn install(name: &str, xml: &str) -> io::Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'));
+    if !valid {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid package name: {name:?}"),
+        ));
+    }
+    let path = dir()?.join("packages").join(format!("{name}.xml"));
+    atomic::write(&path, xml)?;
+    update_database()?;
+    Ok(path)
+}
+
+/// Updates the user's MIME database.
+///
+/// This runs `update-mime-database` if it is installed, and does nothing
+/// otherwise.
+///
+/// # Errors
+///
+/// Errors if the command could not be run or did not succeed.
+pub fn update_database() -> io::Result<()> {
+    cmd::run_if_installed("update-mime-database", dir()?)
+}
+
+fn dir() -> io::Result<PathBuf> {
+    mime().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find data directory"))
+}
@@ -0,0 +1,390 @@
+//! Single-flight creation of directories.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use crate::{App, DirSet};
+
+/// Creations in flight (or completed), by the paths they create.
+static FLIGHTS: Mutex<BTreeMap<Vec<PathBuf>, Arc<Flight>>> = Mutex::new(BTreeMap::new());
+
+/// Outcome of a creation, shared between its callers.
+type Outcome = Result<Vec<PathBuf>, (io::ErrorKind, String)>;
+
+/// A creation shared between its callers.
+#[derive(Debug, Default)]
+struct Flight {
+    state: Mutex<State>,
+    done: Condvar,
+}
+
+/// State of a creation.
+#[derive(Debug)]
+enum State {
+    /// Creation is in progress, with the tasks awaiting it.
+    Running(Vec<Waker>),
+    /// Creation has completed.
+    Done(Outcome),
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::Running(Vec::new())
+    }
+}
+
+impl Flight {
+    /// Locks the state of the creation.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Completes the creation, waking every caller awaiting it.
+    fn complete(&self, outcome: Outcome) {
+        let wakers = match std::mem::replace(&mut *self.lock(), State::Done(outcome)) {
+            State::Running(wakers) => wakers,
+            State::Done(_) => Vec::new(),
+        };
+        self.done.notify_all();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// A caller which performs a creation on behalf of the others.
+///
+/// Should creation panic, the others are released with an error.
+struct Leader<'a> {
+    key: &'a [PathBuf],
+    flight: &'a Flight,
+    done: bool,
+}
+
+impl Leader<'_> {
+    /// Performs the creation.
+    fn run(
+        mut self,
+        create: impl FnOnce() -> io::Result<Vec<PathBuf>>,
+    ) -> io::Result<Vec<PathBuf>> {
+        let result = create();
+        self.done = true;
+        self.finish(match &result {
+            Ok(paths) => Ok(paths.clone()),
+            Err(err) => Err((err.kind(), err.to_string())),
+        });
+        result
+    }
+
+    /// Completes the creation, forgetting it if it failed (such that it is
+    /// retried by later callers).
+    fn finish(&self, outcome: Outcome) {
+        if outcome.is_err() {
+            flights().remove(self.key);
+        }
+        self.flight.complete(outcome);
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.finish(Err((
+                io::ErrorKind::Other,
+                "directory creation panicked".to_string(),
+            )));
+        }
+    }
+}
+
+/// A future resolving once an application's directories are created.
+///
+/// See [`App::ensure_once_async`].
+#[must_use = "futures do nothing unless polled"]
+pub struct EnsureOnce {
+    app: App,
+    dirs: DirSet,
+    flight: Option<Arc<Flight>>,
+}
+
+impl Debug for EnsureOnce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnsureOnce")
+            .field("app", &self.app)
+            .field("dirs", &self.dirs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Future for EnsureOnce {
+    type Output = io::Result<Vec<PathBuf>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let flight = if let Some(flight) = &self.flight {
+            flight.clone()
+        } else {
+            let key = self.app.once_key(self.dirs)?;
+            let (flight, leader) = join(&key);
+            if leader {
+                return Poll::Ready(lead(&key, &flight, || self.app.ensure(self.dirs)));
+            }
+            self.flight = Some(flight.clone());
+            flight
+        };
+        let mut state = flight.lock();
+        match &mut *state {
+            State::Done(outcome) => Poll::Ready(restore(outcome)),
+            State::Running(wakers) => {
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl App {
+    /// Creates each of the given directories of the application once per
+    /// process, returning their paths.
+    ///
+    /// This behaves as [`ensure`](Self::ensure), except that concurrent callers
+    /// creating the same directories share a single creation: exactly one of
+    /// them creates the directories, while the others wait for its result.
+    /// Once created, later calls return immediately, without touching the
+    /// filesystem. Should creation fail, it is retried by the next caller.
+    ///
+    /// Directories removed after their creation are not recreated.
+    ///
+    /// # Errors
+    ///
+    /// Errors if any directory could not be found or created, or if ownership
+    /// is verified and it is owned by another user.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::thread;
+    ///
+    /// use xdir::{App, DirSet};
+    ///
+    /// let app = App::new("myapp");
+    /// thread::scope(|scope| {
+    ///     for _ in 0..8 {
+    ///         scope.spawn(|| app.ensure_once(DirSet::CACHE | DirSet::STATE).unwrap());
+    ///     }
+    /// });
+    /// ```
+    pub fn ensure_once(&self, dirs: DirSet) -> io::Result<Vec<PathBuf>> {
+        once(&self.once_key(dirs)?, || self.ensure(dirs))
+    }
+
+    /// Creates each of the given directories of the application once per
+    /// process, returning a future resolving to their paths.
+    ///
+    /// This behaves as [`ensure_once`](Self::ensure_once), except that callers
+    /// awaiting another's creation yield to their executor rather than block.
+    /// The creation itself is performed by whichever caller first polls its
+    /// future, on its own thread. The future does not depend on any particular
+    /// async runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xdir::{App, DirSet};
+    ///
+    /// async fn start(app: &App) -> std::io::Result<()> {
+    ///     app.ensure_once_async(DirSet::CACHE | DirSet::STATE).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn ensure_once_async(&self, dirs: DirSet) -> EnsureOnce {
+        EnsureOnce {
+            app: self.clone(),
+            dirs,
+            flight: None,
+        }
+    }
+
+    /// Returns the paths identifying a single-flight creation.
+    fn once_key(&self, dirs: DirSet) -> io::Result<Vec<PathBuf>> {
+        let base = self.resolve_all();
+        dirs.into_iter()
+            .map(|dir| {
+                self.dir_in(&base, dir).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("could not find {dir} directory"),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+/// Performs a single-flight creation of the given paths, blocking until it
+/// completes.
+fn once<F>(key: &[PathBuf], create: F) -> io::Result<Vec<PathBuf>>
+where
+    F: FnOnce() -> io::Result<Vec<PathBuf>>,
+{
+    let (flight, leader) = join(key);
+    if leader {
+        return lead(key, &flight, create);
+    }
+    let state = flight.lock();
+    let state = flight
+        .done
+        .wait_while(state, |state| matches!(state, State::Running(_)))
+        .unwrap_or_else(PoisonError::into_inner);
+    match &*state {
+        State::Done(outcome) => restore(outcome),
+        State::Running(_) => unreachable!(),
+    }
+}
+
+/// Performs a single-flight creation on behalf of its callers.
+fn lead<F>(key: &[PathBuf], flight: &Flight, create: F) -> io::Result<Vec<PathBuf>>
+where
+    F: FnOnce() -> io::Result<Vec<PathBuf>>,
+{
+    Leader {
+        key,
+        flight,
+        done: false,
+    }
+    .run(create)
+}
+
+/// Joins the creation of the given paths, returning whether the caller must
+/// perform it.
+fn join(key: &[PathBuf]) -> (Arc<Flight>, bool) {
+    let mut flights = flights();
+    if let Some(flight) = flights.get(key) {
+        return (flight.clone(), false);
+    }
+    let flight = Arc::new(Flight::default());
+    flights.insert(key.to_vec(), flight.clone());
+    (flight, true)
+}
+
+/// Locks the creations in flight.
+fn flights() -> MutexGuard<'static, BTreeMap<Vec<PathBuf>, Arc<Flight>>> {
+    FLIGHTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Restores the result of a creation from its shared outcome.
+fn restore(outcome: &Outcome) -> io::Result<Vec<PathBuf>> {
+    outcome
+        .clone()
+        .map_err(|(kind, msg)| io::Error::new(kind, msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::task::Wake;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    /// A waker counting how often it was woken.
+    #[derive(Default)]
+    struct Count(AtomicUsize);
+
+    impl Wake for Count {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn key(name: &str) -> Vec<PathBuf> {
+        vec![PathBuf::from("/xdir-test/once").join(name)]
+    }
+
+    #[test]
+    fn creates_once() {
+        let key = key("creates");
+        let (calls, barrier) = (AtomicUsize::new(0), Barrier::new(8));
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    let paths = once(&key, || {
+                        calls.fetch_add(1, Ordering::Relaxed);
+                        thread::sleep(Duration::from_millis(20));
+                        Ok(key.clone())
+                    });
+                    assert_eq!(paths.unwrap(), key);
+                });
+            }
+        });
+        // Later callers return without creating anything.
+        assert_eq!(once(&key, || unreachable!()).unwrap(), key);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn retries_failures() {
+        let key = key("retries");
+        let err = once(&key, || {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(once(&key, || Ok(key.clone())).unwrap(), key);
+    }
+
+    #[test]
+    fn releases_waiters_on_panic() {
+        let key = key("panics");
+        let (flight, leader) = join(&key);
+        assert!(leader);
+        thread::scope(|scope| {
+            let waiter = scope.spawn(|| once(&key, || unreachable!()));
+            // Wait for the waiter to join the creation in flight.
+            while Arc::strong_count(&flight) < 3 {
+                thread::yield_now();
+            }
+            let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+                lead(&key, &flight, || panic!("creation failed"))
+            }));
+            assert!(panicked.is_err());
+            assert_eq!(
+                waiter.join().unwrap().unwrap_err().kind(),
+                io::ErrorKind::Other
+            );
+        });
+        // As the creation panicked, it is retried.
+        assert_eq!(once(&key, || Ok(key.clone())).unwrap(), key);
+    }
+
+    #[test]
+    fn wakes_futures() {
+        let app = App::new("xdir-test-once");
+        let Ok(key) = app.once_key(DirSet::CACHE) else {
+            return;
+        };
+        let (flight, leader) = join(&key);
+        assert!(leader);
+        let count = Arc::new(Count::default());
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut future = app.ensure_once_async(DirSet::CACHE);
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        lead(&key, &flight, || Ok(key.clone())).unwrap();
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+        match Pin::new(&mut future).poll(&mut cx) {
+            Poll::Ready(paths) => assert_eq!(paths.unwrap(), key),
+            Poll::Pending => panic!("future pending after creation"),
+        }
+        flights().remove(&key);
+    }
+}
@@ -0,0 +1,120 @@
+//! Layered configuration.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{app, search};
+
+/// A read-only view merging the layers of a search path.
+///
+/// Each path is resolved against the most preferred layer in which it exists,
+/// such that files of the user's config directory take precedence over the
+/// vendor defaults of the system config directories.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xdir::App;
+///
+/// let overlay = App::new("myapp").config_overlay();
+/// let config = overlay.read_to_string("config.toml").unwrap();
+/// for theme in overlay.entries("themes") {
+///     println!("{}", theme.display());
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Overlay {
+    dirs: Vec<PathBuf>,
+}
+
+impl Overlay {
+    /// Constructs an overlay over the given layers, in order of preference.
+    #[must_use]
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs }
+    }
+
+    /// Returns the layers of the overlay, in order of preference.
+    #[must_use]
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Finds the path to a file within the most preferred layer containing it.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is not relative to the layers.
+    pub fn find(&self, path: impl AsRef<Path>) -> io::Result<Option<PathBuf>> {
+        Ok(self.find_all(path)?.into_iter().next())
+    }
+
+    /// Finds the paths to a file within every layer containing it, in order of
+    /// preference.
+    ///
+    /// This allows files to be merged across layers, rather than shadowed.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is not relative to the layers.
+    pub fn find_all(&self, path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let path = path.as_ref();
+        let mut found = Vec::new();
+        for dir in &self.dirs {
+            let path = app::join(dir, path)?;
+            if path.is_file() {
+                found.push(path);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Reads a file from the most preferred layer containing it.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is not relative to the layers, if no layer contains
+    /// the file, or if it could not be read.
+    pub fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        fs::read(self.locate(path.as_ref())?)
+    }
+
+    /// Reads a file from the most preferred layer containing it, as a string.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the path is not relative to the layers, if no layer contains
+    /// the file, or if it could not be read as UTF-8.
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        fs::read_to_string(self.locate(path.as_ref())?)
+    }
+
+    /// Lists the merged entries of a directory across all layers.
+    ///
+    /// Entries are ordered by their layer's preference, then by name. Entries
+    /// shadowed by a more preferred one of the same name are skipped. An empty
+    /// path lists the layers themselves; invalid paths list nothing.
+    #[must_use]
+    pub fn entries(&self, path: impl AsRef<Path>) -> Vec<PathBuf> {
+        let path = path.as_ref();
+        let dirs = if path.as_os_str().is_empty() {
+            self.dirs.clone()
+        } else {
+            self.dirs
+                .iter()
+                .filter_map(|dir| app::join(dir, path).ok())
+                .collect()
+        };
+        search::list(&dirs, true)
+    }
+
+    fn locate(&self, path: &Path) -> io::Result<PathBuf> {
+        self.find(path)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("could not find {} in any layer", path.display()),
+            )
+        })
+    }
+}
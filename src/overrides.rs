@@ -0,0 +1,382 @@
+//! Per-user override file.
+//!
+//! Directory locations may be relocated declaratively through an override
+//! file, found at `xdir.toml` within the user's config directory. It maps
+//! directory kinds to custom locations, either for all applications or for a
+//! single [application](crate::App):
+//!
+//! ```toml
+//! # Relocate all caches to a scratch disk.
+//! cache = "/scratch/cache"
+//!
+//! # Relocate only the data of `myapp`.
+//! [app.myapp]
+//! data = "~/Archive/myapp"
+//! ```
+//!
+//! Keys are the names of each [`Dir`] (i.e. `bin`, `cache`, `config`, `data`,
+//! `runtime` and `state`), and values are absolute paths, optionally starting
+//! with `~/` to refer to the user's home directory. Application overrides are
+//! used as-is, without being scoped by the application's name.
+//!
+//! Alternatively, overrides may be given in dotenv format, at `xdir.env`
+//! within the user's config directory (which is only read where `xdir.toml`
+//! does not exist). Keys are then named after each directory's variable, or
+//! that of an application's directory (see
+//! [`App::env_vars`](crate::App::env_vars)):
+//!
+//! ```sh
+//! # Relocate all caches to a scratch disk.
+//! XDG_CACHE_HOME=/scratch/cache
+//!
+//! # Relocate only the data of `myapp`.
+//! MYAPP_DATA_DIR="~/Archive/myapp"
+//! ```
+//!
+//! Overrides take precedence over the default locations, but not over those
+//! set explicitly through the environment. Unknown keys, values which are not
+//! strings and relative paths are ignored, as is a malformed TOML file (or the
+//! malformed lines of a dotenv file).
+//!
+//! The override file is read once, when first needed, and cached for the
+//! lifetime of the process (unless the config directory changes). Call
+//! [`reload`] to read it again once it has been modified.
+//!
+//! The override file can be disabled by turning off the `overrides` feature.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{app, home, Dir};
+
+/// Names of the override file, in order of precedence.
+const NAMES: [&str; 2] = ["xdir.toml", "xdir.env"];
+
+/// Override file last read, along with the config directory containing it.
+static CACHE: Mutex<Option<(PathBuf, Option<Arc<Overrides>>)>> = Mutex::new(None);
+
+/// Overridden locations, as read from the override file.
+#[derive(Debug, Default)]
+pub(crate) struct Overrides {
+    /// Locations of each directory.
+    dirs: [Option<String>; 6],
+    /// Locations of each application's directories, by application name (or
+    /// by the prefix of its variables, in dotenv format).
+    apps: BTreeMap<String, [Option<String>; 6]>,
+    /// Whether applications are keyed by the prefix of their variables.
+    prefixed: bool,
+}
+
+impl Overrides {
+    /// Parses an override file in TOML format.
+    pub(crate) fn from_toml(text: &str) -> Self {
+        let mut overrides = Self::default();
+        let Ok(table) = text.parse::<toml::Table>() else {
+            return overrides;
+        };
+        overrides.dirs = entries(&table);
+        if let Some(apps) = table.get("app").and_then(toml::Value::as_table) {
+            overrides.apps = apps
+                .iter()
+                .filter_map(|(name, table)| Some((name.clone(), entries(table.as_table()?))))
+                .collect();
+        }
+        overrides
+    }
+
+    /// Parses an override file in dotenv format.
+    pub(crate) fn from_env(text: &str) -> Self {
+        let mut overrides = Self {
+            prefixed: true,
+            ..Self::default()
+        };
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let Some(value) = unquote(value.trim()) else {
+                continue;
+            };
+            let key = key.trim();
+            if let Some(dir) = Dir::ALL.into_iter().find(|dir| dir.var() == key) {
+                overrides.dirs[dir as usize] = Some(value);
+            } else if let Some((prefix, dir)) = Dir::ALL.into_iter().find_map(|dir| {
+                let suffix = format!("_{}_DIR", dir.to_string().to_uppercase());
+                Some((key.strip_suffix(&suffix)?, dir))
+            }) {
+                overrides.apps.entry(prefix.to_string()).or_default()[dir as usize] = Some(value);
+            }
+        }
+        overrides
+    }
+
+    /// Finds the overridden location of a directory, or of an application's
+    /// directory, using the given lookup of the home directory.
+    pub(crate) fn find(
+        &self,
+        app: Option<&str>,
+        dir: Dir,
+        home: &dyn Fn() -> Option<PathBuf>,
+    ) -> Option<PathBuf> {
+        let value = match app {
+            None => self.dirs[dir as usize].as_ref(),
+            Some(_) if dir == Dir::Bin => None,
+            Some(name) if self.prefixed => {
+                self.apps.get(&app::var_prefix(name))?[dir as usize].as_ref()
+            }
+            Some(name) => self.apps.get(name)?[dir as usize].as_ref(),
+        };
+        expand(value?, home)
+    }
+}
+
+/// Returns the path to the override file.
+///
+/// This is `xdir.toml` within the config directory, unless only `xdir.env`
+/// exists. It is always resolved from the environment (or its default), as the
+/// override file cannot relocate itself.
+#[must_use]
+pub fn path() -> Option<PathBuf> {
+    let dir = config()?;
+    NAMES
+        .into_iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .or_else(|| Some(dir.join(NAMES[0])))
+}
+
+/// Returns the overridden location of a directory, if any.
+#[must_use]
+pub fn dir(dir: Dir) -> Option<PathBuf> {
+    load()?.find(None, dir, &home)
+}
+
+/// Returns the overridden location of an application's directory, if any.
+#[must_use]
+pub fn app(name: &str, dir: Dir) -> Option<PathBuf> {
+    load()?.find(Some(name), dir, &home)
+}
+
+/// Reads the override file again.
+///
+/// Changes to the override file are otherwise only observed by later
+/// resolutions if the config directory changes.
+pub fn reload() {
+    *CACHE.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Returns the override file, reading it unless cached.
+pub(crate) fn load() -> Option<Arc<Overrides>> {
+    let dir = config()?;
+    let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    match &*cache {
+        Some((cached, overrides)) if *cached == dir => overrides.clone(),
+        _ => {
+            let overrides = read(&dir).map(Arc::new);
+            *cache = Some((dir, overrides.clone()));
+            overrides
+        }
+    }
+}
+
+/// Returns the config directory containing the override file.
+fn config() -> Option<PathBuf> {
+    crate::resolve::resolve(Dir::Config, false).map(crate::Resolved::into_path)
+}
+
+/// Reads the override file within a directory, if it exists.
+fn read(dir: &std::path::Path) -> Option<Overrides> {
+    if let Ok(text) = fs::read_to_string(dir.join(NAMES[0])) {
+        return Some(Overrides::from_toml(&text));
+    }
+    fs::read_to_string(dir.join(NAMES[1]))
+        .ok()
+        .map(|text| Overrides::from_env(&text))
+}
+
+/// Returns the string values of a TOML table, by directory.
+fn entries(table: &toml::Table) -> [Option<String>; 6] {
+    Dir::ALL.map(|dir| {
+        table
+            .get(&dir.to_string())
+            .and_then(toml::Value::as_str)
+            .map(String::from)
+    })
+}
+
+/// Parses a dotenv value, which may be quoted.
+///
+/// Double-quoted values support escapes of `\\`, `"`, `$` and `\t`, whereas
+/// single-quoted values are taken literally. Unquoted values end at a comment.
+fn unquote(text: &str) -> Option<String> {
+    let mut chars = text.char_indices();
+    let Some((_, quote @ ('"' | '\''))) = chars.next() else {
+        let value = text.find(" #").map_or(text, |idx| &text[..idx]);
+        return Some(value.trim_end().to_string());
+    };
+    let mut value = String::new();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            ch if ch == quote => {
+                let rest = text[idx + 1..].trim_start();
+                return (rest.is_empty() || rest.starts_with('#')).then_some(value);
+            }
+            '\\' if quote == '"' => match chars.next()?.1 {
+                ch @ ('\\' | '"' | '$') => value.push(ch),
+                't' => value.push('\t'),
+                _ => return None,
+            },
+            ch => value.push(ch),
+        }
+    }
+    None
+}
+
+/// Expands a leading `~` to the user's home directory, discarding relative
+/// paths.
+fn expand(value: &str, home: &dyn Fn() -> Option<PathBuf>) -> Option<PathBuf> {
+    let path = match value.strip_prefix('~') {
+        Some("") => home()?,
+        Some(rest) if rest.starts_with(['/', '\\']) => home()?.join(&rest[1..]),
+        _ => PathBuf::from(value),
+    };
+    path.is_absolute().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// Home directory of the tests.
+    #[allow(clippy::unnecessary_wraps)]
+    fn home() -> Option<PathBuf> {
+        Some(PathBuf::from(if cfg!(windows) {
+            r"C:\home"
+        } else {
+            "/home"
+        }))
+    }
+
+    /// An absolute path, on every platform.
+    fn abs(path: &str) -> PathBuf {
+        if cfg!(windows) {
+            Path::new("C:\\").join(path.trim_start_matches('/'))
+        } else {
+            PathBuf::from(path)
+        }
+    }
+
+    #[test]
+    fn toml_globals_and_apps() {
+        let root = abs("/scratch").display().to_string().replace('\\', "/");
+        let text = format!(
+            r#"
+            # Relocate all caches.
+            cache = "{root}/cache"
+            data = 'relative'
+            state = 42
+            unknown = "{root}/unknown"
+
+            [app.myapp]
+            data = "~/Archive/myapp"
+            bin = "{root}/bin"
+
+            [app."other app"]
+            config = "{root}/other"
+            "#
+        );
+        let overrides = Overrides::from_toml(&text);
+        let find = |app, dir| overrides.find(app, dir, &home);
+        assert_eq!(find(None, Dir::Cache), Some(abs("/scratch/cache")));
+        assert_eq!(find(None, Dir::Data), None);
+        assert_eq!(find(None, Dir::State), None);
+        assert_eq!(find(None, Dir::Config), None);
+        let archive = home().unwrap().join("Archive/myapp");
+        assert_eq!(find(Some("myapp"), Dir::Data), Some(archive));
+        assert_eq!(find(Some("myapp"), Dir::Cache), None);
+        assert_eq!(find(Some("myapp"), Dir::Bin), None);
+        assert_eq!(
+            find(Some("other app"), Dir::Config),
+            Some(abs("/scratch/other"))
+        );
+        assert_eq!(find(Some("missing"), Dir::Config), None);
+    }
+
+    #[test]
+    fn toml_malformed() {
+        let overrides = Overrides::from_toml("cache = \"/scratch\"\nthis is not toml");
+        assert_eq!(overrides.find(None, Dir::Cache, &home), None);
+    }
+
+    #[test]
+    fn env_globals_and_apps() {
+        let root = abs("/scratch").display().to_string();
+        let text = format!(
+            r#"
+            # Relocate all caches.
+            export XDG_CACHE_HOME={root}/cache # scratch disk
+            XDG_DATA_HOME=relative
+            XDG_STATE_HOME="{root}/with \"quotes\""
+            XDG_CONFIG_HOME='{root}/single $HOME'
+            malformed line
+            MY_APP_DATA_DIR="~/Archive/myapp"
+            MY_APP_BIN_DIR={root}/bin
+            "#
+        );
+        let overrides = Overrides::from_env(&text);
+        let find = |app, dir| overrides.find(app, dir, &home);
+        assert_eq!(find(None, Dir::Cache), Some(abs("/scratch/cache")));
+        assert_eq!(find(None, Dir::Data), None);
+        assert_eq!(
+            find(None, Dir::State),
+            Some(abs("/scratch/with \"quotes\""))
+        );
+        assert_eq!(find(None, Dir::Config), Some(abs("/scratch/single $HOME")));
+        let archive = home().unwrap().join("Archive/myapp");
+        assert_eq!(find(Some("my-app"), Dir::Data), Some(archive));
+        assert_eq!(find(Some("my-app"), Dir::Bin), None);
+        assert_eq!(find(Some("myapp"), Dir::Data), None);
+    }
+
+    #[test]
+    fn unquote_values() {
+        assert_eq!(unquote("plain").as_deref(), Some("plain"));
+        assert_eq!(unquote("with # comment").as_deref(), Some("with"));
+        assert_eq!(unquote("\"a\\tb\" # comment").as_deref(), Some("a\tb"));
+        assert_eq!(unquote("'a\\tb'").as_deref(), Some("a\\tb"));
+        assert_eq!(unquote("\"unterminated"), None);
+        assert_eq!(unquote("\"quoted\" trailing"), None);
+        assert_eq!(unquote("\"bad \\q escape\""), None);
+    }
+
+    #[test]
+    fn read_prefers_toml() {
+        let tmp = crate::testing::TempDir::new();
+        assert!(read(&tmp).is_none());
+        let root = abs("/scratch").display().to_string();
+        fs::write(tmp.join("xdir.env"), format!("XDG_CACHE_HOME={root}/env")).unwrap();
+        let find = |overrides: Overrides| overrides.find(None, Dir::Cache, &home);
+        assert_eq!(find(read(&tmp).unwrap()), Some(abs("/scratch/env")));
+        let root = root.replace('\\', "/");
+        fs::write(tmp.join("xdir.toml"), format!("cache = '{root}/toml'")).unwrap();
+        assert_eq!(find(read(&tmp).unwrap()), Some(abs("/scratch/toml")));
+    }
+
+    #[test]
+    fn expand_home() {
+        assert_eq!(expand("~", &home), home());
+        assert_eq!(expand("~/a", &home), Some(home().unwrap().join("a")));
+        assert_eq!(expand("~user/a", &home), None);
+        assert_eq!(expand("a/b", &home), None);
+        assert_eq!(expand("~/a", &|| None), None);
+    }
+}
@@ -0,0 +1,59 @@
+//! Ownership of directories.
+//!
+//! Running an application as another user (most commonly through `sudo`) can
+//! leave behind directories the user can no longer write to. Rather than
+//! failing later with an unexplained permission error, directories can be
+//! [verified](verify) to be owned by the effective user before use.
+//!
+//! Ownership is only checked on Unix; on other platforms, all directories are
+//! considered to be owned by the user.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Verifies that a path is owned by the effective user.
+///
+/// # Errors
+///
+/// Errors if the path could not be accessed, or is owned by another user.
+pub fn verify(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let uid = fs::symlink_metadata(path)?.uid();
+        let euid = crate::sys::euid();
+        if uid != euid {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "{} is owned by uid {uid}, not the current user (uid {euid}); it may have \
+                     been created by running as another user (e.g. with sudo)",
+                    path.display()
+                ),
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    fs::symlink_metadata(path)?;
+    Ok(())
+}
+
+/// Recursively creates a directory, verifying that it is owned by the
+/// effective user.
+///
+/// Any parent directories which must be created are verified as well, but
+/// pre-existing ancestors (e.g. `/home`) are not.
+///
+/// # Errors
+///
+/// Errors if a directory could not be created, or is owned by another user.
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    let created = path
+        .ancestors()
+        .take_while(|path| !path.as_os_str().is_empty() && !path.exists())
+        .count();
+    crate::sys::create_dir_all(path)?;
+    path.ancestors().take(created.max(1)).try_for_each(verify)
+}
@@ -0,0 +1,103 @@
+//! Permissions of created directories and files.
+
+use std::io;
+use std::path::Path;
+
+use crate::Dir;
+
+/// Permissions policy of an application's directories and files.
+///
+/// Modes are given per kind of directory as Unix permission bits (e.g. `0o700`
+/// for the state directory), and are applied to the directories and files
+/// created by [`App`](crate::App), regardless of the process's umask. Kinds
+/// without a mode are created with the platform's defaults.
+///
+/// On Windows, modes granting no access to the group or others are mapped to
+/// an access control list granting access to the owner only. Other modes leave
+/// the inherited access control list as-is.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xdir::{App, Dir, Permissions};
+///
+/// let perms = Permissions::new()
+///     .dir(Dir::State, 0o700)
+///     .file(Dir::State, 0o600)
+///     .dir(Dir::Data, 0o755);
+/// let app = App::new("myapp").permissions(perms);
+/// app.create(Dir::State).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Permissions {
+    dirs: [Option<u32>; 6],
+    files: [Option<u32>; 6],
+}
+
+impl Permissions {
+    /// Constructs an empty policy, using the platform's defaults.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            dirs: [None; 6],
+            files: [None; 6],
+        }
+    }
+
+    /// Constructs a policy under which everything is private to its owner.
+    ///
+    /// Directories are created with mode `0o700`, and files with `0o600`.
+    #[must_use]
+    pub const fn private() -> Self {
+        Self {
+            dirs: [Some(0o700); 6],
+            files: [Some(0o600); 6],
+        }
+    }
+
+    /// Sets the mode of created directories of the given kind.
+    #[must_use]
+    pub const fn dir(mut self, dir: Dir, mode: u32) -> Self {
+        self.dirs[dir as usize] = Some(mode & 0o7777);
+        self
+    }
+
+    /// Sets the mode of created files within directories of the given kind.
+    #[must_use]
+    pub const fn file(mut self, dir: Dir, mode: u32) -> Self {
+        self.files[dir as usize] = Some(mode & 0o7777);
+        self
+    }
+
+    /// Returns the mode of created directories of the given kind, if any.
+    #[must_use]
+    pub const fn dir_mode(&self, dir: Dir) -> Option<u32> {
+        self.dirs[dir as usize]
+    }
+
+    /// Returns the mode of created files within directories of the given kind,
+    /// if any.
+    #[must_use]
+    pub const fn file_mode(&self, dir: Dir) -> Option<u32> {
+        self.files[dir as usize]
+    }
+}
+
+/// Applies a mode to a directory or file.
+#[cfg(unix)]
+pub(crate) fn apply(path: &Path, mode: u32) -> io::Result<()> {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+/// Applies a mode to a directory or file.
+#[cfg(not(unix))]
+pub(crate) fn apply(path: &Path, mode: u32) -> io::Result<()> {
+    #[allow(clippy::verbose_bit_mask)]
+    if mode & 0o077 == 0 {
+        crate::sys::restrict(path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! Portable serialization of paths.
+//!
+//! Absolute paths stored within files which are synced between machines (e.g.
+//! a list of recently opened files) break as soon as the user's name, home
+//! directory or operating system differ. Paths may instead be [encoded](encode)
+//! relative to the standard directories containing them, using symbolic
+//! prefixes (e.g. `$XDG_DATA_HOME/myapp/library.db`), which are
+//! [decoded](decode) against the directories of the machine reading them.
+//!
+//! Prefixes are named after each directory's environment variable (see
+//! [`Dir::var`]), or `$HOME` for the user's home directory. Separators are
+//! always encoded as `/`.
+//!
+//! # Examples
+//!
+//! ```
+//! # if let Some(data) = xdir::data() {
+//! let path = data.join("myapp").join("library.db");
+//! let encoded = xdir::portable::encode(&path).unwrap();
+//! assert_eq!(encoded, "$XDG_DATA_HOME/myapp/library.db");
+//! assert_eq!(xdir::portable::decode(&encoded).unwrap(), path);
+//! # }
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{resolve, Dir};
+
+/// Prefix of the user's home directory.
+const HOME: &str = "HOME";
+
+/// Encodes a path, replacing the standard directory containing it by its
+/// symbolic prefix.
+///
+/// The most specific directory is chosen, such that a path within the data
+/// directory is encoded relative to it, rather than to the home directory.
+/// Paths outside of every directory are encoded as-is (with `/` separators).
+///
+/// # Errors
+///
+/// Errors if the path is not valid Unicode.
+pub fn encode(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    let prefixes = Dir::ALL
+        .into_iter()
+        .filter_map(|dir| Some((dir.var(), dir.path()?)))
+        .chain(crate::home().map(|home| (HOME, home)));
+    let (path, prefix) = prefixes
+        .filter_map(|(var, dir)| Some((path.strip_prefix(&dir).ok()?, var, dir)))
+        .max_by_key(|(_, _, dir)| dir.components().count())
+        .map_or((path, None), |(rest, var, _)| (rest, Some(var)));
+    let text = path.to_str().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("path is not valid Unicode: {}", crate::escape_path(path)),
+        )
+    })?;
+    let text = if cfg!(windows) {
+        text.replace('\\', "/")
+    } else {
+        text.to_string()
+    };
+    Ok(match prefix {
+        Some(var) if text.is_empty() => format!("${var}"),
+        Some(var) => format!("${var}/{text}"),
+        None => text,
+    })
+}
+
+/// Decodes a path encoded by [`encode`].
+///
+/// Symbolic prefixes are replaced by the directories of the current process
+/// (as resolved by [`Dir::path`]), even where their environment variable is
+/// unset.
+///
+/// # Errors
+///
+/// Errors if the prefix is unknown, or if its directory could not be found.
+pub fn decode(text: &str) -> io::Result<PathBuf> {
+    let Some(text) = text.strip_prefix('$') else {
+        return Ok(resolve::native(PathBuf::from(text)));
+    };
+    let (var, rest) = text.split_once('/').unwrap_or((text, ""));
+    let (name, dir) = if var == HOME {
+        ("home".to_string(), crate::home())
+    } else {
+        let dir = Dir::ALL
+            .into_iter()
+            .find(|dir| dir.var() == var)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown path prefix: ${var}"),
+                )
+            })?;
+        (dir.to_string(), dir.path())
+    };
+    let dir = dir.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find {name} directory"),
+        )
+    })?;
+    Ok(rest
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .fold(dir, |path, part| path.join(part)))
+}
@@ -0,0 +1,75 @@
+//! Document portal of sandboxed applications.
+//!
+//! Sandboxed applications (e.g. Flatpak) are granted access to files chosen by
+//! the user through the [document portal][portal], which exposes them beneath
+//! a FUSE mount within the runtime directory. Paths received this way are
+//! specific to the mount, but each contains a stable document identifier that
+//! persists for as long as access is granted.
+//!
+//! [portal]: https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Documents.html
+
+use std::env;
+use std::path::{Component, Path, PathBuf};
+
+use crate::runtime;
+
+/// Checks whether the application is running within a sandbox.
+#[must_use]
+pub fn is_sandboxed() -> bool {
+    Path::new("/.flatpak-info").exists()
+        || env::var_os("FLATPAK_ID").is_some()
+        || env::var_os("SNAP").is_some()
+}
+
+/// Returns the path to the document portal's mount point.
+#[must_use]
+pub fn documents() -> Option<PathBuf> {
+    runtime().map(|path| path.join("doc"))
+}
+
+/// A document exported through the portal.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Document {
+    id: String,
+    path: PathBuf,
+}
+
+impl Document {
+    /// Returns the document's stable identifier.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the path of the file within the document (usually just its
+    /// name).
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Translates a path beneath the document portal into its document.
+///
+/// Both the `<id>/<name>` and `by-app/<app>/<id>/<name>` layouts are
+/// recognized. Returns [`None`] if the path is not beneath the portal's mount
+/// point.
+#[must_use]
+pub fn document(path: &Path) -> Option<Document> {
+    let rest = path.strip_prefix(documents()?).ok()?;
+    let mut parts = rest.components().map(|part| match part {
+        Component::Normal(part) => Some(part),
+        _ => None,
+    });
+    let mut id = parts.next()??;
+    if id == "by-app" {
+        parts.next()??;
+        id = parts.next()??;
+    }
+    let id = id.to_str()?;
+    let path = parts.collect::<Option<PathBuf>>()?;
+    Some(Document {
+        id: id.to_string(),
+        path,
+    })
+}
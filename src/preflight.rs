@@ -0,0 +1,314 @@
+//! Startup self-checks of applications.
+//!
+//! Problems with an application's directories (e.g. a directory left behind
+//! by running as another user) usually surface later as unexplained errors.
+//! Applications may instead run a [preflight](App::preflight) check at startup,
+//! reporting each problem found, and offering to fix those which can be fixed
+//! automatically.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::App;
+//!
+//! let app = App::new("myapp");
+//! for problem in app.preflight() {
+//!     if problem.is_fixable() {
+//!         eprintln!("fixing: {problem}");
+//!         problem.fix().unwrap();
+//!     } else {
+//!         eprintln!("error: {problem}");
+//!     }
+//! }
+//! ```
+
+use std::fmt::{self, Debug, Display};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::migrate::{Migrations, Step};
+use crate::{perms, App, Dir, DirSet};
+
+/// Directories which are checked by default.
+const DEFAULT: DirSet = DirSet::CACHE
+    .union(DirSet::CONFIG)
+    .union(DirSet::DATA)
+    .union(DirSet::RUNTIME)
+    .union(DirSet::STATE);
+
+/// A fix of a problem.
+type Fix = Box<dyn Fn() -> io::Result<()> + Send + Sync>;
+
+/// A problem detected with one of an application's directories.
+pub struct Problem {
+    dir: Dir,
+    path: PathBuf,
+    issue: Issue,
+    fix: Option<Fix>,
+}
+
+impl Problem {
+    /// Returns the kind of directory.
+    #[must_use]
+    pub fn dir(&self) -> Dir {
+        self.dir
+    }
+
+    /// Returns the path to the directory.
+    ///
+    /// This is empty if the directory could not be found.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the detected problem.
+    #[must_use]
+    pub fn issue(&self) -> &Issue {
+        &self.issue
+    }
+
+    /// Checks whether the problem can be fixed automatically.
+    #[must_use]
+    pub fn is_fixable(&self) -> bool {
+        self.fix.is_some()
+    }
+
+    /// Fixes the problem.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the problem cannot be fixed automatically, or if fixing it
+    /// failed.
+    pub fn fix(&self) -> io::Result<()> {
+        let fix = self.fix.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("cannot fix automatically: {self}"),
+            )
+        })?;
+        fix()
+    }
+}
+
+impl Debug for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Problem")
+            .field("dir", &self.dir)
+            .field("path", &self.path)
+            .field("issue", &self.issue)
+            .field("fixable", &self.is_fixable())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Issue::Unresolved = self.issue {
+            return write!(f, "{} directory could not be found", self.dir);
+        }
+        write!(f, "{} directory `{}` ", self.dir, self.path.display())?;
+        match &self.issue {
+            Issue::Unresolved => Ok(()),
+            Issue::Missing => write!(f, "does not exist"),
+            Issue::Inaccessible => write!(f, "could not be accessed"),
+            Issue::NotDirectory => write!(f, "is not a directory"),
+            Issue::Owner { uid } => write!(f, "is owned by another user (uid {uid})"),
+            Issue::Mode { mode, expected } => {
+                write!(f, "has mode {mode:04o}, rather than {expected:04o}")
+            }
+            Issue::NotWritable => write!(f, "is not writable"),
+            Issue::Unmigrated { from } => {
+                write!(
+                    f,
+                    "does not exist, but version {from} has not been migrated"
+                )
+            }
+        }
+    }
+}
+
+/// Kinds of problems.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Issue {
+    /// Directory could not be found (e.g. as `$HOME` is unset).
+    Unresolved,
+    /// Directory does not exist.
+    ///
+    /// Fixed by creating the directory.
+    Missing,
+    /// Directory could not be accessed (e.g. as one of its parents cannot be
+    /// searched).
+    Inaccessible,
+    /// Path exists, but is not a directory.
+    NotDirectory,
+    /// Directory is not owned by the effective user.
+    Owner {
+        /// User ID of the directory's owner.
+        uid: u32,
+    },
+    /// Directory's permissions differ from the application's
+    /// [policy](crate::Permissions), or are unsafe (as reported by `audit`).
+    ///
+    /// Fixed by changing the directory's mode.
+    Mode {
+        /// Permission bits of the directory.
+        mode: u32,
+        /// Expected permission bits.
+        expected: u32,
+    },
+    /// Directory cannot be written to.
+    ///
+    /// Fixed by granting the owner write permission, if it is missing.
+    NotWritable,
+    /// Directory of a [versioned](App::versioned) application does not exist,
+    /// but that of an earlier version does.
+    ///
+    /// Fixed by [carrying over](crate::migrate::Step::carry_over) the earlier
+    /// version's directories.
+    Unmigrated {
+        /// Version which was found.
+        from: u64,
+    },
+}
+
+impl App {
+    /// Checks the application's directories, returning any problems found.
+    ///
+    /// The cache, config, data, runtime and state directories are checked. See
+    /// [`preflight_dirs`](Self::preflight_dirs) for details.
+    #[must_use]
+    pub fn preflight(&self) -> Vec<Problem> {
+        self.preflight_dirs(DEFAULT)
+    }
+
+    /// Checks the given directories of the application, returning any problems
+    /// found.
+    ///
+    /// Each directory is checked to exist, to be owned by the effective user,
+    /// to have the mode given by the application's
+    /// [permissions](Self::permissions) (or otherwise a safe mode), and to be
+    /// writable. Writability is checked by creating (and removing) a file
+    /// within the directory, unless the application is in read-only mode.
+    /// Ownership and modes are only checked on Unix.
+    #[must_use]
+    pub fn preflight_dirs(&self, dirs: DirSet) -> Vec<Problem> {
+        let base = self.resolve_all();
+        let mut problems = Vec::new();
+        for dir in dirs - DirSet::BIN {
+            let Some(path) = self.dir_in(&base, dir) else {
+                problems.push(Problem {
+                    dir,
+                    path: PathBuf::new(),
+                    issue: Issue::Unresolved,
+                    fix: None,
+                });
+                continue;
+            };
+            let mut report = |issue, fix| {
+                problems.push(Problem {
+                    dir,
+                    path: path.clone(),
+                    issue,
+                    fix,
+                });
+            };
+            let meta = match fs::metadata(&path) {
+                Ok(meta) => meta,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    if let Some((from, to)) = self.unmigrated(dir) {
+                        let app = self.clone();
+                        let fix: Fix = Box::new(move || {
+                            Migrations::new(&app)
+                                .register_migration(from, to, |step: &Step<'_>| step.carry_over())
+                                .run()
+                                .map(drop)
+                        });
+                        report(Issue::Unmigrated { from }, Some(fix));
+                    } else {
+                        let app = self.clone();
+                        let fix: Fix = Box::new(move || {
+                            let path = app.create(dir)?;
+                            // Runtime directories must be private to the user.
+                            if dir == Dir::Runtime && app.permissions_ref().dir_mode(dir).is_none()
+                            {
+                                perms::apply(&path, 0o700)?;
+                            }
+                            Ok(())
+                        });
+                        report(Issue::Missing, Some(fix));
+                    }
+                    continue;
+                }
+                Err(_) => {
+                    report(Issue::Inaccessible, None);
+                    continue;
+                }
+            };
+            if !meta.is_dir() {
+                report(Issue::NotDirectory, None);
+                continue;
+            }
+            #[cfg(unix)]
+            let owned = {
+                use std::os::unix::fs::MetadataExt;
+
+                let uid = meta.uid();
+                let owned = uid == crate::sys::euid();
+                if !owned {
+                    report(Issue::Owner { uid }, None);
+                }
+                let mode = meta.mode() & 0o7777;
+                let expected = self.permissions_ref().dir_mode(dir).unwrap_or(match dir {
+                    Dir::Runtime => 0o700,
+                    Dir::Config | Dir::State => mode & !0o022,
+                    _ => mode,
+                });
+                if owned && mode != expected {
+                    let path = path.clone();
+                    let fix: Fix = Box::new(move || perms::apply(&path, expected));
+                    report(Issue::Mode { mode, expected }, Some(fix));
+                }
+                owned.then_some(mode)
+            };
+            #[cfg(not(unix))]
+            let owned = None::<u32>;
+            if self.check_writable().is_ok() && !is_writable(&path) {
+                // Only a missing write permission of the owner can be granted.
+                let fix = owned.filter(|mode| mode & 0o200 == 0).map(|mode| {
+                    let path = path.clone();
+                    Box::new(move || perms::apply(&path, mode | 0o200)) as Fix
+                });
+                report(Issue::NotWritable, fix);
+            }
+        }
+        problems
+    }
+
+    /// Returns the earlier and current version of a versioned directory, if
+    /// the former has not been migrated.
+    fn unmigrated(&self, dir: Dir) -> Option<(u64, u64)> {
+        let current = self.version()?;
+        if !matches!(dir, Dir::Data | Dir::State) {
+            return None;
+        }
+        Migrations::new(self)
+            .previous(current)
+            .map(|from| (from, current))
+    }
+}
+
+/// Checks whether a directory can be written to, by creating a file within it.
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".xdir-preflight-{}", process::id()));
+    match File::create_new(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            true
+        }
+        Err(err) => err.kind() == io::ErrorKind::AlreadyExists,
+    }
+}
@@ -0,0 +1,63 @@
+//! Promotion of files between directories.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use crate::{app, transfer, Dir};
+
+/// Moves a file (or directory) from one of the user's directories to another,
+/// returning its new path.
+///
+/// The path is relative to both directories (e.g. `myapp/report.pdf`), such
+/// that a generated artifact can be kept by moving it from the cache to the
+/// data directory. Parent directories are created as needed. Should the
+/// directories be on different filesystems, the file is copied instead,
+/// preserving its permissions and modification time, before the original is
+/// removed.
+///
+/// # Errors
+///
+/// Errors if the directories are the same or could not be found, if the path
+/// is not relative to them, if the file does not exist or the destination
+/// already does, or if the file could not be moved.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xdir::Dir;
+///
+/// let path = xdir::promote(Dir::Cache, Dir::Data, "myapp/report.pdf").unwrap();
+/// ```
+pub fn promote(src: Dir, dst: Dir, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    if src == dst {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cannot promote within the {src} directory"),
+        ));
+    }
+    let from = app::join(&base(src)?, path)?;
+    let to = app::join(&base(dst)?, path)?;
+    from.symlink_metadata()?;
+    // Renaming would otherwise silently replace an existing file.
+    if to.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    transfer::move_path(&from, &to)?;
+    Ok(to)
+}
+
+fn base(dir: Dir) -> io::Result<PathBuf> {
+    dir.path().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find {dir} directory"),
+        )
+    })
+}
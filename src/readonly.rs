@@ -0,0 +1,103 @@
+//! Read-only mode.
+
+#[cfg(test)]
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether read-only mode is enabled.
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+thread_local! {
+    /// Whether read-only mode is enabled for the current thread, such that
+    /// tests can enable it without affecting those running concurrently.
+    static THREAD_READ_ONLY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables (or disables) read-only mode.
+///
+/// While enabled, every helper which would modify the filesystem (creating
+/// directories, writing files, moving, pruning or purging) fails instead, with
+/// an error of kind [`ReadOnlyFilesystem`](std::io::ErrorKind::ReadOnlyFilesystem).
+/// Paths are still resolved as usual. This suits `--dry-run` flags, and
+/// systems on which the home directory is mounted read-only. Read-only mode
+/// can also be enabled for a single application through `App::read_only`.
+/// This is disabled by default.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "core")]
+/// # {
+/// use xdir::App;
+///
+/// xdir::set_read_only(true);
+/// # if xdir::config().is_some() {
+/// let err = App::new("myapp").ensure(xdir::DirSet::CONFIG).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::ReadOnlyFilesystem);
+/// # }
+/// # xdir::set_read_only(false);
+/// # }
+/// ```
+pub fn set_read_only(enabled: bool) {
+    READ_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+/// Checks whether read-only mode is enabled.
+///
+/// See [`set_read_only`] for details.
+#[must_use]
+pub fn is_read_only() -> bool {
+    #[cfg(test)]
+    if THREAD_READ_ONLY.get() {
+        return true;
+    }
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// Enables (or disables) read-only mode for the current thread only.
+#[cfg(all(test, feature = "xdg"))]
+pub(crate) fn set_thread_read_only(enabled: bool) {
+    THREAD_READ_ONLY.set(enabled);
+}
+
+/// Fails if read-only mode is enabled.
+#[cfg(any(
+    test,
+    feature = "core",
+    feature = "desktop",
+    feature = "install",
+    feature = "mime",
+    feature = "owner",
+    feature = "shell",
+    feature = "systemd",
+    feature = "trash",
+    feature = "xdg"
+))]
+pub(crate) fn check() -> std::io::Result<()> {
+    if is_read_only() {
+        Err(error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the error of modifications in read-only mode.
+#[cfg(any(
+    test,
+    feature = "core",
+    feature = "desktop",
+    feature = "install",
+    feature = "mime",
+    feature = "owner",
+    feature = "shell",
+    feature = "systemd",
+    feature = "trash",
+    feature = "xdg"
+))]
+pub(crate) fn error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::ReadOnlyFilesystem,
+        "read-only mode is enabled",
+    )
+}
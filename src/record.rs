@@ -0,0 +1,212 @@
+//! Recording of resolutions.
+//!
+//! When diagnosing why an application used an unexpected location, it helps to
+//! know every directory it resolved, and why. Once [started](start), each
+//! resolution of a directory made by the process is recorded as an [`Event`],
+//! such that `--debug` output or support bundles can list them. This includes
+//! the directories of applications (see `App`), along with how each was
+//! located (e.g. by the application's own variable).
+//!
+//! Recording is disabled by default, and at most the latest 1024 events are
+//! kept.
+//!
+//! # Examples
+//!
+//! ```
+//! xdir::record::start();
+//! let _ = xdir::config();
+//! for event in xdir::record::events() {
+//!     eprintln!("{event}");
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use crate::time::DateTime;
+use crate::{Dir, Resolved, Source};
+
+/// Maximum number of events kept.
+const CAPACITY: usize = 1024;
+
+/// Whether resolutions are recorded.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Recorded events, oldest first.
+static EVENTS: Mutex<VecDeque<Event>> = Mutex::new(VecDeque::new());
+
+/// A recorded resolution of a directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    app: Option<String>,
+    dir: Dir,
+    resolved: Option<Resolved>,
+    time: SystemTime,
+}
+
+impl Event {
+    /// Returns the name of the application whose directory was resolved.
+    ///
+    /// This is [`None`] for the user's own directories.
+    #[must_use]
+    pub fn app(&self) -> Option<&str> {
+        self.app.as_deref()
+    }
+
+    /// Returns the kind of directory which was resolved.
+    #[must_use]
+    pub fn dir(&self) -> Dir {
+        self.dir
+    }
+
+    /// Returns the resolved path, if the directory was found.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.resolved.as_ref().map(Resolved::path)
+    }
+
+    /// Returns the source of the resolved path, if the directory was found.
+    #[must_use]
+    pub fn source(&self) -> Option<&Source> {
+        self.resolved.as_ref().map(Resolved::source)
+    }
+
+    /// Returns the time of the resolution.
+    #[must_use]
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = DateTime::utc(self.time).extended();
+        write!(f, "{time}Z {}", self.dir)?;
+        if let Some(app) = &self.app {
+            write!(f, " of {app}")?;
+        }
+        match &self.resolved {
+            Some(resolved) => write!(f, ": {resolved}"),
+            None => write!(f, ": not found"),
+        }
+    }
+}
+
+/// Starts recording resolutions.
+pub fn start() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stops recording resolutions.
+///
+/// Events recorded so far are kept.
+pub fn stop() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+/// Checks whether resolutions are being recorded.
+#[must_use]
+pub fn is_recording() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns the recorded events, oldest first.
+#[must_use]
+pub fn events() -> Vec<Event> {
+    events_lock().iter().cloned().collect()
+}
+
+/// Removes and returns the recorded events, oldest first.
+#[must_use]
+pub fn take() -> Vec<Event> {
+    events_lock().drain(..).collect()
+}
+
+/// Records a resolution of one of the user's directories, if recording.
+pub(crate) fn resolved(dir: Dir, resolved: Option<&Resolved>) {
+    record(None, dir, resolved);
+}
+
+/// Records a resolution of one of an application's directories, if recording.
+#[cfg(feature = "core")]
+pub(crate) fn app(app: &str, dir: Dir, resolved: Option<&Resolved>) {
+    record(Some(app), dir, resolved);
+}
+
+/// Records a resolution, if recording.
+fn record(app: Option<&str>, dir: Dir, resolved: Option<&Resolved>) {
+    if !is_recording() {
+        return;
+    }
+    let mut events = events_lock();
+    if events.len() == CAPACITY {
+        events.pop_front();
+    }
+    events.push_back(Event {
+        app: app.map(str::to_string),
+        dir,
+        resolved: resolved.cloned(),
+        time: SystemTime::now(),
+    });
+}
+
+/// Locks the recorded events.
+fn events_lock() -> MutexGuard<'static, VecDeque<Event>> {
+    EVENTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes tests, as recording is process-wide.
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn records_every_resolution() {
+        let _lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        start();
+        let single = Dir::Config.resolve();
+        let dirs = crate::resolve_all();
+        stop();
+        let events = take();
+        let count = |dir: Dir| events.iter().filter(|event| event.dir() == dir).count();
+        // Each directory is recorded by batch resolution, and the config
+        // directory once more by its own.
+        assert!(count(Dir::Config) >= 2);
+        for dir in Dir::ALL {
+            assert!(count(dir) >= 1, "{dir}");
+            assert!(events
+                .iter()
+                .any(|event| event.dir() == dir && event.path() == dirs.get(dir)));
+        }
+        assert!(events
+            .iter()
+            .any(|event| event.source() == single.as_ref().map(Resolved::source)));
+    }
+
+    #[cfg(feature = "core")]
+    #[test]
+    fn records_app_resolutions() {
+        use crate::{App, Scope};
+
+        let _lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let app = App::new("record-test").scope(Scope::Machine);
+        start();
+        let resolved = app.dir_resolved(Dir::Config);
+        stop();
+        let events = take();
+        let event = events
+            .iter()
+            .find(|event| event.app() == Some("record-test"))
+            .expect("app resolution should be recorded");
+        assert_eq!(event.dir(), Dir::Config);
+        assert_eq!(event.source(), Some(&Source::Machine));
+        assert_eq!(event.path(), resolved.as_ref().map(Resolved::path));
+        assert!(event.to_string().contains("config of record-test: "));
+    }
+}
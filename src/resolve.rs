@@ -0,0 +1,790 @@
+//! Resolution of directories.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+#[cfg(feature = "volume")]
+use crate::volume;
+use crate::{fallback, home, Dir};
+
+/// Whether the legacy executable directory is used.
+static LEGACY_BIN: AtomicBool = AtomicBool::new(false);
+
+/// Treatment of a symlinked home directory.
+static SYMLINKED_HOME: AtomicU8 = AtomicU8::new(SymlinkedHome::AsIs as u8);
+
+/// Variable encoding the treatment of empty variables within exported
+/// environments.
+const EMPTY_VARS_VAR: &str = "XDIR_EMPTY_VARS";
+
+/// Treatment of empty variables within the process environment.
+static EMPTY_VARS: AtomicU8 = AtomicU8::new(EmptyVars::Unset as u8);
+
+/// A resolved directory, along with the reason it was chosen.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Resolved {
+    path: PathBuf,
+    source: Source,
+}
+
+impl Resolved {
+    /// Constructs a resolved directory.
+    #[cfg(any(feature = "core", all(test, feature = "xdg")))]
+    pub(crate) fn new(path: PathBuf, source: Source) -> Self {
+        Self { path, source }
+    }
+
+    /// Returns the path to the directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the source of the directory's path.
+    #[must_use]
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Returns the path to the directory, losslessly as an OS string.
+    #[must_use]
+    pub fn as_os_str(&self) -> &OsStr {
+        self.path.as_os_str()
+    }
+
+    /// Converts into the path to the directory.
+    #[must_use]
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// Converts into the path to the directory, losslessly as an OS string.
+    #[must_use]
+    pub fn into_os_string(self) -> OsString {
+        self.path.into_os_string()
+    }
+}
+
+impl Display for Resolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (from {})",
+            crate::escape_path(&self.path),
+            self.source
+        )
+    }
+}
+
+/// Source of a resolved directory's path.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Source {
+    /// Set by an environment variable.
+    EnvVar(&'static str),
+    /// Set by the user's override file (see `overrides`).
+    Override,
+    /// Default location within the user's home directory.
+    Default,
+    /// Default location, used as the environment variable was set to an
+    /// invalid (i.e. relative) path.
+    Fallback(&'static str),
+    /// Default location, used as the environment variable was set but empty.
+    /// See [`EmptyVars::Unset`].
+    Empty(&'static str),
+    /// Legacy location within the user's home directory (i.e. `~/bin`), used
+    /// as the default does not exist. See [`set_legacy_bin`].
+    Legacy,
+    /// Local scratch location, used as the default is on a network filesystem.
+    /// See `set_network_redirect`.
+    Redirected,
+    /// Set by one of an application's variables. See `App::dir_var`.
+    AppVar(String),
+    /// Within the root of an application in portable mode (see
+    /// `App::portable`).
+    Portable,
+    /// Machine-wide location of an application. See `App::scope`.
+    Machine,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EnvVar(var) => write!(f, "${var}"),
+            Self::Override => write!(f, "override file"),
+            Self::Default => write!(f, "default"),
+            Self::Fallback(var) => write!(f, "default, as ${var} is invalid"),
+            Self::Empty(var) => write!(f, "default, as ${var} is empty"),
+            Self::Legacy => write!(f, "legacy location"),
+            Self::Redirected => write!(
+                f,
+                "local scratch, as the default is on a network filesystem"
+            ),
+            Self::AppVar(var) => write!(f, "${var}"),
+            Self::Portable => write!(f, "portable root"),
+            Self::Machine => write!(f, "machine scope"),
+        }
+    }
+}
+
+/// Resolves a directory from the environment, optionally consulting the
+/// override file, before falling back to its default (which is reported to the
+/// [fallback hook](crate::fallback) if the variable was rejected).
+///
+/// As per the XDG Base Directory Specification, empty variables are treated as
+/// unset (unless [configured](set_empty_vars) otherwise), and relative paths
+/// are ignored.
+pub(crate) fn resolve(dir: Dir, overrides: bool) -> Option<Resolved> {
+    if overrides {
+        let overridden = |dir| overridden(read().as_ref(), dir, &home);
+        return process(dir, home, &overridden);
+    }
+    lookup(dir, |var| env::var_os(var), home, empty_vars(), None)
+}
+
+/// Resolves a directory of the process through the given lookups of the home
+/// directory and the override file, recording it (see `record`).
+///
+/// Every resolution of the process's directories goes through here.
+pub(crate) fn process(
+    dir: Dir,
+    home: impl Fn() -> Option<PathBuf>,
+    overridden: &dyn Fn(Dir) -> Option<PathBuf>,
+) -> Option<Resolved> {
+    let resolved = lookup(
+        dir,
+        |var| env::var_os(var),
+        home,
+        empty_vars(),
+        Some(overridden),
+    );
+    #[cfg(feature = "record")]
+    crate::record::resolved(dir, resolved.as_ref());
+    resolved
+}
+
+/// Resolves a directory through the given lookups of variables, the home
+/// directory and the override file, treating empty variables as given.
+///
+/// Only resolution from the process environment consults the override file;
+/// as such, the legacy executable directory, symlinked home mode, network
+/// redirection and fallback hook only apply then.
+pub(crate) fn lookup(
+    dir: Dir,
+    vars: impl Fn(&str) -> Option<OsString>,
+    home: impl Fn() -> Option<PathBuf>,
+    empty: EmptyVars,
+    overrides: Option<&dyn Fn(Dir) -> Option<PathBuf>>,
+) -> Option<Resolved> {
+    let var = dir.var();
+    let mut source = Source::Default;
+    let mut rejected = None;
+    let value = vars(var);
+    if value.as_ref().is_some_and(|value| value.is_empty()) {
+        match empty {
+            EmptyVars::Unset => {
+                source = Source::Empty(var);
+                rejected = Some(OsString::new());
+            }
+            EmptyVars::Error => return None,
+            EmptyVars::Literal => {
+                return Some(Resolved {
+                    path: PathBuf::new(),
+                    source: Source::EnvVar(var),
+                })
+            }
+        }
+    }
+    if let Some(value) = value.filter(|value| !value.is_empty()) {
+        let path = native(PathBuf::from(&value));
+        if is_absolute(&path) {
+            return Some(Resolved {
+                path: if overrides.is_some() {
+                    relink(path)
+                } else {
+                    path
+                },
+                source: Source::EnvVar(var),
+            });
+        }
+        source = Source::Fallback(var);
+        rejected = Some(value);
+    }
+    if let Some(path) = overrides.and_then(|overridden| overridden(dir)) {
+        return Some(Resolved {
+            path: relink(path),
+            source: Source::Override,
+        });
+    }
+    let home = home()?;
+    let mut path = join(home.clone(), dir.default()?);
+    if overrides.is_some()
+        && dir == Dir::Bin
+        && LEGACY_BIN.load(Ordering::Relaxed)
+        && !path.is_dir()
+    {
+        let legacy = home.join("bin");
+        if legacy.is_dir() {
+            path = legacy;
+            source = Source::Legacy;
+        }
+    }
+    #[cfg(feature = "volume")]
+    if overrides.is_some() {
+        if let Some(scratch) = volume::redirect(dir, &path) {
+            path = scratch;
+            source = Source::Redirected;
+        }
+    }
+    if let Some(value) = rejected.filter(|_| overrides.is_some()) {
+        fallback::report(var, value, &[&path]);
+    }
+    Some(Resolved { path, source })
+}
+
+/// Enables (or disables) the legacy executable directory.
+///
+/// Many users place executables in `~/bin` rather than `~/.local/bin`, as
+/// `$XDG_BIN_HOME` is not part of the XDG Base Directory Specification. Once
+/// enabled, the user's [executable directory](crate::bin) falls back to
+/// `~/bin` if it exists, and `~/.local/bin` does not. Its [source](Source) is
+/// then reported as [`Source::Legacy`]. This is disabled by default.
+///
+/// # Examples
+///
+/// ```
+/// xdir::set_legacy_bin(true);
+/// if let Some(bin) = xdir::bin_resolved() {
+///     println!("installing into {bin}");
+/// }
+/// ```
+pub fn set_legacy_bin(enabled: bool) {
+    LEGACY_BIN.store(enabled, Ordering::Relaxed);
+}
+
+/// Treatment of a home directory which is a symbolic link.
+///
+/// Where the user's home directory is a symbolic link (e.g. `/home/user`
+/// linking to `/tank/home/user`), its directories may be reached through
+/// either path. Mixing both (e.g. when `$XDG_CONFIG_HOME` is set to the
+/// physical path, while other directories default within `$HOME`) leads to
+/// mismatches between paths which should be equal, such as those registered
+/// with file watchers, and those which were canonicalized.
+///
+/// Once set by [`set_symlinked_home`], every directory within the home
+/// directory (including the [home directory](crate::home) itself) is
+/// consistently resolved through one of the paths. This only applies on Unix.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SymlinkedHome {
+    /// Use paths as they are given.
+    #[default]
+    AsIs,
+    /// Use the logical path, through `$HOME` (as with `$PWD`).
+    Logical,
+    /// Use the physical path, with the symbolic link resolved.
+    Physical,
+}
+
+impl SymlinkedHome {
+    /// Converts from the representation stored within an atomic.
+    fn from_u8(value: u8) -> Self {
+        [Self::AsIs, Self::Logical, Self::Physical]
+            .into_iter()
+            .find(|mode| *mode as u8 == value)
+            .unwrap_or_default()
+    }
+}
+
+/// Sets the treatment of a home directory which is a symbolic link.
+///
+/// By default, paths are used as they are given.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::SymlinkedHome;
+///
+/// xdir::set_symlinked_home(SymlinkedHome::Physical);
+/// if let (Some(home), Some(config)) = (xdir::home(), xdir::config()) {
+///     println!("{} is within {}", config.display(), home.display());
+/// }
+/// # xdir::set_symlinked_home(SymlinkedHome::AsIs);
+/// ```
+pub fn set_symlinked_home(mode: SymlinkedHome) {
+    SYMLINKED_HOME.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Returns the user's home directory, following the symlinked home mode.
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    let home = platform_home()?;
+    if SymlinkedHome::from_u8(SYMLINKED_HOME.load(Ordering::Relaxed)) == SymlinkedHome::Physical {
+        return Some(physical(&home).unwrap_or(home));
+    }
+    Some(home)
+}
+
+/// Returns the user's home directory, as reported by the platform.
+#[cfg(not(target_arch = "wasm32"))]
+fn platform_home() -> Option<PathBuf> {
+    ::home::home_dir()
+}
+
+/// Returns the user's home directory, as reported by the platform.
+///
+/// WebAssembly has no home directory of its own; it can only be given through
+/// an [`Env`].
+#[cfg(target_arch = "wasm32")]
+fn platform_home() -> Option<PathBuf> {
+    None
+}
+
+/// Rewrites a path within the user's home directory through its logical or
+/// physical path, following the symlinked home mode.
+fn relink(path: PathBuf) -> PathBuf {
+    let from_to = match SymlinkedHome::from_u8(SYMLINKED_HOME.load(Ordering::Relaxed)) {
+        SymlinkedHome::AsIs => return path,
+        mode => platform_home().and_then(|logical| {
+            let physical = physical(&logical)?;
+            Some(match mode {
+                SymlinkedHome::Logical => (physical, logical),
+                _ => (logical, physical),
+            })
+        }),
+    };
+    let Some((from, to)) = from_to else {
+        return path;
+    };
+    match path.strip_prefix(&from) {
+        Ok(rest) if rest.as_os_str().is_empty() => to,
+        Ok(rest) => to.join(rest),
+        Err(_) => path,
+    }
+}
+
+/// Returns the physical path of a home directory, if it is a symbolic link.
+#[cfg(unix)]
+fn physical(home: &Path) -> Option<PathBuf> {
+    let physical = std::fs::canonicalize(home).ok()?;
+    (physical != home).then_some(physical)
+}
+
+#[cfg(not(unix))]
+fn physical(_: &Path) -> Option<PathBuf> {
+    None
+}
+
+/// Treatment of environment variables which are set, but empty.
+///
+/// The XDG Base Directory Specification treats an empty variable as unset,
+/// such that the directory's default is used. Other ecosystems disagree: some
+/// reject empty variables as a misconfiguration, while others take them at face
+/// value. The treatment applies to the variables of the user's directories
+/// (e.g. `$XDG_CONFIG_HOME`), and is reflected in each directory's
+/// [source](Source).
+///
+/// The treatment of the process environment is set by [`set_empty_vars`], and
+/// that of an explicit environment by [`Env::empty_vars`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum EmptyVars {
+    /// Treat empty variables as unset, using the directory's default (as per
+    /// the XDG Base Directory Specification). Its source is reported as
+    /// [`Source::Empty`].
+    #[default]
+    Unset,
+    /// Treat empty variables as an error, such that the directory cannot be
+    /// found.
+    Error,
+    /// Treat empty variables literally, such that the directory is the empty
+    /// (i.e. current) path. Its source is reported as [`Source::EnvVar`].
+    Literal,
+}
+
+impl EmptyVars {
+    /// Every treatment.
+    const ALL: [Self; 3] = [Self::Unset, Self::Error, Self::Literal];
+
+    /// Converts from the representation stored within an atomic.
+    fn from_u8(value: u8) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|empty| *empty as u8 == value)
+            .unwrap_or_default()
+    }
+
+    /// Returns the name of the treatment, as exported by [`Env::to_vars`].
+    fn name(self) -> &'static str {
+        match self {
+            Self::Unset => "unset",
+            Self::Error => "error",
+            Self::Literal => "literal",
+        }
+    }
+}
+
+/// Sets the treatment of empty variables within the process environment.
+///
+/// By default, empty variables are treated as unset.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::EmptyVars;
+///
+/// xdir::set_empty_vars(EmptyVars::Error);
+/// # xdir::set_empty_vars(EmptyVars::Unset);
+/// ```
+pub fn set_empty_vars(empty: EmptyVars) {
+    EMPTY_VARS.store(empty as u8, Ordering::Relaxed);
+}
+
+/// Returns the treatment of empty variables within the process environment.
+#[must_use]
+pub fn empty_vars() -> EmptyVars {
+    EmptyVars::from_u8(EMPTY_VARS.load(Ordering::Relaxed))
+}
+
+/// An explicit environment from which to resolve directories.
+///
+/// This allows directories to be resolved exactly as they would be by a
+/// process with the given environment, such as when computing paths on behalf
+/// of another process, or where there is no process environment at all. Unlike
+/// the process environment, the override file is not consulted.
+///
+/// The home directory is taken from `$HOME`, or from `$USERPROFILE` if unset.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::{Dir, Env};
+///
+/// let env = Env::new()
+///     .var("HOME", "/home/user")
+///     .var("XDG_CONFIG_HOME", "/mnt/config");
+/// # #[cfg(unix)]
+/// # {
+/// assert_eq!(env.path(Dir::Config).unwrap(), std::path::Path::new("/mnt/config"));
+/// assert_eq!(env.path(Dir::Cache).unwrap(), std::path::Path::new("/home/user/.cache"));
+/// assert_eq!(env.path(Dir::Runtime), None);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Env {
+    vars: BTreeMap<String, OsString>,
+    empty: EmptyVars,
+}
+
+impl Env {
+    /// Constructs an empty environment.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the resolution of the current process as an environment.
+    ///
+    /// Each directory is set to the location it resolves to for the current
+    /// process, including any relocation by the override file, along with the
+    /// home directory and system search paths, and the process's treatment of
+    /// [empty variables](set_empty_vars). As such, resolving from the captured
+    /// environment agrees with the current process, even in another process or
+    /// on another machine.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::process::Command;
+    ///
+    /// let env = xdir::Env::current();
+    /// Command::new("myapp-worker").envs(env.to_vars()).status().unwrap();
+    /// ```
+    #[must_use]
+    pub fn current() -> Self {
+        let mut env = Self::new().empty_vars(empty_vars());
+        if let Some(home) = home() {
+            env = env.var("HOME", home);
+        }
+        for dir in Dir::ALL {
+            if let Some(path) = dir.path() {
+                env = env.var(dir.var(), path);
+            }
+        }
+        for (var, dirs) in [
+            ("XDG_CONFIG_DIRS", crate::config_dirs()),
+            ("XDG_DATA_DIRS", crate::data_dirs()),
+        ] {
+            if let Ok(dirs) = env::join_paths(dirs) {
+                env = env.var(var, dirs);
+            }
+        }
+        env
+    }
+
+    /// Constructs an environment from its variables, such as those exported by
+    /// [`to_vars`](Self::to_vars).
+    ///
+    /// The treatment of [empty variables](Self::empty_vars) is read from
+    /// `$XDIR_EMPTY_VARS` (i.e. `unset`, `error` or `literal`), defaulting to
+    /// treating them as unset. Variables with names which are not valid UTF-8
+    /// are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::Env;
+    ///
+    /// let env = Env::new().var("HOME", "/home/user");
+    /// assert_eq!(Env::from_vars(env.to_vars()), env);
+    ///
+    /// let process = Env::from_vars(std::env::vars_os());
+    /// ```
+    #[must_use]
+    pub fn from_vars<K, V>(vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<OsStr>,
+        V: Into<OsString>,
+    {
+        let mut vars = vars
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.as_ref().to_str()?.to_string(), value.into())))
+            .collect::<BTreeMap<_, OsString>>();
+        let empty = vars
+            .remove(EMPTY_VARS_VAR)
+            .and_then(|value| {
+                EmptyVars::ALL
+                    .into_iter()
+                    .find(|empty| value == empty.name())
+            })
+            .unwrap_or_default();
+        Self { vars, empty }
+    }
+
+    /// Returns the variables of the environment, ordered by name.
+    ///
+    /// These can be passed to a child process (e.g. through
+    /// [`Command::envs`](std::process::Command::envs)), or serialized and sent
+    /// elsewhere, to be reconstructed by [`from_vars`](Self::from_vars). Unless
+    /// empty variables are treated as unset, their treatment is included as
+    /// `$XDIR_EMPTY_VARS`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::{EmptyVars, Env};
+    ///
+    /// let env = Env::new()
+    ///     .var("XDG_CONFIG_HOME", "")
+    ///     .empty_vars(EmptyVars::Literal);
+    /// let vars = env.to_vars();
+    /// assert!(vars.contains(&("XDIR_EMPTY_VARS".into(), "literal".into())));
+    /// assert_eq!(Env::from_vars(vars), env);
+    /// ```
+    #[must_use]
+    pub fn to_vars(&self) -> Vec<(String, OsString)> {
+        let empty = (self.empty != EmptyVars::Unset).then(|| {
+            (
+                EMPTY_VARS_VAR.to_string(),
+                OsString::from(self.empty.name()),
+            )
+        });
+        let mut vars = self
+            .vars
+            .iter()
+            .filter(|(key, _)| *key != EMPTY_VARS_VAR)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .chain(empty)
+            .collect::<Vec<_>>();
+        vars.sort();
+        vars
+    }
+
+    /// Sets a variable of the environment.
+    #[must_use]
+    pub fn var(mut self, key: impl Into<String>, value: impl Into<OsString>) -> Self {
+        self.vars.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the treatment of empty variables within the environment.
+    ///
+    /// By default, empty variables are treated as unset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xdir::{Dir, EmptyVars, Env, Source};
+    ///
+    /// let env = Env::new()
+    ///     .var("HOME", "/home/user")
+    ///     .var("XDG_CONFIG_HOME", "");
+    /// # #[cfg(unix)]
+    /// # {
+    /// let config = env.resolve(Dir::Config).unwrap();
+    /// assert_eq!(config.source(), &Source::Empty("XDG_CONFIG_HOME"));
+    /// # }
+    /// assert_eq!(env.empty_vars(EmptyVars::Error).resolve(Dir::Config), None);
+    /// ```
+    #[must_use]
+    pub fn empty_vars(mut self, empty: EmptyVars) -> Self {
+        self.empty = empty;
+        self
+    }
+
+    /// Returns the value of a variable, if set.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&OsStr> {
+        self.vars.get(key).map(OsString::as_os_str)
+    }
+
+    /// Returns the path to a directory within the environment.
+    #[must_use]
+    pub fn path(&self, dir: Dir) -> Option<PathBuf> {
+        self.resolve(dir).map(Resolved::into_path)
+    }
+
+    /// Returns the path to a directory within the environment, along with its
+    /// source.
+    #[must_use]
+    pub fn resolve(&self, dir: Dir) -> Option<Resolved> {
+        lookup(
+            dir,
+            |var| self.get(var).map(OsStr::to_os_string),
+            || self.home(),
+            self.empty,
+            None,
+        )
+    }
+
+    /// Returns the home directory of the environment.
+    #[must_use]
+    pub fn home(&self) -> Option<PathBuf> {
+        ["HOME", "USERPROFILE"]
+            .into_iter()
+            .filter_map(|var| self.get(var))
+            .find(|value| !value.is_empty())
+            .map(|value| native(PathBuf::from(value)))
+            .filter(|path| is_absolute(path))
+    }
+}
+
+/// Joins a `/`-separated suffix onto a path, using native separators.
+///
+/// This keeps paths valid in extended-length form on Windows.
+fn join(path: PathBuf, suffix: &str) -> PathBuf {
+    suffix.split('/').fold(path, |path, part| path.join(part))
+}
+
+/// Translates paths set through the environment into their native form.
+///
+/// On Windows, paths given in POSIX form are translated (with the `cygwin`
+/// feature), and separators are normalized such that forward slashes, trailing
+/// separators and redundant `.` segments do not affect comparisons between
+/// paths.
+#[cfg(windows)]
+pub(crate) fn native(path: PathBuf) -> PathBuf {
+    use std::path::Component;
+
+    #[cfg(feature = "cygwin")]
+    let path = crate::cygwin::native(path);
+    match path.components().next() {
+        // Extended-length paths are taken literally.
+        Some(Component::Prefix(prefix)) if prefix.kind().is_verbatim() => path,
+        _ => path.components().collect(),
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn native(path: PathBuf) -> PathBuf {
+    path
+}
+
+/// Checks whether a path is absolute.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn is_absolute(path: &Path) -> bool {
+    path.is_absolute()
+}
+
+/// Checks whether a path is absolute.
+///
+/// Paths within WebAssembly follow the conventions of Unix, under which they
+/// are absolute if they begin with a root.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn is_absolute(path: &Path) -> bool {
+    path.has_root()
+}
+
+/// Contents of the override file.
+#[cfg(feature = "overrides")]
+pub(crate) type Overrides = std::sync::Arc<crate::overrides::Overrides>;
+
+/// Contents of the override file, which is disabled.
+#[cfg(not(feature = "overrides"))]
+pub(crate) type Overrides = std::convert::Infallible;
+
+/// Reads the override file (unless cached), if enabled.
+#[cfg(feature = "overrides")]
+pub(crate) fn read() -> Option<Overrides> {
+    crate::overrides::load()
+}
+
+#[cfg(not(feature = "overrides"))]
+pub(crate) fn read() -> Option<Overrides> {
+    None
+}
+
+/// Finds the overridden location of a directory within the override file.
+#[cfg(feature = "overrides")]
+pub(crate) fn overridden(
+    overrides: Option<&Overrides>,
+    dir: Dir,
+    home: &dyn Fn() -> Option<PathBuf>,
+) -> Option<PathBuf> {
+    overrides?.find(None, dir, home)
+}
+
+#[cfg(not(feature = "overrides"))]
+pub(crate) fn overridden(
+    _: Option<&Overrides>,
+    _: Dir,
+    _: &dyn Fn() -> Option<PathBuf>,
+) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vars_carry_empty_vars() {
+        let env = Env::new()
+            .var("HOME", "/home/user")
+            .var("XDG_CONFIG_HOME", "");
+        for empty in EmptyVars::ALL {
+            let env = env.clone().empty_vars(empty);
+            let copy = Env::from_vars(env.to_vars());
+            assert_eq!(copy, env);
+            assert_eq!(copy.resolve(Dir::Config), env.resolve(Dir::Config));
+        }
+
+        let literal = Env::from_vars(env.clone().empty_vars(EmptyVars::Literal).to_vars());
+        let config = literal.resolve(Dir::Config).unwrap();
+        assert_eq!(config.source(), &Source::EnvVar("XDG_CONFIG_HOME"));
+        assert_eq!(config.path(), Path::new(""));
+        let error = Env::from_vars(env.clone().empty_vars(EmptyVars::Error).to_vars());
+        assert_eq!(error.resolve(Dir::Config), None);
+    }
+
+    #[test]
+    fn vars_omit_default_empty_vars() {
+        let env = Env::new().var("HOME", "/home/user");
+        assert_eq!(env.to_vars(), [("HOME".into(), "/home/user".into())]);
+        let unknown = Env::from_vars([("XDIR_EMPTY_VARS", "bogus")]);
+        assert_eq!(unknown, Env::new());
+    }
+}
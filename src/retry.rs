@@ -0,0 +1,156 @@
+//! Retrying of transient errors.
+
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// Policy of retrying operations which fail with [transient](is_transient)
+/// errors.
+///
+/// Home directories mounted over the network (e.g. through autofs or NFS) may
+/// briefly fail while a mount is being established or recovered. Rather than
+/// failing at startup, operations can be retried a bounded number of times,
+/// waiting between attempts with exponential backoff. Permanent errors (e.g.
+/// missing permissions) fail immediately.
+///
+/// By default, operations are attempted once.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use xdir::{App, Dir, Retry};
+///
+/// let retry = Retry::new(5).delay(Duration::from_millis(100));
+/// let app = App::new("myapp").retry(retry);
+/// app.create(Dir::Config).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Retry {
+    attempts: u32,
+    delay: Duration,
+    max_delay: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Retry {
+    /// Constructs a policy making up to the given number of attempts.
+    ///
+    /// Attempts are initially 50 ms apart, doubling after each one, up to 2 s.
+    #[must_use]
+    pub const fn new(attempts: u32) -> Self {
+        Self {
+            attempts,
+            delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+
+    /// Sets the delay before the first retry.
+    #[must_use]
+    pub const fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets the maximum delay between retries.
+    #[must_use]
+    pub const fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Returns the maximum number of attempts.
+    #[must_use]
+    pub const fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Runs an operation, retrying it while it fails with transient errors.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the operation's error, if it is permanent or once all
+    /// attempts are exhausted.
+    pub fn run<T>(&self, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut delay = self.delay;
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Err(err) if attempt < self.attempts && is_transient(&err) => {
+                    thread::sleep(delay);
+                    delay = delay.saturating_mul(2).min(self.max_delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Checks whether an error is transient, such that retrying may succeed.
+///
+/// Transient errors include stale network file handles (`ESTALE`), timeouts,
+/// interrupted calls, unreachable or unavailable network hosts, shares and
+/// mounts, and I/O errors (as reported by soft NFS mounts which timed out).
+#[must_use]
+pub fn is_transient(err: &io::Error) -> bool {
+    use io::ErrorKind;
+
+    if matches!(
+        err.kind(),
+        ErrorKind::StaleNetworkFileHandle
+            | ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::ResourceBusy
+            | ErrorKind::NetworkDown
+            | ErrorKind::NetworkUnreachable
+            | ErrorKind::HostUnreachable
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+    ) {
+        return true;
+    }
+    err.raw_os_error().is_some_and(is_transient_code)
+}
+
+#[cfg(unix)]
+fn is_transient_code(code: i32) -> bool {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if code == libc::ENOLINK || code == libc::ENOMEDIUM {
+        return true;
+    }
+    code == libc::EIO || code == libc::ENOLCK
+}
+
+#[cfg(windows)]
+fn is_transient_code(code: i32) -> bool {
+    use windows_sys::Win32::Foundation::{
+        ERROR_BAD_NETPATH, ERROR_NETNAME_DELETED, ERROR_NETWORK_BUSY, ERROR_NETWORK_UNREACHABLE,
+        ERROR_SEM_TIMEOUT, ERROR_UNEXP_NET_ERR,
+    };
+
+    u32::try_from(code).is_ok_and(|code| {
+        [
+            ERROR_BAD_NETPATH,
+            ERROR_NETNAME_DELETED,
+            ERROR_NETWORK_BUSY,
+            ERROR_NETWORK_UNREACHABLE,
+            ERROR_SEM_TIMEOUT,
+            ERROR_UNEXP_NET_ERR,
+        ]
+        .contains(&code)
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_transient_code(_: i32) -> bool {
+    false
+}
@@ -0,0 +1,315 @@
+//! Recovery of runtime directories.
+//!
+//! The contents of the runtime directory are bound to the user's login session:
+//! `systemd-logind` removes them once the user logs out, and recreates the
+//! directory (empty) when they log back in. Long-running processes, such as
+//! daemons which outlive the session, may thus find that their sockets and
+//! lock files have silently disappeared.
+//!
+//! A [`Tracker`] remembers the identity of an application's runtime directory,
+//! such that a stale directory can be detected and recreated.
+//!
+//! Before binding sockets, daemons should also [claim](App::runtime_dir) their
+//! runtime directory, as its name may already be taken within a runtime
+//! directory shared between users (e.g. where it falls back to `/tmp`).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::App;
+//!
+//! let mut runtime = App::new("myapp").track_runtime().unwrap();
+//! // ...
+//! if runtime.ensure().unwrap() {
+//!     // The directory was recreated; rebind any sockets within it.
+//! }
+//! ```
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{perms, App, Dir};
+
+/// Maximum number of uniquified names tried, after the application's own.
+const ATTEMPTS: u32 = 16;
+
+/// A tracked runtime directory of an application.
+#[derive(Clone, Debug)]
+pub struct Tracker {
+    app: App,
+    path: PathBuf,
+    id: Option<Id>,
+}
+
+impl Tracker {
+    /// Creates an application's runtime directory, tracking its identity.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory could not be found or created.
+    pub fn new(app: &App) -> io::Result<Self> {
+        let path = app.create(Dir::Runtime)?;
+        let id = identify(&path)?;
+        Ok(Self {
+            app: app.clone(),
+            path,
+            id,
+        })
+    }
+
+    /// Returns the path to the runtime directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks whether the runtime directory is stale.
+    ///
+    /// This is the case if it no longer exists, has been moved (e.g. as
+    /// `$XDG_RUNTIME_DIR` changed), or was recreated since it was tracked.
+    /// Recreation is only detected on Unix.
+    #[must_use]
+    pub fn is_stale(&self) -> bool {
+        self.app.runtime().as_ref() != Some(&self.path)
+            || identify(&self.path).ok() != Some(self.id)
+    }
+
+    /// Recreates the runtime directory if it is stale, returning whether it was
+    /// recreated.
+    ///
+    /// Upon recreation, the directory is empty, so anything placed within it
+    /// (e.g. sockets) must be recreated by the caller.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory could not be found or created.
+    pub fn ensure(&mut self) -> io::Result<bool> {
+        if !self.is_stale() {
+            return Ok(false);
+        }
+        *self = Self::new(&self.app)?;
+        Ok(true)
+    }
+}
+
+/// A claimed runtime directory of an application.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RuntimeDir {
+    path: PathBuf,
+    claim: Claim,
+}
+
+impl RuntimeDir {
+    /// Returns the path to the runtime directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns how the runtime directory was claimed.
+    #[must_use]
+    pub fn claim(&self) -> &Claim {
+        &self.claim
+    }
+
+    /// Converts into the path to the runtime directory.
+    #[must_use]
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+/// How a runtime directory was claimed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Claim {
+    /// Directory was created.
+    Created,
+    /// Directory already existed, and is owned by the effective user.
+    Existing,
+    /// Directory's name was taken, so a uniquified name is used instead.
+    Uniquified(Collision),
+}
+
+/// Reasons a runtime directory's name was taken.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Collision {
+    /// Directory is owned by another user.
+    Owner {
+        /// User ID of the directory's owner.
+        uid: u32,
+    },
+    /// Path exists, but is not a directory (e.g. a file or symbolic link).
+    NotDirectory,
+}
+
+impl App {
+    /// Creates the application's runtime directory, [tracking](Tracker) it such
+    /// that it can be recovered should it be removed.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory could not be found or created.
+    pub fn track_runtime(&self) -> io::Result<Tracker> {
+        Tracker::new(self)
+    }
+
+    /// Claims the application's runtime directory, creating it such that only
+    /// the effective user can access it (i.e. with mode `0700`).
+    ///
+    /// If its name is already taken, by a directory owned by another user or by
+    /// something other than a directory, a uniquified name is claimed instead,
+    /// suffixed by the effective user's ID (and, should that be taken too, a
+    /// counter). As these are tried in order, every process of the same user
+    /// claims the same directory, such that clients can find a daemon's
+    /// sockets. The returned [claim](Claim) reports which case occurred.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory could not be found or created, or if
+    /// every uniquified name is taken as well.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xdir::runtime::Claim;
+    /// use xdir::App;
+    ///
+    /// let runtime = App::new("myapp").runtime_dir().unwrap();
+    /// if let Claim::Uniquified(collision) = runtime.claim() {
+    ///     eprintln!("warning: runtime directory is taken ({collision:?})");
+    /// }
+    /// let socket = runtime.path().join("myapp.sock");
+    /// ```
+    pub fn runtime_dir(&self) -> io::Result<RuntimeDir> {
+        let path = self.runtime().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "could not find runtime directory")
+        })?;
+        let mut collision = None;
+        for attempt in 0..=ATTEMPTS {
+            let candidate = if attempt == 0 {
+                path.clone()
+            } else {
+                uniquify(&path, attempt)
+            };
+            let mut claim = match inspect(&candidate)? {
+                Some(Ok(())) => {
+                    if !is_private(&candidate)? {
+                        self.check_writable()?;
+                        perms::apply(&candidate, 0o700)?;
+                    }
+                    Claim::Existing
+                }
+                Some(Err(taken)) => {
+                    collision.get_or_insert(taken);
+                    continue;
+                }
+                None => {
+                    self.create_dir(Dir::Runtime, &candidate)?;
+                    // Another user may have raced to create the directory.
+                    if let Some(Err(taken)) = inspect(&candidate)? {
+                        collision.get_or_insert(taken);
+                        continue;
+                    }
+                    perms::apply(&candidate, 0o700)?;
+                    Claim::Created
+                }
+            };
+            if let Some(collision) = collision {
+                claim = Claim::Uniquified(collision);
+            }
+            return Ok(RuntimeDir {
+                path: candidate,
+                claim,
+            });
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "runtime directory {} and its alternatives are taken",
+                path.display()
+            ),
+        ))
+    }
+}
+
+/// Returns a uniquified sibling of a path.
+fn uniquify(path: &Path, attempt: u32) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    #[cfg(unix)]
+    name.push(format!("-{}", crate::sys::euid()));
+    if attempt > 1 || cfg!(not(unix)) {
+        name.push(format!("-{attempt}"));
+    }
+    path.with_file_name(name)
+}
+
+/// Checks whether a directory is only accessible by its owner.
+#[cfg(unix)]
+fn is_private(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Ok(fs::metadata(path)?.permissions().mode() & 0o7777 == 0o700)
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn is_private(_: &Path) -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Inspects a path, returning [`None`] if it does not exist, or whether it can
+/// be claimed otherwise.
+fn inspect(path: &Path) -> io::Result<Option<Result<(), Collision>>> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    if !meta.is_dir() {
+        return Ok(Some(Err(Collision::NotDirectory)));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let uid = meta.uid();
+        if uid != crate::sys::euid() {
+            return Ok(Some(Err(Collision::Owner { uid })));
+        }
+    }
+    Ok(Some(Ok(())))
+}
+
+/// Identity of a directory.
+#[cfg(unix)]
+type Id = (u64, u64);
+
+#[cfg(not(unix))]
+type Id = ();
+
+/// Identifies a directory, returning [`None`] if it does not exist.
+#[cfg(unix)]
+fn identify(path: &Path) -> io::Result<Option<Id>> {
+    use std::os::unix::fs::MetadataExt;
+
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => Ok(Some((meta.dev(), meta.ino()))),
+        Ok(_) => Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(not(unix))]
+fn identify(path: &Path) -> io::Result<Option<Id>> {
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => Ok(Some(())),
+        Ok(_) => Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,154 @@
+//! Sandbox profile fragments.
+//!
+//! Confining an application (e.g. with [bubblewrap] or [Firejail]) requires
+//! listing the directories it may access. These helpers generate such lists
+//! from the application's resolved directories, such that wrapper scripts stay
+//! in sync with where the application actually reads and writes.
+//!
+//! The application's own directories are writable, whereas its system search
+//! path is read-only.
+//!
+//! [bubblewrap]: https://github.com/containers/bubblewrap
+//! [Firejail]: https://firejail.wordpress.com
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::process::Command;
+//!
+//! use xdir::{App, DirSet};
+//!
+//! let app = App::new("myapp");
+//! app.ensure(DirSet::ALL - DirSet::BIN).unwrap();
+//! Command::new("bwrap")
+//!     .args(["--ro-bind", "/usr", "/usr"])
+//!     .args(xdir::sandbox::bwrap_args(&app))
+//!     .arg("myapp")
+//!     .status()
+//!     .unwrap();
+//! ```
+
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{App, Dir};
+
+/// Returns the bubblewrap arguments binding the application's directories.
+///
+/// Each of the application's directories is bound read-write (`--bind`), and
+/// as such must exist beforehand (see [`App::ensure`]). System directories are
+/// bound read-only, if they exist (`--ro-bind-try`).
+#[must_use]
+pub fn bwrap_args(app: &App) -> Vec<OsString> {
+    let mut args = Vec::new();
+    let mut bind = |flag: &str, path: PathBuf| {
+        args.push(OsString::from(flag));
+        args.push(path.clone().into_os_string());
+        args.push(path.into_os_string());
+    };
+    for path in writable(app) {
+        bind("--bind", path);
+    }
+    for path in readable(app) {
+        bind("--ro-bind-try", path);
+    }
+    args
+}
+
+/// Returns the Firejail profile lines allowing access to the application's
+/// directories.
+///
+/// Each of the application's directories is created if missing (`mkdir`) and
+/// whitelisted (`whitelist`). System directories are marked read-only
+/// (`read-only`).
+///
+/// # Errors
+///
+/// Errors if the application's name or any directory is not valid UTF-8, or
+/// contains control characters (e.g. newlines), as these cannot be expressed
+/// within a profile.
+pub fn firejail_profile(app: &App) -> io::Result<String> {
+    let mut out = format!(
+        "# Directories of {}
+",
+        entry(Path::new(app.name()))?
+    );
+    for path in writable(app) {
+        let path = entry(&path)?;
+        let _ = writeln!(out, "mkdir {path}");
+        let _ = writeln!(out, "whitelist {path}");
+    }
+    for path in readable(app) {
+        let _ = writeln!(out, "read-only {}", entry(&path)?);
+    }
+    Ok(out)
+}
+
+/// Returns a path as written within a Firejail profile.
+///
+/// Profiles are line-based, such that a newline would inject further
+/// directives, and paths are taken literally, such that those which are not
+/// valid UTF-8 cannot be written lossily.
+fn entry(path: &Path) -> io::Result<&str> {
+    let text = path.to_str().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("path is not valid UTF-8: {}", path.display()),
+        )
+    })?;
+    if text.contains(char::is_control) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("path contains control characters: {text:?}"),
+        ));
+    }
+    Ok(text)
+}
+
+/// Returns the application's own directories.
+fn writable(app: &App) -> Vec<PathBuf> {
+    let base = app.resolve_all();
+    let mut paths = Vec::new();
+    for dir in [Dir::Config, Dir::Data, Dir::State, Dir::Cache, Dir::Runtime] {
+        match app.dir_in(&base, dir) {
+            Some(path) if !paths.contains(&path) => paths.push(path),
+            _ => (),
+        }
+    }
+    paths
+}
+
+/// Returns the application's system search path.
+fn readable(app: &App) -> Vec<PathBuf> {
+    let own = writable(app);
+    app.config_dirs()
+        .into_iter()
+        .chain(app.data_dirs())
+        .filter(|path| !own.contains(path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn firejail_profile_rejects_injection() {
+        let app = App::new("myapp\nwhitelist /");
+        let err = firejail_profile(&app).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(entry(Path::new("/home/me/.config/my app")).is_ok());
+        assert!(entry(Path::new("/home/me/.config/my\rapp")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn firejail_profile_rejects_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/home/me/\xff"));
+        assert_eq!(entry(path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,80 @@
+//! Scopes of application directories.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::Dir;
+
+/// Scope of an application's directories.
+///
+/// Applications usually run on behalf of a user, storing their files within
+/// the user's directories. System services instead run on behalf of the
+/// machine, and should store their files in machine-wide locations, rather
+/// than within the profile of their service account.
+///
+/// | Directory | Unix               | Windows                      |
+/// |-----------|--------------------|------------------------------|
+/// | Cache     | `/var/cache/<app>` | `%ProgramData%\<app>\cache`  |
+/// | Config    | `/etc/<app>`       | `%ProgramData%\<app>\config` |
+/// | Data      | `/var/lib/<app>`   | `%ProgramData%\<app>\data`   |
+/// | Runtime   | `/run/<app>`       | None                         |
+/// | State     | `/var/lib/<app>`   | `%ProgramData%\<app>\state`  |
+///
+/// On Unix, these follow the Filesystem Hierarchy Standard (as do systemd's
+/// `CacheDirectory=`, `ConfigurationDirectory=`, etc.), under which data and
+/// state share a directory. The executable directory is unaffected by
+/// scope.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Scope {
+    /// Directories of the current user.
+    #[default]
+    User,
+    /// Machine-wide directories, for use by system services.
+    Machine,
+}
+
+impl Scope {
+    /// Detects the scope of the running process.
+    ///
+    /// This is the machine scope when running as one of Windows' service
+    /// accounts (i.e. `LocalSystem`, `LocalService` or `NetworkService`), and
+    /// the user scope otherwise. As Unix has no such accounts, services there
+    /// must opt into the machine scope explicitly.
+    #[must_use]
+    pub fn detect() -> Self {
+        static SCOPE: OnceLock<Scope> = OnceLock::new();
+        *SCOPE.get_or_init(|| {
+            if crate::sys::is_service() {
+                Self::Machine
+            } else {
+                Self::User
+            }
+        })
+    }
+}
+
+/// Returns the machine-wide location of one of an application's directories.
+#[cfg(unix)]
+pub(crate) fn machine(app: &str, dir: Dir) -> Option<PathBuf> {
+    let base = match dir {
+        Dir::Bin => return None,
+        Dir::Cache => "/var/cache",
+        Dir::Config => "/etc",
+        Dir::Data | Dir::State => "/var/lib",
+        Dir::Runtime => "/run",
+    };
+    Some(PathBuf::from(base).join(app))
+}
+
+/// Returns the machine-wide location of one of an application's directories.
+#[cfg(not(unix))]
+pub(crate) fn machine(app: &str, dir: Dir) -> Option<PathBuf> {
+    if matches!(dir, Dir::Bin | Dir::Runtime) {
+        return None;
+    }
+    let root = std::env::var_os("ProgramData")
+        .or_else(|| std::env::var_os("ALLUSERSPROFILE"))
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())?;
+    Some(root.join(app).join(dir.to_string()))
+}
@@ -0,0 +1,25 @@
+//! Search path traversal.
+
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+/// Lists the entries of each directory in a search path.
+///
+/// Entries are ordered by their directory's preference, then by name. If
+/// `once` is set, entries shadowed by a more preferred entry of the same name
+/// are skipped.
+pub(crate) fn list(dirs: &[PathBuf], once: bool) -> Vec<PathBuf> {
+    let mut seen = HashSet::<OsString>::new();
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| {
+            let mut entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+            entries.sort_by_key(fs::DirEntry::file_name);
+            entries
+        })
+        .filter(|entry| !once || seen.insert(entry.file_name()))
+        .map(|entry| entry.path())
+        .collect()
+}
@@ -0,0 +1,166 @@
+//! Secret files.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::{app, atomic, Dir};
+
+/// A file holding secrets, such as API tokens.
+///
+/// Secret files are only ever accessible by their owner: on Unix they are
+/// created with mode `0600`, and on Windows with an owner-only access control
+/// list. Permissions are applied as the file is created, rather than after its
+/// contents are written.
+///
+/// Reading a secret file which other users could access is refused by default,
+/// as its contents may have been exposed (or tampered with). Permissions are
+/// only verified on Unix.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xdir::Dir;
+///
+/// let token = xdir::secret_file(Dir::State, "myapp/token").unwrap();
+/// token.write("hunter2").unwrap();
+/// assert_eq!(token.read_to_string().unwrap(), "hunter2");
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SecretFile {
+    path: PathBuf,
+    insecure: bool,
+}
+
+impl SecretFile {
+    /// Returns the path of the secret file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Sets whether the file may be read even if other users could access it.
+    ///
+    /// This is disabled by default.
+    #[must_use]
+    pub fn allow_insecure(mut self, allow: bool) -> Self {
+        self.insecure = allow;
+        self
+    }
+
+    /// Reads the contents of the secret file.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file could not be read, or if its permissions allow other
+    /// users to access it (unless [allowed](Self::allow_insecure)).
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        if !self.insecure {
+            self.verify(&file)?;
+        }
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    /// Reads the contents of the secret file as a string.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file could not be read or is not valid UTF-8, or if its
+    /// permissions allow other users to access it (unless
+    /// [allowed](Self::allow_insecure)).
+    pub fn read_to_string(&self) -> io::Result<String> {
+        String::from_utf8(self.read()?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Writes the contents of the secret file atomically, creating its leading
+    /// directories.
+    ///
+    /// New directories are only accessible by their owner.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file or its directories could not be written.
+    pub fn write(&self, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            let mut builder = fs::DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder.create(parent)?;
+        }
+        atomic::write_private(&self.path, contents)
+    }
+
+    #[cfg_attr(not(unix), allow(clippy::unused_self, clippy::unnecessary_wraps))]
+    fn verify(&self, file: &File) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let meta = file.metadata()?;
+            let euid = crate::sys::euid();
+            if meta.uid() != euid {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "{} is owned by uid {}, not the current user (uid {euid})",
+                        self.path.display(),
+                        meta.uid()
+                    ),
+                ));
+            }
+            let mode = meta.mode() & 0o777;
+            if mode & 0o077 != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "{} is accessible by other users (mode {mode:04o}); restrict it with \
+                         `chmod 600`",
+                        self.path.display()
+                    ),
+                ));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = file;
+        Ok(())
+    }
+}
+
+/// Returns a [secret file](SecretFile) within one of the user's directories.
+///
+/// The path is relative to the directory (e.g. `myapp/token`).
+///
+/// # Errors
+///
+/// Errors if the directory could not be found, or if the path is not relative
+/// to it.
+pub fn secret_file(dir: Dir, path: impl AsRef<Path>) -> io::Result<SecretFile> {
+    let base = dir.path().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find {dir} directory"),
+        )
+    })?;
+    Ok(SecretFile {
+        path: app::join(&base, path.as_ref())?,
+        insecure: false,
+    })
+}
+
+/// Returns a [secret file](SecretFile) within the user's runtime directory.
+///
+/// As the runtime directory is removed when the user logs out, this suits
+/// secrets which should not outlive the session (e.g. session tokens).
+///
+/// # Errors
+///
+/// Errors if the runtime directory could not be found, or if the path is not
+/// relative to it.
+pub fn runtime_secret_file(path: impl AsRef<Path>) -> io::Result<SecretFile> {
+    secret_file(Dir::Runtime, path)
+}
@@ -0,0 +1,93 @@
+//! Desktop session information.
+//!
+//! Session managers describe the user's session through environment variables,
+//! which applications can use to adapt their behaviour to the desktop they are
+//! running in.
+//!
+//! | Function            | Environment            |
+//! |---------------------|------------------------|
+//! | [`current_desktop`] | `$XDG_CURRENT_DESKTOP` |
+//! | [`session_type`]    | `$XDG_SESSION_TYPE`    |
+//! | [`session_class`]   | `$XDG_SESSION_CLASS`   |
+
+use std::env;
+
+/// Returns the names of the current desktop environment, in order of
+/// specificity.
+///
+/// Desktops may report several names (e.g. `ubuntu:GNOME`), the first of which
+/// is the most specific. Returns an empty list if the desktop is unknown.
+#[must_use]
+pub fn current_desktop() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Type of a session.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SessionType {
+    /// Wayland compositor.
+    Wayland,
+    /// X11 server.
+    X11,
+    /// Text console.
+    Tty,
+    /// Mir display server.
+    Mir,
+    /// Unspecified, such as for non-graphical sessions.
+    Unspecified,
+    /// Unrecognized type.
+    Other(String),
+}
+
+/// Returns the type of the current session.
+#[must_use]
+pub fn session_type() -> Option<SessionType> {
+    let kind = env::var("XDG_SESSION_TYPE")
+        .ok()
+        .filter(|kind| !kind.is_empty())?;
+    Some(match kind.as_str() {
+        "wayland" => SessionType::Wayland,
+        "x11" => SessionType::X11,
+        "tty" => SessionType::Tty,
+        "mir" => SessionType::Mir,
+        "unspecified" => SessionType::Unspecified,
+        _ => SessionType::Other(kind),
+    })
+}
+
+/// Class of a session.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SessionClass {
+    /// Regular user session.
+    User,
+    /// Display manager's login screen.
+    Greeter,
+    /// Screen lock.
+    LockScreen,
+    /// Session without a seat.
+    Background,
+    /// Unrecognized class.
+    Other(String),
+}
+
+/// Returns the class of the current session.
+#[must_use]
+pub fn session_class() -> Option<SessionClass> {
+    let class = env::var("XDG_SESSION_CLASS")
+        .ok()
+        .filter(|class| !class.is_empty())?;
+    Some(match class.as_str() {
+        "user" => SessionClass::User,
+        "greeter" => SessionClass::Greeter,
+        "lock-screen" => SessionClass::LockScreen,
+        "background" => SessionClass::Background,
+        _ => SessionClass::Other(class),
+    })
+}
@@ -0,0 +1,201 @@
+//! Integration with command-line shells.
+//!
+//! Shells load completions for commands from conventional per-user
+//! directories, which are provided here for each supported [`Shell`]. Resolved
+//! directories can also be [exported](export) as a script for each shell, such
+//! that it can bootstrap its environment from them.
+//!
+//! # Examples
+//!
+//! To install completions generated by an application:
+//!
+//! ```no_run
+//! use xdir::shell::{self, Shell};
+//!
+//! let script = "complete -W 'build test' myapp\n";
+//! shell::install_completion(Shell::Bash, "myapp", script).unwrap();
+//! ```
+
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::{atomic, config, data, Env};
+
+/// Supported shells.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Shell {
+    /// Bourne Again Shell.
+    Bash,
+    /// Z Shell.
+    Zsh,
+    /// Friendly Interactive Shell.
+    Fish,
+    /// Nushell.
+    Nushell,
+    /// PowerShell.
+    PowerShell,
+}
+
+impl Shell {
+    /// Returns the name of a command's completion file for this shell.
+    #[must_use]
+    pub fn completion_file(self, name: &str) -> String {
+        match self {
+            Self::Bash => name.to_string(),
+            Self::Zsh => format!("_{name}"),
+            Self::Fish => format!("{name}.fish"),
+            Self::Nushell => format!("{name}.nu"),
+            Self::PowerShell => format!("{name}.ps1"),
+        }
+    }
+}
+
+impl Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Nushell => "nu",
+            Self::PowerShell => "pwsh",
+        })
+    }
+}
+
+impl FromStr for Shell {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "nu" | "nushell" => Ok(Self::Nushell),
+            "pwsh" | "powershell" => Ok(Self::PowerShell),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported shell: {s:?}"),
+            )),
+        }
+    }
+}
+
+/// Returns the path to the user's completion directory for a shell.
+///
+/// | Shell   | Directory                                    |
+/// |---------|----------------------------------------------|
+/// | Bash    | `$XDG_DATA_HOME/bash-completion/completions` |
+/// | Zsh     | `$XDG_DATA_HOME/zsh/site-functions`          |
+/// | Fish    | `$XDG_CONFIG_HOME/fish/completions`          |
+/// | Nushell | `$XDG_DATA_HOME/nushell/vendor/autoload`     |
+///
+/// Note that Zsh does not search its directory by default; it must be added to
+/// the user's `fpath`. PowerShell has no completion directory, as completions
+/// are registered by its profile; as such, [`None`] is returned.
+#[must_use]
+pub fn completions(shell: Shell) -> Option<PathBuf> {
+    match shell {
+        Shell::Bash => data().map(|path| path.join("bash-completion/completions")),
+        Shell::Zsh => data().map(|path| path.join("zsh/site-functions")),
+        Shell::Fish => config().map(|path| path.join("fish/completions")),
+        Shell::Nushell => data().map(|path| path.join("nushell/vendor/autoload")),
+        Shell::PowerShell => None,
+    }
+}
+
+/// Installs a command's completions for a shell, returning its path.
+///
+/// # Errors
+///
+/// Errors if the name is invalid, if the shell has no completion directory or
+/// it could not be found, or if the file could not be written.
+pub fn install_completion(shell: Shell, name: &str, contents: &str) -> io::Result<PathBuf> {
+    if shell == Shell::PowerShell {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "powershell has no completion directory",
+        ));
+    }
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid command name: {name:?}"),
+        ));
+    }
+    let path = completions(shell)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find home directory"))?
+        .join(shell.completion_file(name));
+    atomic::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Exports the variables of an environment as a script for a shell.
+///
+/// Each variable is assigned on its own line, using the shell's syntax:
+///
+/// | Shell      | Syntax                          |
+/// |------------|---------------------------------|
+/// | Bash, Zsh  | `export XDG_CONFIG_HOME='...'`  |
+/// | Fish       | `set -gx XDG_CONFIG_HOME '...'` |
+/// | Nushell    | `$env.XDG_CONFIG_HOME = "..."`  |
+/// | PowerShell | `$env:XDG_CONFIG_HOME = '...'`  |
+///
+/// Values are quoted, such that they are assigned literally.
+///
+/// # Errors
+///
+/// Errors if a variable's name is not a valid identifier, or if its value is
+/// not valid Unicode.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::shell::{self, Shell};
+/// use xdir::Env;
+///
+/// let env = Env::new().var("XDG_CONFIG_HOME", "/home/user/.config");
+/// let script = shell::export(Shell::PowerShell, &env).unwrap();
+/// assert_eq!(script, "$env:XDG_CONFIG_HOME = '/home/user/.config'\n");
+/// ```
+pub fn export(shell: Shell, env: &Env) -> io::Result<String> {
+    let mut out = String::new();
+    for (name, value) in env.to_vars() {
+        let invalid = |why: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot export {name}: {why}"),
+            )
+        };
+        if name.is_empty()
+            || name.starts_with(|char: char| char.is_ascii_digit())
+            || !name
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '_')
+        {
+            return Err(invalid("invalid variable name"));
+        }
+        let value = value
+            .to_str()
+            .ok_or_else(|| invalid("value is not valid Unicode"))?;
+        let line = match shell {
+            Shell::Bash | Shell::Zsh => {
+                format!("export {name}='{}'", value.replace('\'', "'\\''"))
+            }
+            Shell::Fish => format!(
+                "set -gx {name} '{}'",
+                value.replace('\\', "\\\\").replace('\'', "\\'")
+            ),
+            Shell::Nushell => format!(
+                "$env.{name} = \"{}\"",
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            Shell::PowerShell => format!("$env:{name} = '{}'", value.replace('\'', "''")),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
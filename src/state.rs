@@ -0,0 +1,531 @@
+//! Versioned state files.
+//!
+//! State files outlive the releases which wrote them, such that their format
+//! must be upgraded as it evolves. A [`StateFile`] embeds the version of its
+//! schema as a top-level `version` key, and upgrades older files one version
+//! at a time through registered functions when opened.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use serde::{Deserialize, Serialize};
+//! use xdir::state::StateFile;
+//!
+//! #[derive(Default, Deserialize, Serialize)]
+//! struct History {
+//!     entries: Vec<String>,
+//! }
+//!
+//! let mut history = StateFile::<History>::schema(2)
+//!     .register_upgrade(1, |table| {
+//!         // Version 2 renamed `items` to `entries`.
+//!         if let Some(items) = table.remove("items") {
+//!             table.insert("entries".into(), items);
+//!         }
+//!         Ok(())
+//!     })
+//!     .open("myapp/history.toml")
+//!     .unwrap();
+//! history.get_mut().entries.push("ls".into());
+//! history.save().unwrap();
+//! ```
+//!
+//! State files shared by several instances of an application can be
+//! [subscribed](StateFile::subscribe) to, such that each instance reloads the
+//! writes of the others.
+
+use std::fmt::{self, Debug};
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use toml::{Table, Value};
+
+use crate::{app, atomic};
+
+/// Key of the schema version.
+const VERSION: &str = "version";
+
+/// An upgrade function.
+type Upgrade = Box<dyn Fn(&mut Table) -> io::Result<()>>;
+
+/// The schema of a state file, along with its upgrades.
+pub struct Schema<T> {
+    version: u64,
+    upgrades: Vec<(u64, Upgrade)>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Schema<T> {
+    /// Registers an upgrade from a version to its successor.
+    ///
+    /// The function is given the file's contents (excluding its version) to
+    /// modify in place.
+    #[must_use]
+    pub fn register_upgrade<F>(mut self, from: u64, f: F) -> Self
+    where
+        F: Fn(&mut Table) -> io::Result<()> + 'static,
+    {
+        self.upgrades.retain(|(other, _)| *other != from);
+        self.upgrades.push((from, Box::new(f)));
+        self
+    }
+}
+
+impl<T: DeserializeOwned + Serialize + Default> Schema<T> {
+    /// Opens a state file, upgrading it to the current version of the schema.
+    ///
+    /// The path is relative to the user's state directory (e.g.
+    /// `myapp/history.toml`). If the file does not exist, it holds the default
+    /// value until [saved](StateFile::save). Upgraded files are written back
+    /// atomically.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the state directory could not be found, if the path is not
+    /// relative to it, if the file could not be read or parsed, if its version
+    /// is missing or newer than the schema, or if it could not be upgraded.
+    pub fn open(&self, path: impl AsRef<Path>) -> io::Result<StateFile<T>> {
+        let base = crate::state().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "could not find state directory")
+        })?;
+        self.open_at(app::join(&base, path.as_ref())?)
+    }
+
+    /// Opens a state file at an absolute path, upgrading it to the current
+    /// version of the schema.
+    fn open_at(&self, path: PathBuf) -> io::Result<StateFile<T>> {
+        let Some((version, mut table)) = read(&path)? else {
+            return Ok(StateFile {
+                path,
+                version: self.version,
+                value: T::default(),
+            });
+        };
+        if version > self.version {
+            return Err(invalid(
+                &path,
+                format!(
+                    "version {version} is newer than supported ({})",
+                    self.version
+                ),
+            ));
+        }
+        for from in version..self.version {
+            let (_, upgrade) = self
+                .upgrades
+                .iter()
+                .find(|(other, _)| *other == from)
+                .ok_or_else(|| invalid(&path, format!("no upgrade from version {from}")))?;
+            upgrade(&mut table)?;
+        }
+        let value = table.try_into().map_err(|err| invalid(&path, err))?;
+        let file = StateFile {
+            path,
+            version: self.version,
+            value,
+        };
+        if version < self.version {
+            file.save()?;
+        }
+        Ok(file)
+    }
+}
+
+impl<T> Debug for Schema<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Schema")
+            .field("version", &self.version)
+            .field(
+                "upgrades",
+                &self
+                    .upgrades
+                    .iter()
+                    .map(|(from, _)| from)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// A state file with an embedded schema version.
+#[derive(Clone, Debug)]
+pub struct StateFile<T> {
+    path: PathBuf,
+    version: u64,
+    value: T,
+}
+
+impl<T> StateFile<T> {
+    /// Constructs the schema of a state file at the given version.
+    #[must_use]
+    pub fn schema(version: u64) -> Schema<T> {
+        Schema {
+            version,
+            upgrades: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the path of the state file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the schema version of the state file.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the value of the state file.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value of the state file mutably.
+    ///
+    /// Changes are not written until the file is [saved](Self::save).
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Consumes the state file, returning its value.
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: DeserializeOwned + Serialize + Default> StateFile<T> {
+    /// Opens a state file without any upgrades, at version 1.
+    ///
+    /// See [`Schema::open`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file could not be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::schema(1).open(path)
+    }
+
+    /// Reads the state file again, replacing its value.
+    ///
+    /// This picks up changes written by other processes (see
+    /// [`subscribe`](Self::subscribe)). If the file no longer exists, it holds
+    /// the default value.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file could not be read or parsed, or if it was written at
+    /// another version of the schema.
+    pub fn reload(&mut self) -> io::Result<()> {
+        let Some((version, table)) = read(&self.path)? else {
+            self.value = T::default();
+            return Ok(());
+        };
+        if version != self.version {
+            return Err(invalid(
+                &self.path,
+                format!("version {version} differs from {}", self.version),
+            ));
+        }
+        self.value = table.try_into().map_err(|err| invalid(&self.path, err))?;
+        Ok(())
+    }
+}
+
+impl<T> StateFile<T> {
+    /// Subscribes to changes of the state file, made by any process.
+    ///
+    /// Changes include this process's own [saves](Self::save). Changes are
+    /// detected through filesystem notifications when the `notify` feature is
+    /// enabled, and otherwise by periodically polling the file.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the subscription could not be started.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use xdir::state::StateFile;
+    ///
+    /// let mut seen = StateFile::<toml::Table>::open("myapp/seen.toml").unwrap();
+    /// let changes = seen.subscribe().unwrap();
+    /// loop {
+    ///     if changes.wait_timeout(Duration::from_secs(1)) {
+    ///         seen.reload().unwrap();
+    ///     }
+    /// }
+    /// ```
+    pub fn subscribe(&self) -> io::Result<Subscription> {
+        let (tx, rx) = mpsc::channel();
+        let watch = watch(self.path.clone(), move || tx.send(()).is_ok())?;
+        Ok(Subscription { rx, _watch: watch })
+    }
+}
+
+impl<T: Serialize> StateFile<T> {
+    /// Writes the state file atomically, along with its version.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value could not be serialized, or if the file could not
+    /// be written.
+    pub fn save(&self) -> io::Result<()> {
+        let mut table = Table::try_from(&self.value).map_err(|err| invalid(&self.path, err))?;
+        let version = i64::try_from(self.version).map_err(|err| invalid(&self.path, err))?;
+        table.insert(VERSION.into(), Value::Integer(version));
+        let text = toml::to_string(&table).map_err(|err| invalid(&self.path, err))?;
+        atomic::write(&self.path, text)
+    }
+}
+
+/// A subscription to changes of a [state file](StateFile).
+///
+/// Changes are coalesced, such that several writes in quick succession may be
+/// reported once. The subscription ends once dropped.
+#[derive(Debug)]
+pub struct Subscription {
+    rx: Receiver<()>,
+    _watch: Watch,
+}
+
+impl Subscription {
+    /// Checks whether the file has changed since last checked, without
+    /// blocking.
+    #[must_use]
+    pub fn changed(&self) -> bool {
+        let changed = self.rx.try_recv().is_ok();
+        self.drain();
+        changed
+    }
+
+    /// Waits for the file to change.
+    ///
+    /// Returns `false` if changes can no longer be detected.
+    #[must_use]
+    pub fn wait(&self) -> bool {
+        let changed = self.rx.recv().is_ok();
+        self.drain();
+        changed
+    }
+
+    /// Waits for the file to change, up to a timeout.
+    ///
+    /// Returns whether the file changed.
+    #[must_use]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let changed = !matches!(
+            self.rx.recv_timeout(timeout),
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected)
+        );
+        self.drain();
+        changed
+    }
+
+    /// Discards pending changes, as they are reported at once.
+    fn drain(&self) {
+        while self.rx.try_recv().is_ok() {}
+    }
+}
+
+/// Watches a file, invoking the callback on each change until it returns
+/// `false`.
+#[cfg(feature = "notify")]
+fn watch(path: PathBuf, notify: impl Fn() -> bool + Send + 'static) -> io::Result<Watch> {
+    crate::watch::watch_path(path, move |event| {
+        if event.is_ok_and(|event| !event.kind.is_access()) {
+            notify();
+        }
+    })
+}
+
+#[cfg(feature = "notify")]
+type Watch = crate::watch::Watcher;
+
+/// Watches a file, invoking the callback on each change until it returns
+/// `false`.
+///
+/// Without filesystem notifications, the file is polled for a change of its
+/// identity, size or modification time.
+#[cfg(not(feature = "notify"))]
+#[allow(clippy::unnecessary_wraps)]
+fn watch(path: PathBuf, notify: impl Fn() -> bool + Send + 'static) -> io::Result<Watch> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Interval between polls.
+    const INTERVAL: Duration = Duration::from_millis(250);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = Arc::clone(&stop);
+    thread::spawn(move || {
+        let mut last = stamp(&path);
+        while !stopped.load(Ordering::Relaxed) {
+            thread::sleep(INTERVAL);
+            let stamp = stamp(&path);
+            if stamp != last {
+                last = stamp;
+                if !notify() {
+                    break;
+                }
+            }
+        }
+    });
+    Ok(Watch { stop })
+}
+
+/// A polling watch, which stops once dropped.
+#[cfg(not(feature = "notify"))]
+#[derive(Debug)]
+struct Watch {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(not(feature = "notify"))]
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Returns what changes when a file is written atomically.
+#[cfg(not(feature = "notify"))]
+fn stamp(path: &Path) -> Option<(Option<std::time::SystemTime>, u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    #[cfg(unix)]
+    let id = std::os::unix::fs::MetadataExt::ino(&meta);
+    #[cfg(not(unix))]
+    let id = 0;
+    Some((meta.modified().ok(), meta.len(), id))
+}
+
+/// Reads a state file along with its version, if it exists.
+fn read(path: &Path) -> io::Result<Option<(u64, Table)>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let mut table = text.parse::<Table>().map_err(|err| invalid(path, err))?;
+    let version = match table.remove(VERSION) {
+        Some(Value::Integer(version)) => u64::try_from(version).ok(),
+        _ => None,
+    }
+    .ok_or_else(|| invalid(path, "missing or invalid version"))?;
+    Ok(Some((version, table)))
+}
+
+fn invalid(path: &Path, err: impl fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {err}", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+    struct History {
+        entries: Vec<String>,
+        limit: i64,
+    }
+
+    /// A schema at version 3, which renamed `items` to `entries` in version 2,
+    /// and added `limit` in version 3.
+    fn schema() -> Schema<History> {
+        StateFile::<History>::schema(3)
+            .register_upgrade(2, |table| {
+                table.insert("limit".into(), Value::Integer(100));
+                Ok(())
+            })
+            .register_upgrade(1, |table| {
+                let items = table.remove("items").unwrap_or(Value::Array(Vec::new()));
+                table.insert("entries".into(), items);
+                Ok(())
+            })
+    }
+
+    #[test]
+    fn upgrades_each_version() {
+        let tmp = TempDir::new();
+        let path = tmp.join("history.toml");
+        fs::write(&path, "version = 1\nitems = [\"ls\"]\n").unwrap();
+        let file = schema().open_at(path.clone()).unwrap();
+        assert_eq!(file.version(), 3);
+        let history = History {
+            entries: vec!["ls".into()],
+            limit: 100,
+        };
+        assert_eq!(file.get(), &history);
+        // The upgraded file is written back.
+        let (version, table) = read(&path).unwrap().unwrap();
+        assert_eq!(version, 3);
+        assert_eq!(History::deserialize(table).unwrap(), history);
+        // Current files are opened as-is.
+        let file = schema().open_at(path).unwrap();
+        assert_eq!(file.into_inner(), history);
+    }
+
+    #[test]
+    fn defaults_missing_files() {
+        let tmp = TempDir::new();
+        let path = tmp.join("history.toml");
+        let file = schema().open_at(path.clone()).unwrap();
+        assert_eq!((file.version(), file.get()), (3, &History::default()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn rejects_traversal() {
+        for path in ["../history.toml", "/tmp/history.toml"] {
+            let err = schema().open(path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{path}");
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let tmp = TempDir::new();
+        let path = tmp.join("history.toml");
+        for text in ["version = 4\n", "version = 0\n", "entries = []\n"] {
+            fs::write(&path, text).unwrap();
+            let err = schema().open_at(path.clone()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{text}");
+            // Files which could not be upgraded are left in place.
+            assert_eq!(fs::read_to_string(&path).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn keeps_failed_upgrades() {
+        let tmp = TempDir::new();
+        let path = tmp.join("history.toml");
+        let text = "version = 2\nentries = []\n";
+        fs::write(&path, text).unwrap();
+        let err = StateFile::<History>::schema(3)
+            .register_upgrade(2, |_| Err(io::Error::other("upgrade failed")))
+            .open_at(path.clone())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "upgrade failed");
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    }
+}
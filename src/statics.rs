@@ -0,0 +1,63 @@
+//! Directory locations resolved once.
+//!
+//! The functions at the crate root resolve each directory anew on every call,
+//! such that changes to the environment (or override file) are always
+//! respected. Applications which access their directories repeatedly, and are
+//! fine with their resolution being frozen at first use, may instead use these
+//! statics. Each is resolved the first time it is accessed, and never again.
+//!
+//! # Examples
+//!
+//! ```
+//! use xdir::statics;
+//!
+//! if let Some(config) = statics::CONFIG.as_deref() {
+//!     println!("config: {}", config.display());
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::Dir;
+
+/// Location of the user's home directory, resolved once.
+pub static HOME: LazyLock<Option<PathBuf>> = LazyLock::new(crate::home);
+
+/// Location of the user's executable directory, resolved once.
+pub static BIN: LazyLock<Option<PathBuf>> = LazyLock::new(crate::bin);
+
+/// Location of the user's cache directory, resolved once.
+pub static CACHE: LazyLock<Option<PathBuf>> = LazyLock::new(crate::cache);
+
+/// Location of the user's config directory, resolved once.
+pub static CONFIG: LazyLock<Option<PathBuf>> = LazyLock::new(crate::config);
+
+/// Location of the user's data directory, resolved once.
+pub static DATA: LazyLock<Option<PathBuf>> = LazyLock::new(crate::data);
+
+/// Location of the user's runtime directory, resolved once.
+pub static RUNTIME: LazyLock<Option<PathBuf>> = LazyLock::new(crate::runtime);
+
+/// Location of the user's state directory, resolved once.
+pub static STATE: LazyLock<Option<PathBuf>> = LazyLock::new(crate::state);
+
+/// Preference-ordered system config directories, resolved once.
+pub static CONFIG_DIRS: LazyLock<Vec<PathBuf>> = LazyLock::new(crate::config_dirs);
+
+/// Preference-ordered system data directories, resolved once.
+pub static DATA_DIRS: LazyLock<Vec<PathBuf>> = LazyLock::new(crate::data_dirs);
+
+/// Returns the location of one of the user's directories, resolved once.
+#[must_use]
+pub fn get(dir: Dir) -> Option<&'static Path> {
+    match dir {
+        Dir::Bin => &BIN,
+        Dir::Cache => &CACHE,
+        Dir::Config => &CONFIG,
+        Dir::Data => &DATA,
+        Dir::Runtime => &RUNTIME,
+        Dir::State => &STATE,
+    }
+    .as_deref()
+}
@@ -0,0 +1,469 @@
+//! Platform-specific functionality.
+
+/// Returns the effective user ID of the process.
+#[cfg(unix)]
+#[cfg(any(
+    feature = "audit",
+    feature = "io-helpers",
+    feature = "owner",
+    feature = "preflight",
+    feature = "runtime"
+))]
+pub(crate) fn euid() -> u32 {
+    // SAFETY: `geteuid` is always successful and has no side effects.
+    unsafe { libc::geteuid() }
+}
+
+/// Creates a new file which only its owner can access.
+///
+/// Permissions are applied as the file is created, such that its contents are
+/// never exposed to other users.
+#[cfg(unix)]
+#[cfg(any(
+    feature = "backup",
+    feature = "cache",
+    feature = "desktop",
+    feature = "install",
+    feature = "io-helpers",
+    feature = "mime",
+    feature = "shell",
+    feature = "systemd",
+    feature = "toml"
+))]
+pub(crate) fn create_private(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+/// Creates a new file which only its owner can access.
+///
+/// Permissions are applied as the file is created, such that its contents are
+/// never exposed to other users.
+#[cfg(windows)]
+#[cfg(any(
+    feature = "backup",
+    feature = "cache",
+    feature = "desktop",
+    feature = "install",
+    feature = "io-helpers",
+    feature = "mime",
+    feature = "shell",
+    feature = "systemd",
+    feature = "toml"
+))]
+pub(crate) fn create_private(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::FromRawHandle;
+    use std::ptr;
+
+    use windows_sys::Win32::Foundation::{GENERIC_READ, GENERIC_WRITE, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{CreateFileW, CREATE_NEW, FILE_ATTRIBUTE_NORMAL};
+
+    let name = wide(path.as_os_str());
+    // Protected access control list, granting access to the file's owner only.
+    let handle = with_security("D:P(A;;FA;;;OW)", |attrs| {
+        // SAFETY: The file name is nul-terminated, and the security attributes
+        // remain valid for the duration of the call.
+        let handle = unsafe {
+            CreateFileW(
+                name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                attrs,
+                CREATE_NEW,
+                FILE_ATTRIBUTE_NORMAL,
+                ptr::null_mut(),
+            )
+        };
+        // Errors are retrieved before the descriptor is freed.
+        if handle == INVALID_HANDLE_VALUE {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(handle)
+        }
+    })??;
+    // SAFETY: The handle is valid and exclusively owned.
+    Ok(unsafe { File::from_raw_handle(handle) })
+}
+
+/// Recursively creates a directory which only the system, administrators and
+/// its owner can access.
+///
+/// Permissions are applied to each directory as it is created, and inherited
+/// by its contents. Existing directories are left unchanged.
+#[cfg(windows)]
+#[cfg(feature = "core")]
+pub(crate) fn create_restricted_dir_all(path: &std::path::Path) -> std::io::Result<()> {
+    use std::io;
+
+    use windows_sys::Win32::Storage::FileSystem::CreateDirectoryW;
+
+    if path.is_dir() {
+        return Ok(());
+    }
+    crate::readonly::check()?;
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        create_restricted_dir_all(parent)?;
+    }
+    let name = wide(path.as_os_str());
+    // Protected access control list, inherited by files and subdirectories.
+    let sddl = "D:P(A;OICI;FA;;;SY)(A;OICI;FA;;;BA)(A;OICI;FA;;;OW)";
+    let result = with_security(sddl, |attrs| {
+        // SAFETY: The directory name is nul-terminated, and the security
+        // attributes remain valid for the duration of the call.
+        if unsafe { CreateDirectoryW(name.as_ptr(), attrs) } == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    })?;
+    match result {
+        // Another process may have created the directory in the meantime.
+        Err(_) if path.is_dir() => Ok(()),
+        result => result,
+    }
+}
+
+/// Recursively creates a directory which only the system, administrators and
+/// its owner can access.
+#[cfg(not(windows))]
+#[cfg(feature = "core")]
+pub(crate) fn create_restricted_dir_all(path: &std::path::Path) -> std::io::Result<()> {
+    create_dir_all(path)
+}
+
+/// Recursively creates a directory, tolerating races with other processes.
+///
+/// Directories created concurrently by another process are accepted as-is, as
+/// by [`std::fs::create_dir_all`]. However, should a parent be removed between
+/// its creation and that of its child (e.g. by a concurrent cleanup of an empty
+/// cache), creation fails with [`NotFound`](std::io::ErrorKind::NotFound)
+/// despite the next attempt likely succeeding. Such failures are retried a few
+/// times, with a brief backoff allowing the cleanup to finish.
+#[cfg(any(
+    test,
+    feature = "core",
+    feature = "desktop",
+    feature = "install",
+    feature = "mime",
+    feature = "owner",
+    feature = "shell",
+    feature = "systemd",
+    feature = "xdg"
+))]
+pub(crate) fn create_dir_all(path: &std::path::Path) -> std::io::Result<()> {
+    use std::io::ErrorKind;
+    use std::thread;
+    use std::time::Duration;
+
+    const ATTEMPTS: u32 = 4;
+
+    if path.is_dir() {
+        return Ok(());
+    }
+    crate::readonly::check()?;
+    let mut attempt = 1;
+    loop {
+        match std::fs::create_dir_all(path) {
+            Err(err) if err.kind() == ErrorKind::NotFound && attempt < ATTEMPTS => {
+                thread::sleep(Duration::from_millis(1 << attempt));
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Checks whether the process is running as one of Windows' service accounts.
+#[cfg(windows)]
+#[cfg(feature = "core")]
+pub(crate) fn is_service() -> bool {
+    use std::ptr;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::{
+        GetTokenInformation, IsWellKnownSid, TokenUser, WinLocalServiceSid, WinLocalSystemSid,
+        WinNetworkServiceSid, TOKEN_QUERY, TOKEN_USER,
+    };
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    let mut token: HANDLE = ptr::null_mut();
+    // SAFETY: The pseudo-handle of the current process is always valid, and the
+    // token is written to a valid pointer.
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &raw mut token) } == 0 {
+        return false;
+    }
+    // Large enough for any user, and suitably aligned for `TOKEN_USER`.
+    let mut buf = [0usize; 64];
+    let mut len = 0;
+    // SAFETY: The token is valid, and the buffer's length is given.
+    #[allow(clippy::cast_possible_truncation)]
+    let ok = unsafe {
+        GetTokenInformation(
+            token,
+            TokenUser,
+            buf.as_mut_ptr().cast(),
+            size_of_val(&buf) as u32,
+            &raw mut len,
+        )
+    };
+    // SAFETY: The token is owned, and no longer used.
+    unsafe { CloseHandle(token) };
+    if ok == 0 {
+        return false;
+    }
+    // SAFETY: Initialized by the successful call above.
+    let sid = unsafe { (*buf.as_ptr().cast::<TOKEN_USER>()).User.Sid };
+    [WinLocalSystemSid, WinLocalServiceSid, WinNetworkServiceSid]
+        .into_iter()
+        // SAFETY: The SID points into the initialized buffer.
+        .any(|kind| unsafe { IsWellKnownSid(sid, kind) } != 0)
+}
+
+/// Checks whether the process is running as one of Windows' service accounts.
+#[cfg(not(windows))]
+#[cfg(feature = "core")]
+pub(crate) fn is_service() -> bool {
+    false
+}
+
+/// Encodes a string as nul-terminated UTF-16.
+#[cfg(any(
+    feature = "core",
+    feature = "desktop",
+    feature = "install",
+    feature = "mime",
+    feature = "shell",
+    feature = "systemd",
+    feature = "toml"
+))]
+#[cfg(windows)]
+fn wide(str: &std::ffi::OsStr) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+
+    str.encode_wide().chain([0]).collect()
+}
+
+/// Restricts access to a directory or file to its owner only.
+///
+/// The access control list is replaced by a protected one, which directories
+/// pass on to their contents.
+#[cfg(windows)]
+#[cfg(feature = "core")]
+pub(crate) fn restrict(path: &std::path::Path) -> std::io::Result<()> {
+    use std::io;
+    use std::ptr;
+
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::Security::Authorization::{SetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows_sys::Win32::Security::{
+        GetSecurityDescriptorDacl, ACL, DACL_SECURITY_INFORMATION,
+        PROTECTED_DACL_SECURITY_INFORMATION,
+    };
+
+    let name = wide(path.as_os_str());
+    let sddl = if path.is_dir() {
+        "D:P(A;OICI;FA;;;OW)"
+    } else {
+        "D:P(A;;FA;;;OW)"
+    };
+    with_descriptor(sddl, |descriptor| {
+        let mut present = 0;
+        let mut defaulted = 0;
+        let mut dacl: *mut ACL = ptr::null_mut();
+        // SAFETY: The descriptor is valid, and its parts are written to valid
+        // pointers.
+        let ok = unsafe {
+            GetSecurityDescriptorDacl(
+                descriptor,
+                &raw mut present,
+                &raw mut dacl,
+                &raw mut defaulted,
+            )
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: The name is nul-terminated, and the list remains valid for
+        // the duration of the call.
+        let err = unsafe {
+            SetNamedSecurityInfoW(
+                name.as_ptr(),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                dacl,
+                ptr::null(),
+            )
+        };
+        if err == ERROR_SUCCESS {
+            Ok(())
+        } else {
+            #[allow(clippy::cast_possible_wrap)]
+            Err(io::Error::from_raw_os_error(err as i32))
+        }
+    })?
+}
+
+/// Restricts access to a directory or file to its owner only.
+#[cfg(not(any(unix, windows)))]
+#[allow(clippy::unnecessary_wraps)]
+#[cfg(feature = "core")]
+pub(crate) fn restrict(_: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Calls a function with security attributes described by an SDDL string.
+#[cfg(any(
+    feature = "core",
+    feature = "desktop",
+    feature = "install",
+    feature = "mime",
+    feature = "shell",
+    feature = "systemd",
+    feature = "toml"
+))]
+#[cfg(windows)]
+fn with_security<T>(
+    sddl: &str,
+    f: impl FnOnce(*const windows_sys::Win32::Security::SECURITY_ATTRIBUTES) -> T,
+) -> std::io::Result<T> {
+    use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
+
+    with_descriptor(sddl, |descriptor| {
+        #[allow(clippy::cast_possible_truncation)]
+        let attrs = SECURITY_ATTRIBUTES {
+            nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor,
+            bInheritHandle: 0,
+        };
+        f(&raw const attrs)
+    })
+}
+
+/// Calls a function with a security descriptor described by an SDDL string.
+#[cfg(any(
+    feature = "core",
+    feature = "desktop",
+    feature = "install",
+    feature = "mime",
+    feature = "shell",
+    feature = "systemd",
+    feature = "toml"
+))]
+#[cfg(windows)]
+fn with_descriptor<T>(
+    sddl: &str,
+    f: impl FnOnce(windows_sys::Win32::Security::PSECURITY_DESCRIPTOR) -> T,
+) -> std::io::Result<T> {
+    use std::io;
+    use std::ptr;
+
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::PSECURITY_DESCRIPTOR;
+
+    let sddl = sddl.encode_utf16().chain([0]).collect::<Vec<_>>();
+    let mut descriptor: PSECURITY_DESCRIPTOR = ptr::null_mut();
+    // SAFETY: The descriptor string is nul-terminated, and the descriptor is
+    // written to a valid pointer.
+    let ok = unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl.as_ptr(),
+            SDDL_REVISION_1,
+            &raw mut descriptor,
+            ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let out = f(descriptor);
+    // SAFETY: The descriptor was allocated by the conversion above, and is no
+    // longer used.
+    unsafe { LocalFree(descriptor) };
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process::{Command, Stdio};
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// Variable passing the tree to create to child processes.
+    const CHILD: &str = "XDIR_TEST_CREATE_DIR_ALL";
+
+    #[test]
+    fn create_dir_all_races_threads() {
+        let tmp = TempDir::new();
+        let path = tmp.join("a/b/c/d");
+        let barrier = Barrier::new(16);
+        thread::scope(|scope| {
+            for _ in 0..16 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    create_dir_all(&path).unwrap();
+                });
+            }
+        });
+        assert!(path.is_dir());
+    }
+
+    #[test]
+    fn create_dir_all_races_processes() {
+        let tmp = TempDir::new();
+        let path = tmp.join("a/b/c/d");
+        let exe = env::current_exe().unwrap();
+        for _ in 0..4 {
+            let children = (0..8)
+                .map(|_| {
+                    Command::new(&exe)
+                        .args(["--exact", "sys::tests::create_dir_all_child", "--quiet"])
+                        .env(CHILD, &path)
+                        .stdout(Stdio::null())
+                        .spawn()
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            for mut child in children {
+                assert!(child.wait().unwrap().success());
+            }
+            assert!(path.is_dir());
+            std::fs::remove_dir_all(tmp.join("a")).unwrap();
+        }
+    }
+
+    /// Creates the tree given by the parent process, as spawned by
+    /// `create_dir_all_races_processes`.
+    #[test]
+    fn create_dir_all_child() {
+        if let Some(path) = env::var_os(CHILD) {
+            create_dir_all(path.as_ref()).unwrap();
+        }
+    }
+
+    #[test]
+    fn create_dir_all_rejects_files() {
+        let tmp = TempDir::new();
+        std::fs::write(tmp.join("file"), "").unwrap();
+        assert!(create_dir_all(&tmp.join("file")).is_err());
+        assert!(create_dir_all(&tmp.join("file/dir")).is_err());
+    }
+}
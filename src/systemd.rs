@@ -0,0 +1,141 @@
+//! Integration with the systemd user manager.
+//!
+//! Services managed by `systemctl --user` are defined by unit files installed
+//! into the user's [unit directory](crate::config::systemd_user). Their
+//! environment can be configured by snippets installed into the user's
+//! [environment directory](crate::config::environment_d).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! let unit = "\
+//! [Unit]
+//! Description=My App
+//!
+//! [Service]
+//! ExecStart=/usr/bin/myapp serve
+//!
+//! [Install]
+//! WantedBy=default.target
+//! ";
+//! xdir::systemd::install_unit("myapp.service", unit).unwrap();
+//! ```
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::atomic;
+use crate::config::{environment_d, systemd_user};
+
+/// Unit types which may be defined by a unit file.
+const UNIT_TYPES: [&str; 9] = [
+    "automount",
+    "mount",
+    "path",
+    "service",
+    "slice",
+    "socket",
+    "swap",
+    "target",
+    "timer",
+];
+
+/// Installs a unit file into the user's unit directory, returning its path.
+///
+/// The user manager must be reloaded (`systemctl --user daemon-reload`) before
+/// the unit can be used.
+///
+/// # Errors
+///
+/// Errors if the unit name is invalid, if the unit directory could not be
+/// found, or if the file could not be written.
+pub fn install_unit(name: &str, contents: &str) -> io::Result<PathBuf> {
+    validate_unit(name)?;
+    let path = dir()?.join(name);
+    atomic::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Removes a unit file from the user's unit directory.
+///
+/// # Errors
+///
+/// Errors if the unit name is invalid, if the unit directory could not be
+/// found, or if the file could not be removed.
+pub fn uninstall_unit(name: &str) -> io::Result<()> {
+    validate_unit(name)?;
+    crate::readonly::check()?;
+    match fs::remove_file(dir()?.join(name)) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+/// Writes an environment snippet, returning its path.
+///
+/// Variables are written to `<name>.conf` in the user's environment directory
+/// as quoted `KEY="VALUE"` assignments. Values may reference previously
+/// assigned variables using `${KEY}`.
+///
+/// # Errors
+///
+/// Errors if the name, a key, or a value is invalid, if the environment
+/// directory could not be found, or if the file could not be written.
+pub fn write_environment<'a, I>(name: &str, vars: I) -> io::Result<PathBuf>
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(invalid(format!("invalid snippet name: {name:?}")));
+    }
+    let mut contents = String::new();
+    for (key, value) in vars {
+        let valid = key
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+            && key
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+        if !valid {
+            return Err(invalid(format!("invalid variable name: {key:?}")));
+        }
+        if value.contains(['\n', '\r']) {
+            return Err(invalid(format!("invalid value of {key}: {value:?}")));
+        }
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = writeln!(contents, "{key}=\"{value}\"");
+    }
+    let path = environment_d()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find config directory"))?
+        .join(format!("{name}.conf"));
+    atomic::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Checks that a unit name is valid.
+fn validate_unit(name: &str) -> io::Result<()> {
+    let valid = name.rsplit_once('.').is_some_and(|(stem, kind)| {
+        !stem.is_empty()
+            && UNIT_TYPES.contains(&kind)
+            && stem
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ":-_.\\@".contains(ch))
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!("invalid unit name: {name:?}")))
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn dir() -> io::Result<PathBuf> {
+    systemd_user()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find config directory"))
+}
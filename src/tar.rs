@@ -0,0 +1,490 @@
+//! Tape archives.
+//!
+//! This implements the subset of the POSIX `ustar` format needed to archive
+//! regular files and directories. Paths which do not fit within a header, or
+//! are not valid UTF-8, are recorded in extended (i.e. `pax`) headers.
+
+use std::io::{self, Read, Write};
+
+/// Size of a block.
+const BLOCK: usize = 512;
+
+/// Kind of an archive entry.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Kind {
+    /// Regular file.
+    File,
+    /// Directory.
+    Dir,
+    /// Any other kind of entry.
+    Other,
+}
+
+/// Header of an archive entry.
+#[derive(Clone, Debug)]
+pub(crate) struct Header {
+    /// Path of the entry, separated by `/`, which need not be valid UTF-8.
+    pub(crate) path: Vec<u8>,
+    pub(crate) kind: Kind,
+    pub(crate) mode: u32,
+    pub(crate) size: u64,
+    pub(crate) mtime: u64,
+}
+
+/// Writer of an archive.
+pub(crate) struct Writer<W> {
+    inner: W,
+}
+
+impl<W: Write> Writer<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Appends an entry, along with its contents.
+    pub(crate) fn append(&mut self, header: &Header, mut data: impl Read) -> io::Result<()> {
+        let mut path = header.path.clone();
+        if header.kind == Kind::Dir {
+            path.push(b'/');
+        }
+        let utf8 = std::str::from_utf8(&path).is_ok();
+        let (prefix, name) = split(&path).unwrap_or_else(|| (&[][..], truncate(&path, 100)));
+        if !utf8 || name.len() + prefix.len() + usize::from(!prefix.is_empty()) != path.len() {
+            // Paths which do not fit are recorded in an extended header, as
+            // are those which are not valid UTF-8. As with GNU tar, the latter
+            // are recorded as-is.
+            let record = record("path", &path);
+            let pax = Header {
+                path: b"PaxHeader".to_vec(),
+                kind: Kind::Other,
+                mode: 0o644,
+                size: record.len() as u64,
+                mtime: header.mtime,
+            };
+            self.inner.write_all(&encode(&pax, &[], &pax.path, b'x')?)?;
+            self.inner.write_all(&record)?;
+            self.pad(pax.size)?;
+        }
+        let kind = match header.kind {
+            Kind::File | Kind::Other => b'0',
+            Kind::Dir => b'5',
+        };
+        self.inner.write_all(&encode(header, prefix, name, kind)?)?;
+        let copied = io::copy(&mut data.by_ref().take(header.size), &mut self.inner)?;
+        if copied != header.size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "{} changed while being archived",
+                    String::from_utf8_lossy(&header.path)
+                ),
+            ));
+        }
+        self.pad(header.size)
+    }
+
+    /// Finishes the archive, returning the underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[0; 2 * BLOCK])?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn pad(&mut self, size: u64) -> io::Result<()> {
+        #[allow(clippy::cast_possible_truncation)]
+        let rem = (size % BLOCK as u64) as usize;
+        if rem != 0 {
+            self.inner.write_all(&[0; BLOCK][rem..])?;
+        }
+        Ok(())
+    }
+}
+
+/// Reader of an archive.
+pub(crate) struct Reader<R> {
+    inner: R,
+    /// Bytes remaining of the current entry, including padding.
+    remaining: u64,
+}
+
+impl<R: Read> Reader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+        }
+    }
+
+    /// Advances to the next entry, returning its header.
+    ///
+    /// Any unread contents of the previous entry are skipped.
+    pub(crate) fn next_entry(&mut self) -> io::Result<Option<Header>> {
+        let mut path = None;
+        loop {
+            self.skip()?;
+            let mut block = [0; BLOCK];
+            self.inner.read_exact(&mut block)?;
+            if block.iter().all(|&byte| byte == 0) {
+                return Ok(None);
+            }
+            let (mut header, flag) = decode(&block)?;
+            self.remaining = header.size.div_ceil(BLOCK as u64) * BLOCK as u64;
+            match flag {
+                // Extended headers apply to the following entry.
+                b'x' => path = pax_path(&self.contents(&header)?).or(path),
+                // As do GNU long names.
+                b'L' => path = Some(string(&self.contents(&header)?).to_vec()),
+                // Link targets are never restored, so GNU long links are
+                // skipped without affecting the path of the following entry.
+                b'K' => (),
+                _ => {
+                    if let Some(path) = path {
+                        if header.kind == Kind::File && path.ends_with(b"/") {
+                            header.kind = Kind::Dir;
+                        }
+                        header.path = trim(&path).to_vec();
+                    }
+                    return Ok(Some(header));
+                }
+            }
+        }
+    }
+
+    /// Reads the contents of the current entry.
+    pub(crate) fn contents(&mut self, header: &Header) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        (&mut self.inner).take(header.size).read_to_end(&mut data)?;
+        if data.len() as u64 != header.size {
+            return Err(truncated());
+        }
+        self.remaining -= header.size;
+        Ok(data)
+    }
+
+    fn skip(&mut self) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.inner).take(self.remaining), &mut io::sink())?;
+        if skipped != self.remaining {
+            return Err(truncated());
+        }
+        self.remaining = 0;
+        Ok(())
+    }
+}
+
+/// Splits a path across the name and prefix fields of a header, if it fits.
+fn split(path: &[u8]) -> Option<(&[u8], &[u8])> {
+    if path.len() <= 100 {
+        return Some((&[], path));
+    }
+    path.iter()
+        .enumerate()
+        .filter(|&(idx, &byte)| byte == b'/' && idx <= 155 && path.len() - idx - 1 <= 100)
+        .map(|(idx, _)| (&path[..idx], &path[idx + 1..]))
+        .find(|(_, name)| !name.is_empty())
+}
+
+/// Truncates a path to at most `len` bytes, on a character boundary if it is
+/// valid UTF-8.
+fn truncate(path: &[u8], len: usize) -> &[u8] {
+    let end = match std::str::from_utf8(path) {
+        Ok(text) => (0..=len.min(text.len()))
+            .rev()
+            .find(|&idx| text.is_char_boundary(idx))
+            .unwrap_or_default(),
+        Err(_) => len.min(path.len()),
+    };
+    &path[..end]
+}
+
+/// Trims trailing separators from a path.
+fn trim(path: &[u8]) -> &[u8] {
+    let end = path
+        .iter()
+        .rposition(|&byte| byte != b'/')
+        .map_or(0, |idx| idx + 1);
+    &path[..end]
+}
+
+/// Formats a record of an extended header.
+fn record(key: &str, value: &[u8]) -> Vec<u8> {
+    // Each record is prefixed by its length, including that of the prefix.
+    let len = key.len() + value.len() + 3;
+    let mut total = len + len.to_string().len();
+    if total.to_string().len() != len.to_string().len() {
+        total += 1;
+    }
+    let mut record = format!("{total} {key}=").into_bytes();
+    record.extend_from_slice(value);
+    record.push(b'\n');
+    record
+}
+
+/// Encodes the header of an entry.
+fn encode(header: &Header, prefix: &[u8], name: &[u8], kind: u8) -> io::Result<[u8; BLOCK]> {
+    // Sizes and times are limited to 11 octal digits.
+    if header.size >= 1 << 33 || header.mtime >= 1 << 33 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "file too large to archive: {}",
+                String::from_utf8_lossy(&header.path)
+            ),
+        ));
+    }
+    let mut block = [0; BLOCK];
+    block[..name.len()].copy_from_slice(name);
+    octal(&mut block[100..108], u64::from(header.mode & 0o7777));
+    octal(&mut block[108..116], 0);
+    octal(&mut block[116..124], 0);
+    octal(&mut block[124..136], header.size);
+    octal(&mut block[136..148], header.mtime);
+    block[156] = kind;
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[345..345 + prefix.len()].copy_from_slice(prefix);
+    // The checksum is computed with its own field set to spaces.
+    block[148..156].fill(b' ');
+    let sum = block.iter().map(|&byte| u64::from(byte)).sum();
+    octal(&mut block[148..155], sum);
+    Ok(block)
+}
+
+/// Decodes the header of an entry, along with its type flag.
+fn decode(block: &[u8; BLOCK]) -> io::Result<(Header, u8)> {
+    let sum = block
+        .iter()
+        .enumerate()
+        .map(|(idx, &byte)| {
+            if (148..156).contains(&idx) {
+                u64::from(b' ')
+            } else {
+                u64::from(byte)
+            }
+        })
+        .sum::<u64>();
+    if parse(&block[148..156])? != sum {
+        return Err(invalid("invalid archive checksum"));
+    }
+    let name = string(&block[..100]);
+    let prefix = if &block[257..262] == b"ustar" {
+        string(&block[345..500])
+    } else {
+        &[]
+    };
+    let path = if prefix.is_empty() {
+        name.to_vec()
+    } else {
+        [prefix, b"/", name].concat()
+    };
+    let kind = match block[156] {
+        b'0' | b'\0' if !path.ends_with(b"/") => Kind::File,
+        b'0' | b'\0' | b'5' => Kind::Dir,
+        _ => Kind::Other,
+    };
+    let header = Header {
+        path: trim(&path).to_vec(),
+        kind,
+        mode: u32::try_from(parse(&block[100..108])?).map_err(|_| invalid("invalid entry mode"))?,
+        size: if kind == Kind::Dir {
+            0
+        } else {
+            parse(&block[124..136])?
+        },
+        mtime: parse(&block[136..148])?,
+    };
+    Ok((header, block[156]))
+}
+
+/// Parses the path from the records of an extended header.
+///
+/// Paths are taken as raw bytes, such that those which are not valid UTF-8
+/// (as written by GNU tar, or marked as binary) are preserved.
+fn pax_path(data: &[u8]) -> Option<Vec<u8>> {
+    let mut rest = data;
+    let mut path = None;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&byte| byte == b' ')?;
+        let len = std::str::from_utf8(&rest[..space])
+            .ok()?
+            .parse::<usize>()
+            .ok()?;
+        let record = rest.get(space + 1..len)?.strip_suffix(b"\n")?;
+        if let Some(value) = record.strip_prefix(b"path=") {
+            path = Some(value.to_vec());
+        }
+        rest = &rest[len..];
+    }
+    path
+}
+
+/// Writes a NUL-terminated octal number into a field.
+///
+/// The value must fit within the field.
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+/// Parses an octal number from a field.
+fn parse(field: &[u8]) -> io::Result<u64> {
+    let text = std::str::from_utf8(field).map_err(|_| invalid("invalid archive header"))?;
+    let text = text.trim_matches(|ch: char| ch == '\0' || ch == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid("invalid archive header"))
+}
+
+/// Parses a NUL-terminated string from a field.
+fn string(field: &[u8]) -> &[u8] {
+    let end = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    &field[..end]
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "truncated archive")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(path: &[u8], kind: Kind, size: u64) -> Header {
+        Header {
+            path: path.to_vec(),
+            kind,
+            mode: 0o644,
+            size,
+            mtime: 1_700_000_000,
+        }
+    }
+
+    /// Writes an archive of files (or directories, without contents).
+    fn archive(entries: &[(&[u8], Option<&[u8]>)]) -> Vec<u8> {
+        let mut writer = Writer::new(Vec::new());
+        for &(path, data) in entries {
+            match data {
+                Some(data) => writer
+                    .append(&header(path, Kind::File, data.len() as u64), data)
+                    .unwrap(),
+                None => writer
+                    .append(&header(path, Kind::Dir, 0), io::empty())
+                    .unwrap(),
+            }
+        }
+        writer.finish().unwrap()
+    }
+
+    /// Reads the entries of an archive, along with the contents of files.
+    fn entries(data: &[u8]) -> Vec<(Vec<u8>, Kind, Vec<u8>)> {
+        let mut reader = Reader::new(data);
+        let mut entries = Vec::new();
+        while let Some(header) = reader.next_entry().unwrap() {
+            let contents = reader.contents(&header).unwrap();
+            entries.push((header.path, header.kind, contents));
+        }
+        entries
+    }
+
+    #[test]
+    fn round_trips_paths() {
+        let long = [b"dir/".repeat(30), b"file.txt".to_vec()].concat();
+        let split = [b"a".repeat(120), b"/".to_vec(), b"b".repeat(90)].concat();
+        let unsplit = b"c".repeat(200);
+        let binary = b"dir/caf\xe9.txt".to_vec();
+        let data = archive(&[
+            (b"short.txt", Some(b"short")),
+            (&long, Some(b"long")),
+            (&split, Some(b"split")),
+            (&unsplit, Some(b"unsplit")),
+            (&binary, Some(b"binary")),
+            (b"empty", None),
+            (&b"d".repeat(150), None),
+        ]);
+        assert_eq!(
+            entries(&data),
+            [
+                (b"short.txt".to_vec(), Kind::File, b"short".to_vec()),
+                (long, Kind::File, b"long".to_vec()),
+                (split, Kind::File, b"split".to_vec()),
+                (unsplit, Kind::File, b"unsplit".to_vec()),
+                (binary, Kind::File, b"binary".to_vec()),
+                (b"empty".to_vec(), Kind::Dir, Vec::new()),
+                (b"d".repeat(150), Kind::Dir, Vec::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_binary_paths() {
+        let data = archive(&[(b"caf\xe9", Some(b""))]);
+        assert_eq!(data[156], b'x');
+        let record = record("path", b"caf\xe9");
+        assert_eq!(&data[BLOCK..BLOCK + record.len()], record);
+    }
+
+    #[test]
+    fn records_lengths() {
+        assert_eq!(record("path", b"abc"), b"12 path=abc\n");
+        // Lengths which gain a digit by including themselves.
+        for len in 0..1000 {
+            let record = record("path", &vec![b'x'; len]);
+            let text = std::str::from_utf8(&record).unwrap();
+            let prefix = text.split(' ').next().unwrap();
+            assert_eq!(prefix.parse::<usize>().unwrap(), record.len());
+        }
+    }
+
+    #[test]
+    fn skips_long_links() {
+        let target = b"t".repeat(300);
+        let mut data = Vec::new();
+        // A symbolic link, whose target is recorded in an extended header.
+        let records = record("linkpath", &target);
+        let pax = header(b"PaxHeader", Kind::Other, records.len() as u64);
+        data.extend(encode(&pax, &[], &pax.path, b'x').unwrap());
+        data.extend(&records);
+        data.resize(data.len().div_ceil(BLOCK) * BLOCK, 0);
+        data.extend(encode(&header(b"pax-link", Kind::Other, 0), &[], b"pax-link", b'2').unwrap());
+        // As well as a GNU long link, preceded by a GNU long name.
+        let name = b"n".repeat(150);
+        for (flag, value) in [(b'L', &name), (b'K', &target)] {
+            let long = header(b"././@LongLink", Kind::Other, value.len() as u64 + 1);
+            data.extend(encode(&long, &[], &long.path, flag).unwrap());
+            data.extend(value.iter());
+            data.push(0);
+            data.resize(data.len().div_ceil(BLOCK) * BLOCK, 0);
+        }
+        data.extend(encode(&header(b"gnu-link", Kind::Other, 0), &[], b"gnu-link", b'2').unwrap());
+        data.extend(archive(&[(b"file", Some(b"data"))]));
+        assert_eq!(
+            entries(&data),
+            [
+                (b"pax-link".to_vec(), Kind::Other, Vec::new()),
+                (name, Kind::Other, Vec::new()),
+                (b"file".to_vec(), Kind::File, b"data".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_headers() {
+        let mut data = archive(&[(b"file", Some(b"data"))]);
+        data[0] ^= 1;
+        let err = Reader::new(&data[..]).next_entry().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let data = archive(&[(b"file", Some(b"data"))]);
+        let mut reader = Reader::new(&data[..BLOCK + 2]);
+        let header = reader.next_entry().unwrap().unwrap();
+        let err = reader.contents(&header).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,40 @@
+//! Emission of metrics.
+//!
+//! Without the `metrics` feature, these do nothing.
+
+#[cfg(feature = "core")]
+use crate::Dir;
+
+/// Records a run of pruning an application's cache.
+#[cfg(all(feature = "cache-gc", feature = "metrics"))]
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn pruned(app: &str, removed: usize, size: u64) {
+    let app = app.to_string();
+    metrics::counter!("xdir_cache_prune_runs_total", "app" => app.clone()).increment(1);
+    metrics::counter!("xdir_cache_pruned_files_total", "app" => app.clone())
+        .increment(removed as u64);
+    metrics::gauge!("xdir_cache_size_bytes", "app" => app).set(size as f64);
+}
+
+#[cfg(all(feature = "cache-gc", not(feature = "metrics")))]
+pub(crate) fn pruned(_: &str, _: usize, _: u64) {}
+
+/// Records the rejection of a variable's value, in favor of its default.
+#[cfg(feature = "metrics")]
+pub(crate) fn rejected(var: &'static str) {
+    metrics::counter!("xdir_fallbacks_total", "var" => var).increment(1);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn rejected(_: &'static str) {}
+
+/// Records a failure to create a directory.
+#[cfg(feature = "metrics")]
+#[cfg(feature = "core")]
+pub(crate) fn create_failed(dir: Dir) {
+    metrics::counter!("xdir_create_failures_total", "dir" => dir.to_string()).increment(1);
+}
+
+#[cfg(not(feature = "metrics"))]
+#[cfg(feature = "core")]
+pub(crate) fn create_failed(_: Dir) {}
@@ -0,0 +1,188 @@
+//! Path templates.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::{resolve, Dir};
+
+/// A path template, resolving the standard directories within it.
+///
+/// Templates allow users to configure the locations of files within their
+/// configuration, without hard-coding the locations of their directories.
+/// Variables are enclosed in braces (e.g. `{state}`), and are replaced by:
+///
+/// - `{home}`: the user's home directory.
+/// - `{bin}`, `{cache}`, `{config}`, `{data}`, `{runtime}` and `{state}`: the
+///   user's directory of each kind.
+/// - Any variable defined with [`var`](Self::var), which takes precedence over
+///   the above.
+///
+/// Literal braces are written as `{{` and `}}`. Path separators may be written
+/// as `/` on every platform.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::PathTemplate;
+///
+/// let template = PathTemplate::parse("{state}/myapp/{profile}/history.db")
+///     .unwrap()
+///     .var("profile", "work");
+/// # if xdir::state().is_some() {
+/// let path = template.resolve().unwrap();
+/// assert!(path.ends_with("myapp/work/history.db"));
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PathTemplate {
+    parts: Vec<Part>,
+    vars: BTreeMap<String, OsString>,
+}
+
+/// A part of a template.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Part {
+    Text(String),
+    Var(String),
+}
+
+impl PathTemplate {
+    /// Parses a template.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a brace is unmatched, or if a variable is unnamed.
+    pub fn parse(template: &str) -> io::Result<Self> {
+        let invalid = |why: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid path template {template:?}: {why}"),
+            )
+        };
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(char) = chars.next() {
+            match char {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err(invalid("unmatched `{`")),
+                            Some(char) => name.push(char),
+                        }
+                    }
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(invalid("unnamed variable"));
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Var(name.to_string()));
+                }
+                '}' => return Err(invalid("unmatched `}`")),
+                char => text.push(char),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self {
+            parts,
+            vars: BTreeMap::new(),
+        })
+    }
+
+    /// Defines a variable of the template.
+    #[must_use]
+    pub fn var(mut self, name: impl Into<String>, value: impl Into<OsString>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the names of the variables used within the template, in order of
+    /// appearance.
+    pub fn vars(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Var(name) => Some(name.as_str()),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// Resolves the template into a path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a variable is unknown, or if its directory could not be found.
+    pub fn resolve(&self) -> io::Result<PathBuf> {
+        let mut path = OsString::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => path.push(text),
+                Part::Var(name) => path.push(self.lookup(name)?),
+            }
+        }
+        Ok(resolve::native(PathBuf::from(path)))
+    }
+
+    /// Looks up the value of a variable.
+    fn lookup(&self, name: &str) -> io::Result<OsString> {
+        if let Some(value) = self.vars.get(name) {
+            return Ok(value.clone());
+        }
+        let path = if name == "home" {
+            crate::home()
+        } else {
+            let dir = Dir::ALL
+                .into_iter()
+                .find(|dir| dir.to_string() == name)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("unknown template variable: {{{name}}}"),
+                    )
+                })?;
+            dir.path()
+        };
+        path.map(PathBuf::into_os_string).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("could not find {name} directory"),
+            )
+        })
+    }
+}
+
+impl Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => write!(f, "{}", text.replace('{', "{{").replace('}', "}}"))?,
+                Part::Var(name) => write!(f, "{{{name}}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = io::Error;
+
+    fn from_str(template: &str) -> io::Result<Self> {
+        Self::parse(template)
+    }
+}
@@ -0,0 +1,138 @@
+//! Thumbnail cache.
+//!
+//! Thumbnails of files are shared between applications through the user's
+//! cache, as per the [Thumbnail Managing Standard][spec]. Each thumbnail is a
+//! PNG image named after the MD5 digest of its file's URI, stored within the
+//! directory of its [size](Size).
+//!
+//! # Examples
+//!
+//! To look up an existing thumbnail of a file:
+//!
+//! ```
+//! use xdir::thumbnail::{self, Size};
+//!
+//! let file = std::env::current_dir().unwrap().join("image.png");
+//! let thumb = thumbnail::path(Size::Large, &file).filter(|path| path.exists());
+//! ```
+//!
+//! [spec]: https://specifications.freedesktop.org/thumbnail-spec/latest/
+
+use std::path::{Path, PathBuf};
+
+use crate::{cache, md5, uri};
+
+/// Size of a thumbnail.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Size {
+    /// Up to 128x128 pixels.
+    Normal,
+    /// Up to 256x256 pixels.
+    Large,
+    /// Up to 512x512 pixels.
+    XLarge,
+    /// Up to 1024x1024 pixels.
+    XXLarge,
+}
+
+impl Size {
+    /// All thumbnail sizes, from smallest to largest.
+    pub const ALL: [Self; 4] = [Self::Normal, Self::Large, Self::XLarge, Self::XXLarge];
+
+    /// Returns the maximum width and height of the size, in pixels.
+    #[must_use]
+    pub fn pixels(self) -> u32 {
+        match self {
+            Self::Normal => 128,
+            Self::Large => 256,
+            Self::XLarge => 512,
+            Self::XXLarge => 1024,
+        }
+    }
+
+    /// Returns the name of the size's directory.
+    #[must_use]
+    pub fn dir(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Large => "large",
+            Self::XLarge => "x-large",
+            Self::XXLarge => "xx-large",
+        }
+    }
+}
+
+/// Returns the path to the user's thumbnail directory.
+#[must_use]
+pub fn thumbnails() -> Option<PathBuf> {
+    cache().map(|path| path.join("thumbnails"))
+}
+
+/// Returns the path to the thumbnail directory of a size.
+#[must_use]
+pub fn dir(size: Size) -> Option<PathBuf> {
+    thumbnails().map(|path| path.join(size.dir()))
+}
+
+/// Returns the path to an application's directory of failed thumbnails.
+///
+/// Applications record files which they failed to thumbnail here, so as not to
+/// retry them. The name should include the application's version (e.g.
+/// `myapp-1.0`).
+#[must_use]
+pub fn failed(app: &str) -> Option<PathBuf> {
+    thumbnails().map(|path| path.join("fail").join(app))
+}
+
+/// Returns the path of a file's thumbnail of a size.
+///
+/// The file's path must be absolute; [`None`] is returned otherwise.
+#[must_use]
+pub fn path(size: Size, file: &Path) -> Option<PathBuf> {
+    file.is_absolute()
+        .then(|| dir(size))
+        .flatten()
+        .map(|path| path.join(name(&uri::file(file))))
+}
+
+/// Returns the canonical URI of a file, which identifies its thumbnail.
+///
+/// The path is percent-encoded as by `g_filename_to_uri`, such that
+/// thumbnails are shared with desktop environments. The file's path must be
+/// absolute.
+#[must_use]
+pub fn file_uri(file: &Path) -> String {
+    uri::file(file)
+}
+
+/// Returns the name of the thumbnail for a URI.
+///
+/// This is the hex-encoded MD5 digest of the URI, with a `.png` extension.
+#[must_use]
+pub fn name(uri: &str) -> String {
+    let mut name = md5::hex(uri.as_bytes());
+    name.push_str(".png");
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn name_matches_spec() {
+        // Example of the thumbnail managing standard.
+        let uri = file_uri(Path::new("/home/jens/photos/me.png"));
+        assert_eq!(uri, "file:///home/jens/photos/me.png");
+        assert_eq!(name(&uri), "c6ee772d9e49320e97ec29a7eb5b1697.png");
+        // Reserved characters are left as-is by desktop environments.
+        let uri = file_uri(Path::new("/home/u/a (1)+b,c.png"));
+        assert_eq!(name(&uri), "1fc73efbac8348938f2f492ba90b55af.png");
+    }
+
+    #[test]
+    fn path_requires_absolute() {
+        assert_eq!(path(Size::Normal, Path::new("relative.png")), None);
+    }
+}
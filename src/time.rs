@@ -0,0 +1,102 @@
+//! Calendar time.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A UTC date and time, with one-second precision.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    /// Converts a system time to its UTC date and time.
+    pub fn utc(time: SystemTime) -> Self {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX),
+            Err(err) => -i64::try_from(err.duration().as_secs()).unwrap_or(i64::MAX),
+        };
+        Self::from_unix(secs)
+    }
+
+    /// Converts a system time to its local date and time.
+    ///
+    /// Falls back to UTC on platforms where the local timezone is unavailable.
+    #[cfg(feature = "trash")]
+    pub fn local(time: SystemTime) -> Self {
+        #[cfg(unix)]
+        {
+            let utc = Self::utc(time);
+            let secs = match time.duration_since(UNIX_EPOCH) {
+                Ok(elapsed) => elapsed.as_secs(),
+                Err(_) => return utc,
+            };
+            let Ok(secs) = libc::time_t::try_from(secs) else {
+                return utc;
+            };
+            // SAFETY: `localtime_r` only writes to the provided `tm`, which is
+            // valid for writes and initialized on success.
+            let tm = unsafe {
+                let mut tm = std::mem::zeroed::<libc::tm>();
+                if libc::localtime_r(&raw const secs, &raw mut tm).is_null() {
+                    return utc;
+                }
+                tm
+            };
+            // The width of these types varies by platform.
+            #[allow(clippy::useless_conversion)]
+            Self::from_unix(i64::from(secs) + i64::from(tm.tm_gmtoff))
+        }
+        #[cfg(not(unix))]
+        Self::utc(time)
+    }
+
+    /// Converts seconds since the Unix epoch to a date and time.
+    ///
+    /// Uses the days-to-civil algorithm described by Howard Hinnant.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn from_unix(secs: i64) -> Self {
+        let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Self {
+            year,
+            month,
+            day,
+            hour: (rem / 3600) as u32,
+            minute: (rem % 3600 / 60) as u32,
+            second: (rem % 60) as u32,
+        }
+    }
+
+    /// Formats the date and time in ISO 8601 basic format (e.g.
+    /// `20240421T135900Z`).
+    #[cfg(any(feature = "io-helpers", feature = "journal"))]
+    pub fn basic(&self) -> String {
+        format!(
+            "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+
+    /// Formats the date and time in ISO 8601 extended format, without a
+    /// timezone (e.g. `2024-04-21T13:59:00`).
+    #[cfg(any(feature = "record", feature = "trash"))]
+    pub fn extended(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
@@ -0,0 +1,69 @@
+//! Moving of files across filesystems.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+/// Moves a file or directory, falling back to a copy if it crosses devices.
+///
+/// When copying, permissions and modification times are preserved, and the
+/// source is only removed once it has been copied in full.
+pub(crate) fn move_path(src: &Path, dst: &Path) -> io::Result<()> {
+    crate::readonly::check()?;
+    match fs::rename(src, dst) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(err) = copy_all(src, dst) {
+                let _ = remove_all(dst);
+                return Err(err);
+            }
+            remove_all(src)
+        }
+        res => res,
+    }
+}
+
+/// Recursively copies a file or directory.
+fn copy_all(src: &Path, dst: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(src)?;
+    if meta.is_symlink() {
+        let target = fs::read_link(src)?;
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(target, dst);
+        #[cfg(windows)]
+        return if fs::metadata(src)?.is_dir() {
+            std::os::windows::fs::symlink_dir(target, dst)
+        } else {
+            std::os::windows::fs::symlink_file(target, dst)
+        };
+        #[cfg(not(any(unix, windows)))]
+        return fs::copy(src, dst).map(drop);
+    }
+    if meta.is_dir() {
+        fs::create_dir(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        fs::set_permissions(dst, meta.permissions())?;
+    } else {
+        fs::copy(src, dst)?;
+    }
+    if let Ok(mtime) = meta.modified() {
+        File::options()
+            .write(!meta.is_dir())
+            .read(meta.is_dir())
+            .open(dst)
+            .and_then(|file| file.set_modified(mtime))
+            .ok();
+    }
+    Ok(())
+}
+
+/// Removes a file or directory.
+fn remove_all(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -0,0 +1,228 @@
+//! Trash can.
+//!
+//! This module implements the user's home trash as per the [FreeDesktop.org
+//! Trash Specification][spec]. Trashed files are moved into `files/` within
+//! the [trash directory](dir), alongside a corresponding `.trashinfo` file in
+//! `info/` that records their original location and time of deletion.
+//!
+//! Files on other filesystems are moved into the home trash by copying, rather
+//! than into a per-filesystem trash directory.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::trash;
+//!
+//! trash::trash_file("notes.txt").unwrap();
+//! for entry in trash::list().unwrap() {
+//!     if entry.original().ends_with("notes.txt") {
+//!         trash::restore(&entry).unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! [spec]: https://specifications.freedesktop.org/trash-spec/latest/
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::data;
+use crate::time::DateTime;
+use crate::transfer::move_path;
+use crate::uri;
+
+/// Extension of trash info files.
+const INFO: &str = "trashinfo";
+
+/// Returns the path to the user's home trash directory.
+#[must_use]
+pub fn dir() -> Option<PathBuf> {
+    data().map(|path| path.join("Trash"))
+}
+
+/// A file within the trash.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    name: OsString,
+    path: PathBuf,
+    original: PathBuf,
+    deleted: String,
+}
+
+impl Entry {
+    /// Returns the path of the trashed file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the original path of the trashed file.
+    #[must_use]
+    pub fn original(&self) -> &Path {
+        &self.original
+    }
+
+    /// Returns the local time at which the file was trashed, formatted as
+    /// `YYYY-MM-DDThh:mm:ss`.
+    #[must_use]
+    pub fn deleted(&self) -> &str {
+        &self.deleted
+    }
+}
+
+/// Moves a file or directory into the trash, returning its trash entry.
+///
+/// # Errors
+///
+/// Errors if the path does not exist, if the trash directory could not be
+/// found or created, or if the file could not be moved.
+pub fn trash_file(path: impl AsRef<Path>) -> io::Result<Entry> {
+    let original = std::path::absolute(path)?;
+    fs::symlink_metadata(&original)?;
+    crate::readonly::check()?;
+    let trash = trash()?;
+    let (files, info) = (trash.join("files"), trash.join("info"));
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let base = original
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot trash root"))?;
+    let deleted = DateTime::local(SystemTime::now()).extended();
+    // Reserve a unique name by atomically creating its info file.
+    let mut idx = 1;
+    let (name, mut file, info) = loop {
+        let mut name = base.to_os_string();
+        if idx > 1 {
+            name.push(format!(".{idx}"));
+        }
+        let mut path = info.join(&name).into_os_string();
+        path.push(format!(".{INFO}"));
+        let path = PathBuf::from(path);
+        match File::create_new(&path) {
+            Ok(file) => break (name, file, path),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => idx += 1,
+            Err(err) => return Err(err),
+        }
+    };
+    let path = files.join(&name);
+    let result = write!(
+        file,
+        "[Trash Info]\nPath={}\nDeletionDate={deleted}\n",
+        uri::encode(&original)
+    )
+    .and_then(|()| file.sync_all())
+    .and_then(|()| move_path(&original, &path));
+    if let Err(err) = result {
+        let _ = fs::remove_file(&info);
+        return Err(err);
+    }
+    Ok(Entry {
+        name,
+        path,
+        original,
+        deleted,
+    })
+}
+
+/// Lists the entries of the trash.
+///
+/// Files without a valid info file are skipped.
+///
+/// # Errors
+///
+/// Errors if the trash directory could not be found or read.
+pub fn list() -> io::Result<Vec<Entry>> {
+    let trash = trash()?;
+    let entries = match fs::read_dir(trash.join("info")) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut list = Vec::new();
+    for entry in entries {
+        let info = entry?.path();
+        if info.extension().is_none_or(|ext| ext != INFO) {
+            continue;
+        }
+        let Some(name) = info.file_stem().map(ToOwned::to_owned) else {
+            continue;
+        };
+        let Ok(contents) = fs::read_to_string(&info) else {
+            continue;
+        };
+        let mut original = None;
+        let mut deleted = String::new();
+        for line in contents.lines() {
+            if let Some(path) = line.strip_prefix("Path=") {
+                original = uri::decode(path).filter(|path| path.is_absolute());
+            } else if let Some(date) = line.strip_prefix("DeletionDate=") {
+                deleted = date.to_string();
+            }
+        }
+        let path = trash.join("files").join(&name);
+        if let Some(original) = original {
+            if fs::symlink_metadata(&path).is_ok() {
+                list.push(Entry {
+                    name,
+                    path,
+                    original,
+                    deleted,
+                });
+            }
+        }
+    }
+    list.sort_by(|a, b| a.deleted.cmp(&b.deleted));
+    Ok(list)
+}
+
+/// Restores an entry of the trash to its original location, returning its
+/// path.
+///
+/// # Errors
+///
+/// Errors if a file already exists at the original location, or if the entry
+/// could not be moved.
+pub fn restore(entry: &Entry) -> io::Result<PathBuf> {
+    if fs::symlink_metadata(&entry.original).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", entry.original.display()),
+        ));
+    }
+    crate::readonly::check()?;
+    if let Some(parent) = entry.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_path(&entry.path, &entry.original)?;
+    remove_info(entry)?;
+    Ok(entry.original.clone())
+}
+
+/// Permanently deletes an entry of the trash.
+///
+/// # Errors
+///
+/// Errors if the entry could not be removed.
+pub fn remove(entry: &Entry) -> io::Result<()> {
+    crate::readonly::check()?;
+    if fs::symlink_metadata(&entry.path)?.is_dir() {
+        fs::remove_dir_all(&entry.path)?;
+    } else {
+        fs::remove_file(&entry.path)?;
+    }
+    remove_info(entry)
+}
+
+fn remove_info(entry: &Entry) -> io::Result<()> {
+    let mut name = entry.name.clone();
+    name.push(format!(".{INFO}"));
+    fs::remove_file(trash()?.join("info").join(name))
+}
+
+fn trash() -> io::Result<PathBuf> {
+    dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find data directory"))
+}
@@ -0,0 +1,189 @@
+//! Percent-encoding of paths.
+
+use std::fmt::Write as _;
+use std::path::Path;
+#[cfg(any(feature = "journal", feature = "trash"))]
+use std::path::PathBuf;
+
+/// Characters left as-is within the path of a `file://` URI, besides
+/// alphanumerics.
+///
+/// These match those of `g_filename_to_uri`, such that URIs (and the
+/// names derived from them) agree with those of desktop environments.
+#[cfg(feature = "thumbnail")]
+const FILE: &[u8] = b"/-_.~!$&'()*+,:=@";
+
+/// Percent-encodes a path, leaving unreserved characters and separators as-is.
+#[cfg(any(feature = "journal", feature = "trash"))]
+pub(crate) fn encode(path: &Path) -> String {
+    escape(path, b"/-_.~")
+}
+
+/// Percent-encodes a path, leaving alphanumerics and the given characters
+/// as-is.
+fn escape(path: &Path, allowed: &[u8]) -> String {
+    bytes(path).iter().fold(String::new(), |mut out, &byte| {
+        if byte.is_ascii_alphanumeric() || allowed.contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+        out
+    })
+}
+
+/// Decodes a percent-encoded path.
+#[cfg(any(feature = "journal", feature = "trash"))]
+pub(crate) fn decode(path: &str) -> Option<PathBuf> {
+    let mut out = Vec::with_capacity(path.len());
+    let mut bytes = path.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            out.push(byte);
+        }
+    }
+    #[cfg(unix)]
+    return Some(PathBuf::from(
+        <std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(out),
+    ));
+    #[cfg(windows)]
+    return wtf8::decode(&out).map(|wide| {
+        PathBuf::from(<std::ffi::OsString as std::os::windows::ffi::OsStringExt>::from_wide(&wide))
+    });
+    #[cfg(not(any(unix, windows)))]
+    return String::from_utf8(out).ok().map(PathBuf::from);
+}
+
+/// Returns the `file://` URI of an absolute path.
+#[cfg(feature = "thumbnail")]
+pub(crate) fn file(path: &Path) -> String {
+    let path = escape(path, FILE);
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else {
+        format!("file:///{path}")
+    }
+}
+
+fn bytes(path: &Path) -> Vec<u8> {
+    #[cfg(unix)]
+    return std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(windows)]
+    return wtf8::encode(std::os::windows::ffi::OsStrExt::encode_wide(
+        path.as_os_str(),
+    ))
+    .into_iter()
+    .map(|byte| if byte == b'\\' { b'/' } else { byte })
+    .collect();
+    #[cfg(not(any(unix, windows)))]
+    return path.to_string_lossy().replace('\\', "/").into_bytes();
+}
+
+/// [WTF-8] encoding of Windows paths, which may contain unpaired surrogates.
+///
+/// This is UTF-8, except that unpaired surrogates are encoded as if they were
+/// code points, such that every path round-trips losslessly.
+///
+/// [WTF-8]: https://simonsapin.github.io/wtf-8/
+#[cfg(windows)]
+mod wtf8 {
+    /// Encodes UTF-16 as WTF-8.
+    pub(super) fn encode(wide: impl Iterator<Item = u16>) -> Vec<u8> {
+        let mut out = Vec::new();
+        for char in char::decode_utf16(wide) {
+            let code = char.map_or_else(|err| u32::from(err.unpaired_surrogate()), u32::from);
+            let mut buf = [0; 4];
+            let len = match code {
+                0..=0x7F => 1,
+                0x80..=0x7FF => 2,
+                0x800..=0xFFFF => 3,
+                _ => 4,
+            };
+            let lead = [0, 0, 0xC0, 0xE0, 0xF0][len];
+            for (idx, byte) in buf[..len].iter_mut().enumerate() {
+                let shift = 6 * (len - 1 - idx);
+                #[allow(clippy::cast_possible_truncation)]
+                let bits = (code >> shift) as u8;
+                *byte = if idx == 0 {
+                    lead | bits
+                } else {
+                    0x80 | (bits & 0x3F)
+                };
+            }
+            out.extend_from_slice(&buf[..len]);
+        }
+        out
+    }
+
+    /// Decodes WTF-8 as UTF-16, if valid.
+    #[cfg(any(feature = "journal", feature = "trash"))]
+    pub(super) fn decode(bytes: &[u8]) -> Option<Vec<u16>> {
+        let mut out = Vec::new();
+        let mut bytes = bytes.iter().copied();
+        while let Some(lead) = bytes.next() {
+            let (len, mut code) = match lead {
+                0x00..=0x7F => (1, u32::from(lead)),
+                0xC0..=0xDF => (2, u32::from(lead & 0x1F)),
+                0xE0..=0xEF => (3, u32::from(lead & 0x0F)),
+                0xF0..=0xF7 => (4, u32::from(lead & 0x07)),
+                _ => return None,
+            };
+            for _ in 1..len {
+                let byte = bytes.next().filter(|byte| byte & 0xC0 == 0x80)?;
+                code = (code << 6) | u32::from(byte & 0x3F);
+            }
+            if code < 0x10000 {
+                #[allow(clippy::cast_possible_truncation)]
+                out.push(code as u16);
+            } else {
+                let mut buf = [0; 2];
+                out.extend_from_slice(char::from_u32(code)?.encode_utf16(&mut buf));
+            }
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "journal", feature = "trash"))]
+    #[test]
+    fn encode_round_trips() {
+        for path in ["/home/u/a b/c%d", "/x/é\u{1}", "/a (1)+b,c;d=e"] {
+            let encoded = encode(Path::new(path));
+            assert!(!encoded.contains([' ', '(', ';']));
+            assert_eq!(decode(&encoded).unwrap(), Path::new(path));
+        }
+        assert_eq!(decode("%2"), None);
+        assert_eq!(decode("%zz"), None);
+    }
+
+    #[cfg(feature = "thumbnail")]
+    #[test]
+    fn file_matches_glib() {
+        // As produced by `g_filename_to_uri`.
+        assert_eq!(
+            file(Path::new("/home/u/a (1)+b,c.png")),
+            "file:///home/u/a%20(1)+b,c.png"
+        );
+        // Backslashes are only literal on Unix.
+        #[cfg(unix)]
+        {
+            let ascii = (32..127u8)
+                .filter(|&byte| byte != b'/')
+                .map(char::from)
+                .collect::<String>();
+            assert_eq!(
+                file(&Path::new("/x").join(ascii)),
+                "file:///x/%20!%22%23$%25&'()*+,-.0123456789:%3B%3C=%3E%3F@\
+                 ABCDEFGHIJKLMNOPQRSTUVWXYZ%5B%5C%5D%5E_%60abcdefghijklmnopqrstuvwxyz%7B%7C%7D~"
+            );
+        }
+        assert_eq!(file(Path::new("/x/é\u{1}")), "file:///x/%C3%A9%01");
+    }
+}
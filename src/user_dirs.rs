@@ -0,0 +1,168 @@
+//! Well-known user directories.
+//!
+//! Directories of the user's own files (e.g. their documents or music) are
+//! localized, and so cannot be assumed. Instead, their locations are read from
+//! `user-dirs.dirs` within the config directory, as maintained by
+//! [`xdg-user-dirs`][user-dirs].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::user_dirs::UserDir;
+//!
+//! if let Some(path) = UserDir::Download.path() {
+//!     println!("downloads are saved to {}", path.display());
+//! }
+//! ```
+//!
+//! [user-dirs]: https://www.freedesktop.org/wiki/Software/xdg-user-dirs/
+
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{config, home};
+
+/// Kinds of well-known user directories.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum UserDir {
+    /// User's desktop directory.
+    Desktop,
+    /// User's documents directory.
+    Documents,
+    /// User's downloads directory.
+    Download,
+    /// User's music directory.
+    Music,
+    /// User's pictures directory.
+    Pictures,
+    /// User's public share directory.
+    PublicShare,
+    /// User's templates directory.
+    Templates,
+    /// User's videos directory.
+    Videos,
+}
+
+impl UserDir {
+    /// All kinds of user directories.
+    pub const ALL: [Self; 8] = [
+        Self::Desktop,
+        Self::Documents,
+        Self::Download,
+        Self::Music,
+        Self::Pictures,
+        Self::PublicShare,
+        Self::Templates,
+        Self::Videos,
+    ];
+
+    /// Returns the path to the directory.
+    ///
+    /// Returns [`None`] if the directory is not configured, or is disabled
+    /// (i.e. set to the home directory itself). The desktop directory defaults
+    /// to `$HOME/Desktop`, as per `xdg-user-dirs`.
+    #[must_use]
+    pub fn path(self) -> Option<PathBuf> {
+        let home = home()?;
+        let dirs = config()
+            .and_then(|path| fs::read_to_string(path.join("user-dirs.dirs")).ok())
+            .unwrap_or_default();
+        self.parse(&dirs, &home)
+    }
+
+    /// Returns the variable used to configure the directory.
+    #[must_use]
+    pub fn var(self) -> &'static str {
+        match self {
+            Self::Desktop => "XDG_DESKTOP_DIR",
+            Self::Documents => "XDG_DOCUMENTS_DIR",
+            Self::Download => "XDG_DOWNLOAD_DIR",
+            Self::Music => "XDG_MUSIC_DIR",
+            Self::Pictures => "XDG_PICTURES_DIR",
+            Self::PublicShare => "XDG_PUBLICSHARE_DIR",
+            Self::Templates => "XDG_TEMPLATES_DIR",
+            Self::Videos => "XDG_VIDEOS_DIR",
+        }
+    }
+
+    /// Finds the directory within the contents of `user-dirs.dirs`.
+    ///
+    /// Values are quoted, and are either absolute or relative to `$HOME`. Later
+    /// assignments replace earlier ones.
+    fn parse(self, dirs: &str, home: &Path) -> Option<PathBuf> {
+        let value = dirs
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .filter(|(var, _)| var.trim() == self.var())
+            .filter_map(|(_, value)| value.trim().strip_prefix('"')?.strip_suffix('"'))
+            .next_back();
+        let Some(value) = value else {
+            return (self == Self::Desktop).then(|| home.join("Desktop"));
+        };
+        let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+        let path = match value.strip_prefix("$HOME") {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                home.join(rest.trim_start_matches('/'))
+            }
+            Some(_) => return None,
+            None => PathBuf::from(value),
+        };
+        (path.is_absolute() && path != home).then_some(path)
+    }
+}
+
+impl Display for UserDir {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Desktop => "desktop",
+            Self::Documents => "documents",
+            Self::Download => "download",
+            Self::Music => "music",
+            Self::Pictures => "pictures",
+            Self::PublicShare => "publicshare",
+            Self::Templates => "templates",
+            Self::Videos => "videos",
+        }
+        .fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn parses_dirs() {
+        const DIRS: &str = r#"
+# This file is written by xdg-user-dirs-update
+XDG_DESKTOP_DIR="$HOME/Bureau"
+XDG_DOCUMENTS_DIR="$HOME/Documents"
+XDG_DOWNLOAD_DIR="/mnt/downloads"
+XDG_MUSIC_DIR="$HOME/"
+XDG_PICTURES_DIR=$HOME/Pictures
+XDG_VIDEOS_DIR="$HOMEVideos"
+XDG_DOCUMENTS_DIR="$HOME/My \"Docs\""
+"#;
+
+        let home = Path::new("/home/user");
+        let parse = |dir: UserDir| dir.parse(DIRS, home);
+        assert_eq!(parse(UserDir::Desktop), Some(home.join("Bureau")));
+        assert_eq!(parse(UserDir::Documents), Some(home.join("My \"Docs\"")));
+        assert_eq!(parse(UserDir::Download), Some("/mnt/downloads".into()));
+        assert_eq!(parse(UserDir::Music), None);
+        assert_eq!(parse(UserDir::Pictures), None);
+        assert_eq!(parse(UserDir::Templates), None);
+        assert_eq!(parse(UserDir::Videos), None);
+    }
+
+    #[test]
+    fn defaults_desktop() {
+        let home = Path::new("/home/user");
+        assert_eq!(UserDir::Desktop.parse("", home), Some(home.join("Desktop")));
+        assert_eq!(UserDir::Documents.parse("", home), None);
+    }
+}
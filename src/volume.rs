@@ -0,0 +1,469 @@
+//! Filesystem classification and capacity.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{owner, Dir, DirSet};
+
+/// Variable listing alternative cache locations, in order of preference.
+const CACHE_FALLBACK: &str = "XDIR_CACHE_FALLBACK";
+
+/// Variable setting the local scratch location.
+const SCRATCH: &str = "XDIR_SCRATCH";
+
+/// Directories redirected off of network filesystems.
+static NETWORK_REDIRECT: AtomicU8 = AtomicU8::new(DirSet::EMPTY.bits());
+
+/// Kind of filesystem on which a path resides.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum FsKind {
+    /// Local disk.
+    Local,
+    /// Memory-backed filesystem (e.g. tmpfs, or a RAM disk), whose contents do
+    /// not survive a reboot.
+    Memory,
+    /// Network filesystem (e.g. NFS, or SMB/CIFS).
+    Network,
+}
+
+impl FsKind {
+    /// Checks whether this is a network filesystem.
+    ///
+    /// Network filesystems are typically slow, and may not support memory
+    /// mapping or locking reliably.
+    #[must_use]
+    pub fn is_network(self) -> bool {
+        self == Self::Network
+    }
+}
+
+/// Classifies the filesystem on which a path resides.
+///
+/// This allows applications to decide where to place their files, such as by
+/// relocating caches off of a network home directory. Filesystems which are not
+/// recognized as either memory-backed or networked are considered local.
+///
+/// # Errors
+///
+/// Errors if the path could not be accessed, or if filesystems cannot be
+/// classified on this platform.
+///
+/// # Examples
+///
+/// ```no_run
+/// if let Some(cache) = xdir::cache() {
+///     if xdir::fs_kind(&cache).unwrap().is_network() {
+///         eprintln!("warning: cache is on a network filesystem");
+///     }
+/// }
+/// ```
+pub fn fs_kind(path: impl AsRef<Path>) -> io::Result<FsKind> {
+    classify(path.as_ref())
+}
+
+/// Returns the space available to the user on the filesystem of one of their
+/// directories, in bytes.
+///
+/// Directories need not exist yet, in which case the space is measured at their
+/// nearest existing ancestor.
+///
+/// # Errors
+///
+/// Errors if the directory could not be found or accessed, or if space cannot
+/// be measured on this platform.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xdir::Dir;
+///
+/// let free = xdir::free_space(Dir::Cache).unwrap();
+/// println!("{} MiB free", free / 1024 / 1024);
+/// ```
+pub fn free_space(dir: Dir) -> io::Result<u64> {
+    let path = dir.path().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find {dir} directory"),
+        )
+    })?;
+    available(&path)
+}
+
+/// Chooses a cache location with at least the required space available.
+///
+/// The user's cache directory is preferred. Should its filesystem lack space,
+/// the alternatives listed in `$XDIR_CACHE_FALLBACK` (a search path, e.g.
+/// `/scratch/cache:/mnt/big/cache`) are tried in order. Where space cannot be
+/// measured on this platform, the cache directory is chosen as-is.
+///
+/// # Errors
+///
+/// Errors if the cache directory could not be found, or if no location has
+/// the required space available.
+///
+/// # Examples
+///
+/// ```no_run
+/// let dir = xdir::choose_cache_location(8 << 30).unwrap();
+/// let path = dir.join("myapp").join("model.bin");
+/// ```
+pub fn choose_cache_location(required: u64) -> io::Result<PathBuf> {
+    let primary = crate::cache()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find cache directory"))?;
+    let fallback = env::var_os(CACHE_FALLBACK)
+        .map(|value| crate::parse_search_path(&value))
+        .unwrap_or_default();
+    for path in std::iter::once(primary).chain(fallback) {
+        match available(&path) {
+            Ok(free) if free >= required => return Ok(path),
+            Err(err) if err.kind() == io::ErrorKind::Unsupported => return Ok(path),
+            _ => (),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::StorageFull,
+        format!("no cache location has {required} bytes available"),
+    ))
+}
+
+/// Sets which directories are redirected to local scratch space when their
+/// default location is on a network filesystem.
+///
+/// Home directories mounted over NFS or SMB make for slow caches. When
+/// enabled, a redirected directory which would otherwise default to a location
+/// on a [network filesystem](FsKind::Network) is instead placed within local
+/// scratch space, leaving the other directories within the home directory.
+/// Only the cache and state directories may be redirected, and only from their
+/// default location, such that those set explicitly through the environment or
+/// the override file are respected. Redirected directories are reported as
+/// [`Source::Redirected`](crate::Source::Redirected). This is disabled by
+/// default.
+///
+/// Scratch space is taken from `$XDIR_SCRATCH`, or is otherwise `/var/tmp/$USER`
+/// on Unix, and the user's temporary directory elsewhere. Redirected
+/// directories are named by their kind within it (e.g. `/var/tmp/me/cache`).
+/// Scratch space is created if it does not exist, such that only the user may
+/// access it. Should it be on a network filesystem itself, be owned by another
+/// user, or be accessible by others, directories are not redirected to it.
+///
+/// # Examples
+///
+/// ```
+/// use xdir::DirSet;
+///
+/// xdir::set_network_redirect(DirSet::CACHE);
+/// if let Some(cache) = xdir::cache_resolved() {
+///     println!("caching into {cache}");
+/// }
+/// # xdir::set_network_redirect(DirSet::EMPTY);
+/// ```
+pub fn set_network_redirect(dirs: DirSet) {
+    let dirs = dirs & (DirSet::CACHE | DirSet::STATE);
+    NETWORK_REDIRECT.store(dirs.bits(), Ordering::Relaxed);
+}
+
+/// Returns the location within scratch space to which a directory's default
+/// location is redirected, if any.
+pub(crate) fn redirect(dir: Dir, path: &Path) -> Option<PathBuf> {
+    if !DirSet::from_bits(NETWORK_REDIRECT.load(Ordering::Relaxed)).contains(dir) {
+        return None;
+    }
+    let is_network = |path: &Path| {
+        path.ancestors()
+            .find(|path| path.exists())
+            .is_some_and(|path| classify(path).is_ok_and(FsKind::is_network))
+    };
+    if !is_network(path) {
+        return None;
+    }
+    let scratch = scratch().filter(|path| !is_network(path))?;
+    prepare(&scratch).ok()?;
+    Some(scratch.join(dir.to_string()))
+}
+
+/// Returns the local scratch location of the user.
+fn scratch() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(SCRATCH)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+    {
+        return Some(path);
+    }
+    let user = ["USER", "LOGNAME", "USERNAME"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .find(|user| {
+            let mut parts = Path::new(user).components();
+            matches!(parts.next(), Some(Component::Normal(_))) && parts.next().is_none()
+        });
+    #[cfg(unix)]
+    let user = user.or_else(|| Some(crate::sys::euid().to_string().into()));
+    #[cfg(unix)]
+    let root = PathBuf::from("/var/tmp");
+    #[cfg(not(unix))]
+    let root = env::temp_dir();
+    Some(root.join(user?))
+}
+
+/// Creates the scratch location, such that only the user may access it.
+///
+/// As scratch space is typically within a directory shared by all users (e.g.
+/// `/var/tmp`), another user may have created it first. It is therefore only
+/// accepted if it is a directory owned by the user and inaccessible to others.
+fn prepare(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path).is_err() {
+        crate::readonly::check()?;
+        create_private(path)?;
+    }
+    owner::verify(path)?;
+    if is_private(path)? {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is accessible by other users", path.display()),
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn create_private(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)
+}
+
+/// Checks whether a directory is only accessible by its owner.
+#[cfg(unix)]
+fn is_private(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = fs::symlink_metadata(path)?;
+    Ok(meta.is_dir() && meta.mode() & 0o777 == 0o700)
+}
+
+#[cfg(not(unix))]
+fn is_private(path: &Path) -> io::Result<bool> {
+    Ok(fs::symlink_metadata(path)?.is_dir())
+}
+
+/// Returns the space available at a path (or its nearest existing ancestor).
+fn available(path: &Path) -> io::Result<u64> {
+    let path = path
+        .ancestors()
+        .find(|path| path.exists())
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+    free(path)
+}
+
+#[cfg(unix)]
+fn free(path: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: The path is NUL-terminated, and the buffer is large enough.
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: Initialized by the successful call above.
+    let stat = unsafe { stat.assume_init() };
+    #[allow(clippy::unnecessary_cast, clippy::useless_conversion)]
+    Ok(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+}
+
+#[cfg(windows)]
+fn free(path: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<_>>();
+    let mut free = 0;
+    // SAFETY: The path is NUL-terminated, and the result is written to a
+    // valid pointer.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &raw mut free,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(free)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn free(_: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "free space cannot be measured on this platform",
+    ))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn classify(path: &Path) -> io::Result<FsKind> {
+    // Magic numbers, as reported by statfs(2).
+    const TMPFS: u32 = 0x0102_1994;
+    const RAMFS: u32 = 0x8584_58f6;
+    const NFS: u32 = 0x6969;
+    const SMB: u32 = 0x517b;
+    const SMB2: u32 = 0xfe53_4d42;
+    const CIFS: u32 = 0xff53_4d42;
+    const AFS: u32 = 0x5346_414f;
+    const CEPH: u32 = 0x00c3_6400;
+    const CODA: u32 = 0x7375_7245;
+
+    let stat = statfs(path)?;
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::unnecessary_cast
+    )]
+    let magic = stat.f_type as u32;
+    Ok(match magic {
+        TMPFS | RAMFS => FsKind::Memory,
+        NFS | SMB | SMB2 | CIFS | AFS | CEPH | CODA => FsKind::Network,
+        _ => FsKind::Local,
+    })
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd"
+))]
+fn classify(path: &Path) -> io::Result<FsKind> {
+    let stat = statfs(path)?;
+    // SAFETY: The name is NUL-terminated by statfs(2).
+    let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    Ok(match name.to_bytes() {
+        b"tmpfs" | b"mfs" => FsKind::Memory,
+        b"nfs" | b"smbfs" | b"afpfs" | b"webdav" | b"cifs" => FsKind::Network,
+        _ => FsKind::Local,
+    })
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd"
+))]
+fn statfs(path: &Path) -> io::Result<libc::statfs> {
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut stat = MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: The path is NUL-terminated, and the buffer is large enough.
+    if unsafe { libc::statfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: Initialized by the successful call above.
+    Ok(unsafe { stat.assume_init() })
+}
+
+#[cfg(windows)]
+fn classify(path: &Path) -> io::Result<FsKind> {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Storage::FileSystem::{GetDriveTypeW, GetVolumePathNameW};
+    use windows_sys::Win32::System::WindowsProgramming::{DRIVE_RAMDISK, DRIVE_REMOTE};
+
+    if crate::windows::is_unc(path) {
+        return Ok(FsKind::Network);
+    }
+    let path = std::path::absolute(path)?;
+    path.metadata()?;
+    let wide = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<_>>();
+    let mut root = [0; 261];
+    // SAFETY: The path is NUL-terminated, and the buffer's length is given.
+    #[allow(clippy::cast_possible_truncation)]
+    if unsafe { GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: The root is NUL-terminated by the call above.
+    Ok(match unsafe { GetDriveTypeW(root.as_ptr()) } {
+        DRIVE_REMOTE => FsKind::Network,
+        DRIVE_RAMDISK => FsKind::Memory,
+        _ => FsKind::Local,
+    })
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    windows
+)))]
+fn classify(_: &Path) -> io::Result<FsKind> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "filesystems cannot be classified on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn prepare_creates_private_scratch() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = TempDir::new();
+        let scratch = tmp.join("scratch");
+        prepare(&scratch).unwrap();
+        assert!(is_private(&scratch).unwrap());
+        fs::set_permissions(&scratch, fs::Permissions::from_mode(0o777)).unwrap();
+        let err = prepare(&scratch).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn prepare_rejects_files() {
+        let tmp = TempDir::new();
+        let scratch = tmp.join("scratch");
+        fs::write(&scratch, b"").unwrap();
+        assert!(prepare(&scratch).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! JavaScript bindings.
+//!
+//! Exposes directory resolution to JavaScript through `wasm-bindgen`, such
+//! that tooling running on Node or Electron computes the same paths as Rust
+//! code. As there is no process environment within WebAssembly, each function
+//! takes the environment to resolve from as an object mapping variable names
+//! to their values (e.g. `process.env`), as per [`Env`]. Variables which are
+//! not strings are ignored. The bindings are built with:
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target nodejs --out-dir pkg target/wasm32-unknown-unknown/release/xdir.wasm
+//! ```
+//!
+//! Paths are interpreted following the conventions of Unix, as on all
+//! WebAssembly targets.
+//!
+//! # Examples
+//!
+//! ```js
+//! const xdir = require("xdir");
+//!
+//! const config = xdir.config(process.env);
+//! const state = xdir.dir(process.env, "state");
+//! ```
+
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{Dir, Env};
+
+/// Returns the path to the user's executable directory.
+#[must_use]
+#[wasm_bindgen]
+pub fn bin(env: &Object) -> Option<String> {
+    path(env, Dir::Bin)
+}
+
+/// Returns the path to the user's cache directory.
+#[must_use]
+#[wasm_bindgen]
+pub fn cache(env: &Object) -> Option<String> {
+    path(env, Dir::Cache)
+}
+
+/// Returns the path to the user's config directory.
+#[must_use]
+#[wasm_bindgen]
+pub fn config(env: &Object) -> Option<String> {
+    path(env, Dir::Config)
+}
+
+/// Returns the path to the user's data directory.
+#[must_use]
+#[wasm_bindgen]
+pub fn data(env: &Object) -> Option<String> {
+    path(env, Dir::Data)
+}
+
+/// Returns the path to the user's runtime directory.
+#[must_use]
+#[wasm_bindgen]
+pub fn runtime(env: &Object) -> Option<String> {
+    path(env, Dir::Runtime)
+}
+
+/// Returns the path to the user's state directory.
+#[must_use]
+#[wasm_bindgen]
+pub fn state(env: &Object) -> Option<String> {
+    path(env, Dir::State)
+}
+
+/// Returns the path to the user's home directory.
+#[must_use]
+#[wasm_bindgen]
+pub fn home(env: &Object) -> Option<String> {
+    vars(env).home()?.into_os_string().into_string().ok()
+}
+
+/// Returns the path to a directory, given by its name (e.g. `"config"`).
+///
+/// Returns `undefined` if the directory is unknown or could not be found.
+#[must_use]
+#[wasm_bindgen]
+pub fn dir(env: &Object, name: &str) -> Option<String> {
+    let dir = Dir::ALL.into_iter().find(|dir| dir.to_string() == name)?;
+    path(env, dir)
+}
+
+/// Resolves a directory within an environment.
+fn path(env: &Object, dir: Dir) -> Option<String> {
+    vars(env).path(dir)?.into_os_string().into_string().ok()
+}
+
+/// Converts an object of variables into an environment.
+fn vars(env: &Object) -> Env {
+    Env::from_vars(Object::entries(env).iter().filter_map(|entry| {
+        let entry = Array::from(&entry);
+        Some((entry.get(0).as_string()?, entry.get(1).as_string()?))
+    }))
+}
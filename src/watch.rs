@@ -0,0 +1,186 @@
+//! Watching of directories.
+//!
+//! This module requires the `notify` feature, and uses the [`notify`] crate to
+//! [watch] directories for changes. Watches survive the directory being
+//! created, removed, or recreated, making them suitable for reloading
+//! configuration at runtime.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::Dir;
+//!
+//! let watcher = xdir::watch(Dir::Config, "myapp", |event| {
+//!     if let Ok(event) = event {
+//!         println!("changed: {:?}", event.paths);
+//!     }
+//! })
+//! .unwrap();
+//! // Changes are reported until the watcher is dropped.
+//! drop(watcher);
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+pub use notify;
+use notify::event::{CreateKind, EventKind};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::Dir;
+
+/// A watch on a directory.
+///
+/// Dropping the watcher stops the watch.
+#[derive(Debug)]
+pub struct Watcher {
+    path: PathBuf,
+    tx: Sender<Message>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Returns the watched path.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Message::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+enum Message {
+    Event(notify::Result<Event>),
+    Stop,
+}
+
+/// Recursively watches a path within a directory, invoking the callback for
+/// each change.
+///
+/// The path need not exist when the watch is started; its creation will be
+/// reported as a change, after which its contents are watched.
+///
+/// # Errors
+///
+/// Errors if the directory could not be found, or if the watch could not be
+/// started.
+pub fn watch<P, F>(dir: Dir, path: P, callback: F) -> io::Result<Watcher>
+where
+    P: AsRef<Path>,
+    F: FnMut(notify::Result<Event>) + Send + 'static,
+{
+    let path = dir
+        .path()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("could not find {dir} directory"),
+            )
+        })?
+        .join(path);
+    watch_path(path, callback)
+}
+
+/// Recursively watches an arbitrary path, invoking the callback for each
+/// change.
+///
+/// The path may be either a directory or a file.
+///
+/// # Errors
+///
+/// Errors if the watch could not be started.
+pub fn watch_path<F>(path: PathBuf, mut callback: F) -> io::Result<Watcher>
+where
+    F: FnMut(notify::Result<Event>) + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    let events = tx.clone();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = events.send(Message::Event(event));
+    })
+    .map_err(io::Error::other)?;
+    let mut state = State {
+        target: path.clone(),
+        watching: false,
+        anchor: None,
+    };
+    state.rearm(&mut watcher).map_err(io::Error::other)?;
+
+    let thread = thread::spawn(move || {
+        while let Ok(Message::Event(event)) = rx.recv() {
+            match event {
+                Ok(event) => {
+                    let relevant = event
+                        .paths
+                        .iter()
+                        .any(|path| path.starts_with(&state.target));
+                    let created = !state.watching;
+                    if let Err(err) = state.rearm(&mut watcher) {
+                        callback(Err(err));
+                    }
+                    if relevant {
+                        callback(Ok(event));
+                    } else if created && state.watching {
+                        // Creation of the target may have been observed only
+                        // indirectly, through the creation of an ancestor.
+                        let kind = EventKind::Create(CreateKind::Folder);
+                        callback(Ok(Event::new(kind).add_path(state.target.clone())));
+                    }
+                }
+                Err(err) => callback(Err(err)),
+            }
+        }
+    });
+    Ok(Watcher {
+        path,
+        tx,
+        thread: Some(thread),
+    })
+}
+
+/// Watches held on the target and its nearest existing ancestor.
+struct State {
+    target: PathBuf,
+    watching: bool,
+    anchor: Option<PathBuf>,
+}
+
+impl State {
+    /// Updates the watches to reflect the current state of the filesystem.
+    fn rearm(&mut self, watcher: &mut RecommendedWatcher) -> notify::Result<()> {
+        let exists = self.target.is_dir();
+        if exists && !self.watching {
+            watcher.watch(&self.target, RecursiveMode::Recursive)?;
+        } else if !exists && self.watching {
+            let _ = watcher.unwatch(&self.target);
+        }
+        self.watching = exists;
+
+        // Watch the nearest existing ancestor to detect (re)creation.
+        let anchor = self
+            .target
+            .ancestors()
+            .skip(1)
+            .find(|path| path.is_dir())
+            .map(Path::to_path_buf);
+        if anchor != self.anchor {
+            if let Some(old) = self.anchor.take() {
+                let _ = watcher.unwatch(&old);
+            }
+            if let Some(new) = &anchor {
+                watcher.watch(new, RecursiveMode::NonRecursive)?;
+            }
+            self.anchor = anchor;
+        }
+        Ok(())
+    }
+}
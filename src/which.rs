@@ -0,0 +1,85 @@
+//! Executable lookup.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default executable extensions on Windows, if `$PATHEXT` is unset.
+const PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Finds all executables of the given name, in order of preference.
+///
+/// The user's [executable directory](crate::bin) is searched first, followed by
+/// each directory of `$PATH`, such that user-installed executables
+/// consistently shadow those of the system. On Unix, only files with an
+/// executable bit set are matched; on Windows, each extension of `$PATHEXT` is
+/// tried in turn.
+///
+/// Names containing a path separator are not searched for, and match only
+/// themselves.
+///
+/// # Examples
+///
+/// ```no_run
+/// if let Some(tool) = xdir::which("tool").first() {
+///     println!("found: {}", tool.display());
+/// }
+/// ```
+#[must_use]
+pub fn which(name: impl AsRef<Path>) -> Vec<PathBuf> {
+    let name = name.as_ref();
+    if name.components().count() != 1 {
+        return candidates(name)
+            .into_iter()
+            .filter(|path| is_executable(path))
+            .collect();
+    }
+    let path = env::var_os("PATH").unwrap_or_default();
+    let mut dirs = Vec::new();
+    for dir in crate::bin().into_iter().chain(env::split_paths(&path)) {
+        if !dir.as_os_str().is_empty() && !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs.iter()
+        .flat_map(|dir| candidates(&dir.join(name)))
+        .filter(|path| is_executable(path))
+        .collect()
+}
+
+/// Returns the candidate files of an executable.
+fn candidates(path: &Path) -> Vec<PathBuf> {
+    if cfg!(windows) {
+        let exts = env::var("PATHEXT").unwrap_or_else(|_| PATHEXT.to_string());
+        let mut paths = Vec::new();
+        if path.extension().is_some() {
+            paths.push(path.to_path_buf());
+        }
+        for ext in exts.split(';').filter(|ext| !ext.is_empty()) {
+            let mut file = OsString::from(path);
+            file.push(ext);
+            paths.push(file.into());
+        }
+        paths
+    } else {
+        vec![path.to_path_buf()]
+    }
+}
+
+/// Checks whether a file is executable.
+fn is_executable(path: &Path) -> bool {
+    let Ok(meta) = fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        meta.is_file() && meta.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        meta.is_file()
+    }
+}
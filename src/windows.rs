@@ -0,0 +1,69 @@
+//! Windows path forms.
+//!
+//! Most Windows APIs limit paths to `MAX_PATH` (260) characters, which deeply
+//! nested application directories can exceed, particularly within roaming
+//! profiles on network shares (e.g. `\\server\share\me`). Extended-length
+//! paths (prefixed with `\\?\`) lift this limit, but are taken literally,
+//! such that they must be absolute and use only backslashes.
+//!
+//! On other platforms, paths are returned unchanged.
+
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf, Prefix};
+
+/// Converts an absolute path into its extended-length form (e.g.
+/// `\\?\C:\Users\me` or `\\?\UNC\server\share\me`).
+///
+/// As extended-length paths are not normalized by Windows, `.` and `..`
+/// segments are resolved lexically. Relative paths, and those already in
+/// extended-length or device form, are returned unchanged.
+#[must_use]
+pub fn extended(path: &Path) -> PathBuf {
+    let mut components = path.components();
+    let mut out = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) if path.has_root() => {
+                let mut out = OsString::from(r"\\?\");
+                out.push(prefix.as_os_str());
+                out
+            }
+            Prefix::UNC(server, share) => {
+                let mut out = OsString::from(r"\\?\UNC\");
+                out.push(server);
+                out.push(r"\");
+                out.push(share);
+                out
+            }
+            // Drive-relative paths (e.g. `C:foo`) cannot be made extended-length.
+            _ => return path.to_path_buf(),
+        },
+        _ => return path.to_path_buf(),
+    };
+    let mut parts = Vec::new();
+    for part in components {
+        match part {
+            Component::Normal(part) => parts.push(part),
+            Component::ParentDir => drop(parts.pop()),
+            _ => (),
+        }
+    }
+    if parts.is_empty() {
+        out.push(r"\");
+    }
+    for part in parts {
+        out.push(r"\");
+        out.push(part);
+    }
+    PathBuf::from(out)
+}
+
+/// Checks whether a path is on a network share (e.g. `\\server\share`).
+#[cfg(any(feature = "windows", all(windows, feature = "volume")))]
+#[must_use]
+pub fn is_unc(path: &Path) -> bool {
+    matches!(
+        path.components().next(),
+        Some(Component::Prefix(prefix))
+            if matches!(prefix.kind(), Prefix::UNC(..) | Prefix::VerbatimUNC(..))
+    )
+}
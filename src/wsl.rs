@@ -0,0 +1,185 @@
+//! Windows Subsystem for Linux.
+//!
+//! Within WSL, the Windows side of the machine is reachable through drives
+//! mounted at `/mnt/<drive>`, while the Linux side is exposed to Windows at
+//! `\\wsl.localhost\<distro>`. This module detects whether the process runs
+//! within WSL, and translates paths between both forms (as per `wslpath`),
+//! allowing tools to share configuration with their Windows counterparts.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xdir::wsl;
+//!
+//! if wsl::is_wsl() {
+//!     let home = wsl::windows_home();
+//!     println!("windows home: {home:?}");
+//! }
+//! ```
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Default mount point of Windows drives.
+const MOUNT_ROOT: &str = "/mnt/";
+
+/// Version of WSL.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Version {
+    /// WSL 1, which translates Linux system calls.
+    Wsl1,
+    /// WSL 2, which runs a Linux kernel within a virtual machine.
+    Wsl2,
+}
+
+/// Detects the version of WSL the process is running within, if any.
+#[must_use]
+pub fn detect() -> Option<Version> {
+    let release = fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    if release.contains("WSL2") || release.contains("microsoft-standard") {
+        Some(Version::Wsl2)
+    } else if release.to_lowercase().contains("microsoft") {
+        Some(Version::Wsl1)
+    } else {
+        None
+    }
+}
+
+/// Checks whether the process is running within WSL.
+#[must_use]
+pub fn is_wsl() -> bool {
+    detect().is_some()
+}
+
+/// Returns the mount point of Windows drives.
+///
+/// This is configured by `root` within the `[automount]` section of
+/// `/etc/wsl.conf`, defaulting to `/mnt/`.
+#[must_use]
+pub fn mount_root() -> PathBuf {
+    let root = fs::read_to_string("/etc/wsl.conf").ok().and_then(|conf| {
+        let mut automount = false;
+        conf.lines().map(str::trim).find_map(|line| {
+            if line.starts_with('[') {
+                automount = line.eq_ignore_ascii_case("[automount]");
+                return None;
+            }
+            let (key, value) = line.split_once('=')?;
+            (automount && key.trim() == "root").then(|| value.trim().trim_matches('"').to_string())
+        })
+    });
+    PathBuf::from(root.unwrap_or_else(|| MOUNT_ROOT.to_string()))
+}
+
+/// Translates a Linux path into its Windows form.
+///
+/// Paths on a mounted drive (e.g. `/mnt/c/Users`) are translated to that drive
+/// (`C:\Users`), while all others are translated to the distribution's network
+/// share (e.g. `\\wsl.localhost\Ubuntu\home`). Returns [`None`] if the path is
+/// relative, or if the distribution could not be determined.
+#[must_use]
+pub fn to_windows(path: &Path) -> Option<String> {
+    let text = path.to_str()?;
+    if !text.starts_with('/') {
+        return None;
+    }
+    if let Ok(rest) = path.strip_prefix(mount_root()) {
+        let mut parts = rest.iter().filter_map(|part| part.to_str());
+        if let Some(drive) = parts.next().filter(|drive| is_drive(drive)) {
+            let rest = parts.collect::<Vec<_>>().join("\\");
+            return Some(format!("{}:\\{rest}", drive.to_ascii_uppercase()));
+        }
+    }
+    let distro = env::var("WSL_DISTRO_NAME").ok()?;
+    Some(format!(
+        "\\\\wsl.localhost\\{distro}{}",
+        text.replace('/', "\\")
+    ))
+}
+
+/// Translates a Windows path into its Linux form.
+///
+/// Both drive paths (e.g. `C:\Users`) and paths within the distribution's
+/// network share (e.g. `\\wsl.localhost\Ubuntu\home`) are recognized, with
+/// either separator. Returns [`None`] for any other path.
+#[must_use]
+pub fn to_linux(path: &str) -> Option<PathBuf> {
+    if let Some(share) = path.strip_prefix(r"\\").or_else(|| path.strip_prefix("//")) {
+        let mut parts = share.split(['\\', '/']).filter(|part| !part.is_empty());
+        let host = parts.next()?;
+        if !(host.eq_ignore_ascii_case("wsl.localhost") || host.eq_ignore_ascii_case("wsl$")) {
+            return None;
+        }
+        let distro = parts.next()?;
+        if env::var("WSL_DISTRO_NAME").is_ok_and(|name| !name.eq_ignore_ascii_case(distro)) {
+            return None;
+        }
+        return Some(parts.fold(PathBuf::from("/"), |path, part| path.join(part)));
+    }
+    let (drive, rest) = path.split_once(':')?;
+    if !is_drive(drive) || !(rest.is_empty() || rest.starts_with(['\\', '/'])) {
+        return None;
+    }
+    let root = mount_root().join(drive.to_ascii_lowercase());
+    Some(
+        rest.split(['\\', '/'])
+            .filter(|part| !part.is_empty())
+            .fold(root, |path, part| path.join(part)),
+    )
+}
+
+/// Returns the Linux path to the Windows user's profile directory (e.g.
+/// `/mnt/c/Users/me`).
+#[must_use]
+pub fn windows_home() -> Option<PathBuf> {
+    windows_path("USERPROFILE")
+}
+
+/// Returns the Linux path to the Windows user's roaming application data
+/// directory (e.g. `/mnt/c/Users/me/AppData/Roaming`).
+#[must_use]
+pub fn windows_config() -> Option<PathBuf> {
+    windows_path("APPDATA")
+}
+
+/// Resolves a path held by a Windows environment variable.
+///
+/// Variables shared through `WSLENV` are used directly, and are otherwise
+/// queried from `cmd.exe`.
+fn windows_path(var: &str) -> Option<PathBuf> {
+    if !is_wsl() {
+        return None;
+    }
+    if let Some(value) = env::var_os(var).filter(|value| !value.is_empty()) {
+        let path = PathBuf::from(&value);
+        // Variables shared with the `/p` flag are translated already.
+        return if path.is_absolute() {
+            Some(path)
+        } else {
+            to_linux(value.to_str()?)
+        };
+    }
+    let output = Command::new("cmd.exe")
+        .args(["/D", "/C", &format!("echo %{var}%")])
+        // Avoid warnings from starting within the distribution's network share.
+        .current_dir(mount_root().join("c"))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let value = String::from_utf8_lossy(&output.stdout);
+    let value = value.trim();
+    // Unset variables are echoed verbatim.
+    if value.is_empty() || value == format!("%{var}%") {
+        return None;
+    }
+    to_linux(value)
+}
+
+/// Checks whether a path component names a drive.
+fn is_drive(part: &str) -> bool {
+    part.len() == 1 && part.bytes().all(|byte| byte.is_ascii_alphabetic())
+}
@@ -0,0 +1,597 @@
+//! Compatibility with the [`xdg`][xdg] crate.
+//!
+//! This module provides [`BaseDirectories`], a drop-in replacement for the
+//! type of the same name from the `xdg` crate, implemented on top of `xdir`'s
+//! directory resolution. Unlike the original, it is available on all
+//! platforms.
+//!
+//! # Examples
+//!
+//! Switching over only requires changing the import:
+//!
+//! ```no_run
+//! use xdir::xdg::BaseDirectories;
+//!
+//! let dirs = BaseDirectories::with_prefix("myapp").unwrap();
+//! let path = dirs.place_config_file("config.toml").unwrap();
+//! ```
+//!
+//! [xdg]: https://docs.rs/xdg
+
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::{io, vec};
+
+use crate::{search, sys, Dir, Dirs};
+
+/// Base directories of a (possibly prefixed) application.
+///
+/// User directories are always resolved; it is an error to construct this type
+/// without a detectable home directory.
+#[derive(Clone, Debug)]
+pub struct BaseDirectories {
+    shared_prefix: PathBuf,
+    user_prefix: PathBuf,
+    data_home: PathBuf,
+    config_home: PathBuf,
+    cache_home: PathBuf,
+    state_home: PathBuf,
+    data_dirs: Vec<PathBuf>,
+    config_dirs: Vec<PathBuf>,
+    runtime_dir: Option<PathBuf>,
+}
+
+impl BaseDirectories {
+    /// Constructs base directories without a prefix.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the home directory could not be found.
+    pub fn new() -> Result<Self, BaseDirectoriesError> {
+        Self::with_profile("", "")
+    }
+
+    /// Constructs base directories with a prefix.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the home directory could not be found.
+    pub fn with_prefix<P: AsRef<Path>>(prefix: P) -> Result<Self, BaseDirectoriesError> {
+        Self::with_profile(prefix, "")
+    }
+
+    /// Constructs base directories with a prefix and profile.
+    ///
+    /// The profile is only applied to user directories, allowing system-wide
+    /// files to be shared between profiles.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the home directory could not be found.
+    pub fn with_profile<P, Q>(prefix: P, profile: Q) -> Result<Self, BaseDirectoriesError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        Self::with_dirs(&crate::resolve_all(), prefix.as_ref(), profile.as_ref())
+    }
+
+    /// Constructs base directories with a prefix and profile from resolved
+    /// directories.
+    fn with_dirs(dirs: &Dirs, prefix: &Path, profile: &Path) -> Result<Self, BaseDirectoriesError> {
+        let get = |dir| {
+            dirs.get(dir)
+                .map(Path::to_path_buf)
+                .ok_or(BaseDirectoriesError::HomeMissing)
+        };
+        Ok(Self {
+            shared_prefix: prefix.to_path_buf(),
+            user_prefix: prefix.join(profile),
+            data_home: get(Dir::Data)?,
+            config_home: get(Dir::Config)?,
+            cache_home: get(Dir::Cache)?,
+            state_home: get(Dir::State)?,
+            data_dirs: dirs.data_dirs().to_vec(),
+            config_dirs: dirs.config_dirs().to_vec(),
+            runtime_dir: dirs.get(Dir::Runtime).map(Path::to_path_buf),
+        })
+    }
+
+    /// Returns the user's (unprefixed) cache directory.
+    #[must_use]
+    pub fn get_cache_home(&self) -> PathBuf {
+        self.cache_home.clone()
+    }
+
+    /// Returns the user's (unprefixed) config directory.
+    #[must_use]
+    pub fn get_config_home(&self) -> PathBuf {
+        self.config_home.clone()
+    }
+
+    /// Returns the user's (unprefixed) data directory.
+    #[must_use]
+    pub fn get_data_home(&self) -> PathBuf {
+        self.data_home.clone()
+    }
+
+    /// Returns the user's (unprefixed) state directory.
+    #[must_use]
+    pub fn get_state_home(&self) -> PathBuf {
+        self.state_home.clone()
+    }
+
+    /// Returns the (unprefixed) system config directories.
+    #[must_use]
+    pub fn get_config_dirs(&self) -> Vec<PathBuf> {
+        self.config_dirs.clone()
+    }
+
+    /// Returns the (unprefixed) system data directories.
+    #[must_use]
+    pub fn get_data_dirs(&self) -> Vec<PathBuf> {
+        self.data_dirs.clone()
+    }
+
+    /// Returns the user's (unprefixed) runtime directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory is not set.
+    pub fn get_runtime_directory(&self) -> Result<&PathBuf, BaseDirectoriesError> {
+        self.runtime_dir
+            .as_ref()
+            .ok_or(BaseDirectoriesError::RuntimeMissing)
+    }
+
+    /// Checks whether the runtime directory is set.
+    #[must_use]
+    pub fn has_runtime_directory(&self) -> bool {
+        self.runtime_dir.is_some()
+    }
+
+    /// Returns the path of a cache file, without checking for its existence.
+    pub fn get_cache_file<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.cache_home.join(&self.user_prefix).join(path)
+    }
+
+    /// Returns the path of a config file, without checking for its existence.
+    pub fn get_config_file<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.config_home.join(&self.user_prefix).join(path)
+    }
+
+    /// Returns the path of a data file, without checking for its existence.
+    pub fn get_data_file<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.data_home.join(&self.user_prefix).join(path)
+    }
+
+    /// Returns the path of a state file, without checking for its existence.
+    pub fn get_state_file<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.state_home.join(&self.user_prefix).join(path)
+    }
+
+    /// Returns the path of a runtime file, without checking for its existence.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory is not set.
+    pub fn get_runtime_file<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        Ok(self.runtime_home()?.join(&self.user_prefix).join(path))
+    }
+
+    /// Returns the path of a cache file, creating its leading directories.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the leading directories could not be created.
+    pub fn place_cache_file<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        place(self.get_cache_file(path))
+    }
+
+    /// Returns the path of a config file, creating its leading directories.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the leading directories could not be created.
+    pub fn place_config_file<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        place(self.get_config_file(path))
+    }
+
+    /// Returns the path of a data file, creating its leading directories.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the leading directories could not be created.
+    pub fn place_data_file<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        place(self.get_data_file(path))
+    }
+
+    /// Returns the path of a state file, creating its leading directories.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the leading directories could not be created.
+    pub fn place_state_file<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        place(self.get_state_file(path))
+    }
+
+    /// Returns the path of a runtime file, creating its leading directories.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory is not set, or if the leading
+    /// directories could not be created.
+    pub fn place_runtime_file<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        place(self.get_runtime_file(path)?)
+    }
+
+    /// Finds a cache file.
+    pub fn find_cache_file<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.find_cache_files(path).next()
+    }
+
+    /// Finds a config file, in order of preference.
+    pub fn find_config_file<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.find_config_files(path).next()
+    }
+
+    /// Finds a data file, in order of preference.
+    pub fn find_data_file<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.find_data_files(path).next()
+    }
+
+    /// Finds a state file.
+    pub fn find_state_file<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.find_state_files(path).next()
+    }
+
+    /// Finds a runtime file.
+    pub fn find_runtime_file<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let path = self.get_runtime_file(path).ok()?;
+        path.exists().then_some(path)
+    }
+
+    /// Finds all existing cache files.
+    pub fn find_cache_files<P: AsRef<Path>>(&self, path: P) -> FileFindIterator {
+        FileFindIterator::new(self.search(&self.cache_home, &[]), path.as_ref())
+    }
+
+    /// Finds all existing config files, in order of preference.
+    pub fn find_config_files<P: AsRef<Path>>(&self, path: P) -> FileFindIterator {
+        FileFindIterator::new(
+            self.search(&self.config_home, &self.config_dirs),
+            path.as_ref(),
+        )
+    }
+
+    /// Finds all existing data files, in order of preference.
+    pub fn find_data_files<P: AsRef<Path>>(&self, path: P) -> FileFindIterator {
+        FileFindIterator::new(self.search(&self.data_home, &self.data_dirs), path.as_ref())
+    }
+
+    /// Finds all existing state files.
+    pub fn find_state_files<P: AsRef<Path>>(&self, path: P) -> FileFindIterator {
+        FileFindIterator::new(self.search(&self.state_home, &[]), path.as_ref())
+    }
+
+    /// Lists all files within a cache directory.
+    pub fn list_cache_files<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(&self.search(&self.cache_home, &[]), path.as_ref(), false)
+    }
+
+    /// Lists all files within a config directory, in order of preference.
+    pub fn list_config_files<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(
+            &self.search(&self.config_home, &self.config_dirs),
+            path.as_ref(),
+            false,
+        )
+    }
+
+    /// Lists all files within a data directory, in order of preference.
+    pub fn list_data_files<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(
+            &self.search(&self.data_home, &self.data_dirs),
+            path.as_ref(),
+            false,
+        )
+    }
+
+    /// Lists all files within a state directory.
+    pub fn list_state_files<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(&self.search(&self.state_home, &[]), path.as_ref(), false)
+    }
+
+    /// Lists all files within a cache directory, keeping only the first file
+    /// with a given name.
+    pub fn list_cache_files_once<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(&self.search(&self.cache_home, &[]), path.as_ref(), true)
+    }
+
+    /// Lists all files within a config directory, keeping only the most
+    /// preferred file with a given name.
+    pub fn list_config_files_once<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(
+            &self.search(&self.config_home, &self.config_dirs),
+            path.as_ref(),
+            true,
+        )
+    }
+
+    /// Lists all files within a data directory, keeping only the most
+    /// preferred file with a given name.
+    pub fn list_data_files_once<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(
+            &self.search(&self.data_home, &self.data_dirs),
+            path.as_ref(),
+            true,
+        )
+    }
+
+    /// Lists all files within a state directory, keeping only the first file
+    /// with a given name.
+    pub fn list_state_files_once<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        list(&self.search(&self.state_home, &[]), path.as_ref(), true)
+    }
+
+    /// Creates a cache directory, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be created.
+    pub fn create_cache_directory<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        create(self.get_cache_file(path))
+    }
+
+    /// Creates a config directory, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be created.
+    pub fn create_config_directory<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        create(self.get_config_file(path))
+    }
+
+    /// Creates a data directory, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be created.
+    pub fn create_data_directory<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        create(self.get_data_file(path))
+    }
+
+    /// Creates a state directory, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be created.
+    pub fn create_state_directory<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        create(self.get_state_file(path))
+    }
+
+    /// Creates a runtime directory, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the runtime directory is not set, or if the directory could
+    /// not be created.
+    pub fn create_runtime_directory<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        create(self.get_runtime_file(path)?)
+    }
+
+    /// Returns the prefixed search path for a user directory and its system
+    /// counterparts, in order of preference.
+    fn search(&self, home: &Path, dirs: &[PathBuf]) -> Vec<PathBuf> {
+        std::iter::once(home.join(&self.user_prefix))
+            .chain(dirs.iter().map(|dir| dir.join(&self.shared_prefix)))
+            .collect()
+    }
+
+    fn runtime_home(&self) -> io::Result<&PathBuf> {
+        self.get_runtime_directory()
+            .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))
+    }
+}
+
+/// An error constructing or querying base directories.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BaseDirectoriesError {
+    /// Home directory could not be found.
+    HomeMissing,
+    /// Runtime directory is not set.
+    RuntimeMissing,
+}
+
+impl Display for BaseDirectoriesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HomeMissing => write!(f, "could not find home directory"),
+            Self::RuntimeMissing => write!(f, "$XDG_RUNTIME_DIR is not set"),
+        }
+    }
+}
+
+impl Error for BaseDirectoriesError {}
+
+/// An iterator over existing files within a search path.
+#[derive(Debug)]
+pub struct FileFindIterator {
+    dirs: vec::IntoIter<PathBuf>,
+    path: PathBuf,
+}
+
+impl FileFindIterator {
+    fn new(dirs: Vec<PathBuf>, path: &Path) -> Self {
+        Self {
+            dirs: dirs.into_iter(),
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl Iterator for FileFindIterator {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.dirs
+            .by_ref()
+            .map(|dir| dir.join(&self.path))
+            .find(|path| path.exists())
+    }
+}
+
+fn place(path: PathBuf) -> io::Result<PathBuf> {
+    if let Some(parent) = path.parent() {
+        sys::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
+fn create(path: PathBuf) -> io::Result<PathBuf> {
+    sys::create_dir_all(&path)?;
+    Ok(path)
+}
+
+fn list(dirs: &[PathBuf], path: &Path, once: bool) -> Vec<PathBuf> {
+    let dirs = dirs.iter().map(|dir| dir.join(path)).collect::<Vec<_>>();
+    search::list(&dirs, once)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// Constructs base directories within a temporary home directory, with
+    /// system config directories `sys1` and `sys2`.
+    fn dirs(tmp: &Path, prefix: &str, profile: &str) -> BaseDirectories {
+        let mut dirs = BaseDirectories::with_dirs(
+            &Dirs::at(&tmp.join("home")),
+            prefix.as_ref(),
+            profile.as_ref(),
+        )
+        .unwrap();
+        dirs.config_dirs = vec![tmp.join("sys1"), tmp.join("sys2")];
+        dirs
+    }
+
+    #[test]
+    fn joins_prefix_and_profile() {
+        let tmp = TempDir::new();
+        let home = tmp.join("home");
+        let dirs = dirs(&tmp, "myapp", "work");
+        assert_eq!(dirs.get_config_home(), home.join(".config"));
+        assert_eq!(
+            dirs.get_config_file("config.toml"),
+            home.join(".config/myapp/work/config.toml")
+        );
+        assert_eq!(
+            dirs.get_cache_file("index"),
+            home.join(".cache/myapp/work/index")
+        );
+        // Only user directories are scoped by the profile.
+        assert_eq!(
+            dirs.search(&dirs.config_home, &dirs.config_dirs),
+            [
+                home.join(".config/myapp/work"),
+                tmp.join("sys1/myapp"),
+                tmp.join("sys2/myapp"),
+            ]
+        );
+        let dirs = self::dirs(&tmp, "", "");
+        assert_eq!(
+            dirs.get_config_file("config.toml"),
+            home.join(".config/config.toml")
+        );
+        assert!(!dirs.has_runtime_directory());
+        let err = dirs.get_runtime_file("socket").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn find_prefers_user_files() {
+        let tmp = TempDir::new();
+        let dirs = dirs(&tmp, "myapp", "work");
+        let user = dirs.get_config_file("config.toml");
+        let system = [
+            tmp.join("sys1/myapp/config.toml"),
+            tmp.join("sys2/myapp/config.toml"),
+        ];
+        assert_eq!(dirs.find_config_file("config.toml"), None);
+        fs::create_dir_all(tmp.join("sys2/myapp")).unwrap();
+        fs::write(&system[1], "").unwrap();
+        assert_eq!(
+            dirs.find_config_file("config.toml").as_ref(),
+            Some(&system[1])
+        );
+        fs::create_dir_all(tmp.join("sys1/myapp")).unwrap();
+        fs::write(&system[0], "").unwrap();
+        assert_eq!(
+            dirs.find_config_file("config.toml").as_ref(),
+            Some(&system[0])
+        );
+        dirs.place_config_file("config.toml").unwrap();
+        fs::write(&user, "").unwrap();
+        assert_eq!(dirs.find_config_file("config.toml").as_ref(), Some(&user));
+        assert_eq!(
+            dirs.find_config_files("config.toml").collect::<Vec<_>>(),
+            [user, system[0].clone(), system[1].clone()]
+        );
+    }
+
+    #[test]
+    fn list_orders_by_preference() {
+        let tmp = TempDir::new();
+        let dirs = dirs(&tmp, "myapp", "");
+        let user = dirs.create_config_directory("conf.d").unwrap();
+        let system = tmp.join("sys1/myapp/conf.d");
+        fs::create_dir_all(&system).unwrap();
+        for path in [user.join("a"), system.join("a"), system.join("b")] {
+            fs::write(path, "").unwrap();
+        }
+        assert_eq!(
+            dirs.list_config_files("conf.d"),
+            [user.join("a"), system.join("a"), system.join("b")]
+        );
+        assert_eq!(
+            dirs.list_config_files_once("conf.d"),
+            [user.join("a"), system.join("b")]
+        );
+    }
+
+    #[test]
+    fn place_creates_parents() {
+        let tmp = TempDir::new();
+        let dirs = dirs(&tmp, "myapp", "");
+        let path = dirs.place_data_file("db/library.db").unwrap();
+        assert_eq!(path, dirs.get_data_file("db/library.db"));
+        assert!(path.parent().unwrap().is_dir());
+        assert!(!path.exists());
+        let path = dirs.create_cache_directory("thumbs").unwrap();
+        assert!(path.is_dir());
+    }
+
+    #[test]
+    fn place_respects_read_only() {
+        let tmp = TempDir::new();
+        let dirs = dirs(&tmp, "myapp", "");
+        crate::readonly::set_thread_read_only(true);
+        let placed = dirs.place_state_file("logs/today.log");
+        let created = dirs.create_state_directory("history");
+        crate::readonly::set_thread_read_only(false);
+        assert_eq!(
+            placed.unwrap_err().kind(),
+            io::ErrorKind::ReadOnlyFilesystem
+        );
+        assert_eq!(
+            created.unwrap_err().kind(),
+            io::ErrorKind::ReadOnlyFilesystem
+        );
+        assert!(!dirs.get_state_home().exists());
+    }
+}